@@ -8,38 +8,23 @@
 //! 5. Properly handling the result
 
 use display_error_chain::DisplayErrorChain;
-use gemini_rust::{Batch, BatchHandleError, BatchStatus, Gemini, Message};
+use gemini_rust::{Batch, BatchHandleError, BatchOutcome, Gemini, Message, PollPolicy};
 use std::process::ExitCode;
 use std::{env, sync::Arc, time::Duration};
 use tokio::{signal, sync::Mutex};
 use tracing::{error, info, warn};
 
-/// Waits for the batch operation to complete by periodically polling its status.
+/// Waits for the batch operation to reach a terminal state, polling at a fixed delay.
 ///
-/// This method polls the batch status with a specified delay until the operation
-/// reaches a terminal state (Succeeded, Failed, Cancelled, or Expired).
-///
-/// Consumes the batch and returns the final status. If there's an error during polling,
+/// Consumes the batch and returns the outcome. If there's an error during polling,
 /// the batch is returned in the error variant so it can be retried.
 pub async fn wait_for_completion(
     batch: Batch,
     delay: Duration,
-) -> Result<BatchStatus, (Batch, BatchHandleError)> {
-    let batch_name = batch.name.clone();
-    loop {
-        match batch.status().await {
-            Ok(status) => match status {
-                BatchStatus::Succeeded { .. } | BatchStatus::Cancelled => return Ok(status),
-                BatchStatus::Expired => {
-                    return Err((batch, BatchHandleError::BatchExpired { name: batch_name }));
-                }
-                _ => tokio::time::sleep(delay).await,
-            },
-            Err(e) => match e {
-                BatchHandleError::BatchFailed { .. } => return Err((batch, e)),
-                _ => return Err((batch, e)), // Return the batch and error for retry
-            },
-        }
+) -> Result<BatchOutcome, (Batch, BatchHandleError)> {
+    match batch.wait(PollPolicy::fixed(delay)).await {
+        Ok(outcome) => Ok(outcome),
+        Err(e) => Err((batch, e)),
     }
 }
 
@@ -145,18 +130,15 @@ async fn do_main() -> Result<(), Box<dyn std::error::Error>> {
 
                 // Log details about the results
                 match final_status {
-                    gemini_rust::BatchStatus::Succeeded { .. } => {
+                    BatchOutcome::Succeeded { .. } => {
                         info!("batch succeeded");
                     }
-                    gemini_rust::BatchStatus::Cancelled => {
+                    BatchOutcome::Cancelled => {
                         info!("batch was cancelled as requested");
                     }
-                    gemini_rust::BatchStatus::Expired => {
+                    BatchOutcome::Expired => {
                         warn!("batch expired");
                     }
-                    _ => {
-                        warn!("batch finished with unexpected status");
-                    }
                 }
             }
             Err((batch, e)) => {
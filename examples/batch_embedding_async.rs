@@ -0,0 +1,95 @@
+use display_error_chain::DisplayErrorChain;
+use gemini_rust::{
+    BatchOutcome, Content, EmbedContentRequest, Gemini, Model, PollPolicy, TaskType,
+};
+use std::process::ExitCode;
+use std::time::Duration;
+use tracing::{error, info};
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    tracing_subscriber::fmt()
+        .with_env_filter(
+            tracing_subscriber::EnvFilter::builder()
+                .with_default_directive(tracing::level_filters::LevelFilter::INFO.into())
+                .from_env_lossy(),
+        )
+        .init();
+
+    match do_main().await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            let error_chain = DisplayErrorChain::new(e.as_ref());
+            tracing::error!(error.debug = ?e, error.chained = %error_chain, "execution failed");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+async fn do_main() -> Result<(), Box<dyn std::error::Error>> {
+    let api_key = std::env::var("GEMINI_API_KEY")?;
+
+    // Embedding batches are submitted against the client's model
+    let client = Gemini::with_model(api_key, Model::TextEmbedding004)
+        .expect("unable to create Gemini API client");
+
+    let documents = [
+        (
+            "doc-rust",
+            "Rust is a systems programming language focused on safety.",
+        ),
+        ("doc-tokio", "Tokio is an asynchronous runtime for Rust."),
+        (
+            "doc-serde",
+            "Serde is a framework for serializing Rust data structures.",
+        ),
+    ];
+
+    let mut builder = client
+        .batch_embed_content()
+        .with_name("documents-embedding".to_string());
+    for (key, text) in documents {
+        builder = builder.with_keyed_request(
+            key,
+            EmbedContentRequest {
+                model: Model::TextEmbedding004,
+                content: Content::text(text),
+                task_type: Some(TaskType::RetrievalDocument),
+                title: None,
+                output_dimensionality: None,
+            },
+        );
+    }
+
+    let batch = builder.execute().await?;
+    info!(batch_name = batch.name(), "embedding batch created");
+
+    let outcome = batch
+        .wait_with_progress(
+            PollPolicy::default().with_initial_interval(Duration::from_secs(10)),
+            |progress| info!(?progress, "embedding batch in progress"),
+        )
+        .await?;
+
+    match outcome {
+        BatchOutcome::Succeeded { results } => {
+            for item in results {
+                match item.response {
+                    Ok(embedding) => info!(
+                        key = %item.meta.key,
+                        dimensions = embedding.values.len(),
+                        "embedding result"
+                    ),
+                    Err(err) => {
+                        error!(key = %item.meta.key, message = err.message, "embedding failed")
+                    }
+                }
+            }
+        }
+        BatchOutcome::Cancelled | BatchOutcome::Expired => {
+            error!("embedding batch did not complete");
+        }
+    }
+
+    Ok(())
+}
@@ -12,40 +12,11 @@
 //! ```
 
 use display_error_chain::DisplayErrorChain;
-use gemini_rust::{Batch, BatchHandleError, BatchStatus, Gemini, Message};
+use gemini_rust::{BatchOutcome, Gemini, Message, PollPolicy};
 use std::process::ExitCode;
 use std::time::Duration;
 use tracing::{error, info, warn};
 
-/// Waits for the batch operation to complete by periodically polling its status.
-///
-/// This method polls the batch status with a specified delay until the operation
-/// reaches a terminal state (Succeeded, Failed, Cancelled, or Expired).
-///
-/// Consumes the batch and returns the final status. If there's an error during polling,
-/// the batch is returned in the error variant so it can be retried.
-pub async fn wait_for_completion(
-    batch: Batch,
-    delay: Duration,
-) -> Result<BatchStatus, (Batch, BatchHandleError)> {
-    let batch_name = batch.name.clone();
-    loop {
-        match batch.status().await {
-            Ok(status) => match status {
-                BatchStatus::Succeeded { .. } | BatchStatus::Cancelled => return Ok(status),
-                BatchStatus::Expired => {
-                    return Err((batch, BatchHandleError::BatchExpired { name: batch_name }));
-                }
-                _ => tokio::time::sleep(delay).await,
-            },
-            Err(e) => match e {
-                BatchHandleError::BatchFailed { .. } => return Err((batch, e)),
-                _ => return Err((batch, e)), // Return the batch and error for retry
-            },
-        }
-    }
-}
-
 #[tokio::main]
 async fn main() -> ExitCode {
     tracing_subscriber::fmt()
@@ -96,51 +67,51 @@ async fn do_main() -> Result<(), Box<dyn std::error::Error>> {
     // Print the batch information
     info!(batch_name = batch.name(), "batch created successfully");
 
-    // Wait for the batch to complete
+    // Wait for the batch to complete, logging progress while it runs
     info!("waiting for batch to complete");
-    match wait_for_completion(batch, Duration::from_secs(5)).await {
-        Ok(final_status) => {
-            // Print the final status
-            match final_status {
-                BatchStatus::Succeeded { results } => {
-                    info!("batch succeeded");
-                    for item in results {
-                        match item.response {
-                            Ok(response) => {
-                                info!(
-                                    key = item.meta.key,
-                                    response = response.text(),
-                                    "batch response"
-                                );
-                            }
-                            Err(error) => {
-                                error!(
-                                    key = item.meta.key,
-                                    code = error.code,
-                                    message = error.message,
-                                    "batch error"
-                                );
-                                if let Some(details) = &error.details {
-                                    error!(details = ?details, "error details");
-                                }
-                            }
+    let policy = PollPolicy::default().with_timeout(Duration::from_secs(60 * 60));
+    let outcome = batch
+        .wait_with_progress(policy, |progress| {
+            info!(
+                completed = progress.completed_count,
+                failed = progress.failed_count,
+                total = progress.total_count,
+                "batch in progress"
+            );
+        })
+        .await?;
+
+    match outcome {
+        BatchOutcome::Succeeded { results } => {
+            info!("batch succeeded");
+            for item in results {
+                match item.response {
+                    Ok(response) => {
+                        info!(
+                            key = %item.meta.key,
+                            response = response.text(),
+                            "batch response"
+                        );
+                    }
+                    Err(error) => {
+                        error!(
+                            key = %item.meta.key,
+                            code = error.code,
+                            message = error.message,
+                            "batch error"
+                        );
+                        if let Some(details) = &error.details {
+                            error!(details = ?details, "error details");
                         }
                     }
                 }
-                BatchStatus::Cancelled => {
-                    warn!("batch was cancelled");
-                }
-                BatchStatus::Expired => {
-                    warn!("batch expired");
-                }
-                _ => {
-                    warn!(status = ?final_status, "batch finished with unexpected status");
-                }
             }
         }
-        Err((_batch, e)) => {
-            error!(error = %e, "batch failed - you can retry with the returned batch");
-            // Here you could retry: batch.wait_for_completion(Duration::from_secs(5)).await, etc.
+        BatchOutcome::Cancelled => {
+            warn!("batch was cancelled");
+        }
+        BatchOutcome::Expired => {
+            warn!("batch expired");
         }
     }
 
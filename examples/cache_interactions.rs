@@ -0,0 +1,80 @@
+//! Context caching with the Interactions API
+//!
+//! Builds a cache from Interactions API inputs (content items, step history and tools),
+//! then reuses it across interactions with `InteractionBuilder::with_cache`.
+//!
+//! ```sh
+//! export GEMINI_API_KEY=your_api_key
+//! cargo run --package gemini-rust --example cache_interactions
+//! ```
+
+use display_error_chain::DisplayErrorChain;
+use gemini_rust::{Gemini, InteractionContent, Model, Step};
+use std::process::ExitCode;
+use std::time::Duration;
+use tracing::{info, warn};
+
+const GRIEF_EATER_STORY: &str = include_str!("../test_data/grief_eater.txt");
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    tracing_subscriber::fmt()
+        .with_env_filter(
+            tracing_subscriber::EnvFilter::builder()
+                .with_default_directive(tracing::level_filters::LevelFilter::INFO.into())
+                .from_env_lossy(),
+        )
+        .init();
+
+    match do_main().await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            let error_chain = DisplayErrorChain::new(e.as_ref());
+            tracing::error!(error.debug = ?e, error.chained = %error_chain, "execution failed");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+async fn do_main() -> Result<(), Box<dyn std::error::Error>> {
+    let api_key = std::env::var("GEMINI_API_KEY")?;
+    let client = Gemini::new(api_key)?;
+
+    let cache = client
+        .create_cache()
+        .with_model(Model::Gemini25Flash)
+        .with_system_instruction("You are a literary analyst. Answer concisely.")
+        .with_interaction_content(vec![
+            InteractionContent::text("Please read this story:"),
+            InteractionContent::text(GRIEF_EATER_STORY),
+        ])?
+        .with_steps(vec![Step::ModelOutput {
+            content: vec![InteractionContent::text(
+                "I have read the story and am ready for questions.",
+            )],
+            error: None,
+        }])?
+        .with_ttl(Duration::from_secs(600))
+        .execute()
+        .await?;
+    info!(cache = cache.name(), model = ?cache.model(), "cache created");
+
+    for question in [
+        "What is the central theme of the story?",
+        "Describe the protagonist in one sentence.",
+    ] {
+        let interaction = client
+            .create_interaction()
+            .with_model("gemini-2.5-flash")
+            .with_cache(&cache)
+            .with_text(question)
+            .execute()
+            .await?;
+        info!(question, answer = interaction.output_text(), "answered");
+    }
+
+    if let Err((_, e)) = cache.delete().await {
+        warn!(error = %e, "failed to delete cache");
+    }
+    Ok(())
+}
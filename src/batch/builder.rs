@@ -1,11 +1,18 @@
-use snafu::ResultExt;
-use std::sync::Arc;
+use snafu::{ensure, ResultExt};
+use std::{collections::HashSet, ops::Range, sync::Arc};
 use tracing::{instrument, Span};
 
 use super::handle::BatchHandle;
+use super::journal::BatchJournal;
 use super::model::*;
+use super::sharded_handle::{Shard, ShardedBatchHandle};
 use super::*;
-use crate::{client::GeminiClient, generation::GenerateContentRequest};
+use crate::{
+    client::GeminiClient,
+    files::{builder::FileBuilder, handle::FileHandle},
+    generation::GenerateContentRequest,
+    safety::validate_settings,
+};
 
 /// A builder for creating and executing synchronous batch content generation requests.
 ///
@@ -16,7 +23,34 @@ use crate::{client::GeminiClient, generation::GenerateContentRequest};
 pub struct BatchBuilder {
     client: Arc<GeminiClient>,
     display_name: String,
-    requests: Vec<GenerateContentRequest>,
+    /// Requests paired with their caller-supplied key, if any.
+    requests: Vec<(Option<String>, GenerateContentRequest)>,
+    shard_limits: ShardLimits,
+    journal: Option<BatchJournal>,
+}
+
+/// Size limits used by [`BatchBuilder::execute_auto`] to split a batch into jobs.
+///
+/// Sizes are measured on the serialized JSON Lines input, one line per request.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShardLimits {
+    /// Largest payload sent inline; larger batches are uploaded as files.
+    pub max_inline_bytes: usize,
+    /// Largest input file uploaded for a single batch job.
+    pub max_file_bytes: usize,
+    /// Optional cap on the number of requests per batch job.
+    pub max_requests_per_batch: Option<usize>,
+}
+
+impl Default for ShardLimits {
+    /// The documented API limits: 20 MB inline requests and 2 GB input files.
+    fn default() -> Self {
+        Self {
+            max_inline_bytes: 20 * 1024 * 1024,
+            max_file_bytes: 2 * 1024 * 1024 * 1024,
+            max_requests_per_batch: None,
+        }
+    }
 }
 
 impl BatchBuilder {
@@ -26,6 +60,8 @@ impl BatchBuilder {
             client,
             display_name: "RustBatch".to_string(),
             requests: Vec::new(),
+            shard_limits: ShardLimits::default(),
+            journal: None,
         }
     }
 
@@ -37,38 +73,87 @@ impl BatchBuilder {
 
     /// Sets all requests for the batch operation, replacing any existing requests.
     pub fn with_requests(mut self, requests: Vec<GenerateContentRequest>) -> Self {
-        self.requests = requests;
+        self.requests = requests.into_iter().map(|r| (None, r)).collect();
         self
     }
 
     /// Adds a single `GenerateContentRequest` to the batch.
+    ///
+    /// The request is keyed by its position in the batch (`"0"`, `"1"`, ...). Build it with
+    /// [`ContentBuilder::build_checked`](crate::generation::ContentBuilder::build_checked) to
+    /// run the builder's content filters. Safety settings are validated when the batch is
+    /// built or submitted.
     pub fn with_request(mut self, request: GenerateContentRequest) -> Self {
-        self.requests.push(request);
+        self.requests.push((None, request));
+        self
+    }
+
+    /// Adds a `GenerateContentRequest` identified by a caller-supplied key.
+    ///
+    /// The key is sent as the request metadata and returned unchanged alongside the
+    /// corresponding result, in both inline and file-based batches. Keys must be unique
+    /// within a batch, including against the index-based keys of unkeyed requests.
+    pub fn with_keyed_request(
+        mut self,
+        key: impl Into<String>,
+        request: GenerateContentRequest,
+    ) -> Self {
+        self.requests.push((Some(key.into()), request));
+        self
+    }
+
+    /// Overrides the limits [`execute_auto`](Self::execute_auto) uses to pick a submission
+    /// mode and split the batch.
+    pub fn with_shard_limits(mut self, limits: ShardLimits) -> Self {
+        self.shard_limits = limits;
+        self
+    }
+
+    /// Records every submitted batch job in `journal`, so it can be resumed with
+    /// [`Gemini::resume_batches`](crate::Gemini::resume_batches) after a restart.
+    ///
+    /// A batch is recorded right after the API accepts it. If writing the journal fails, the
+    /// batch is already running, so the error is [`Error::Unjournaled`], naming the job and
+    /// returning a handle to every job submitted.
+    pub fn with_journal(mut self, journal: BatchJournal) -> Self {
+        self.journal = Some(journal);
         self
     }
 
     /// Constructs the final `BatchGenerateContentRequest` from the builder's configuration.
     ///
-    /// This method consumes the builder.
-    pub fn build(self) -> BatchGenerateContentRequest {
-        let batch_requests: Vec<BatchRequestItem> = self
-            .requests
+    /// This method consumes the builder. Fails with [`Error::DuplicateKey`] if two requests
+    /// share a key, and with [`Error::InvalidSafetySettings`] if a request uses a safety
+    /// category Gemini models reject.
+    pub fn build(self) -> Result<BatchGenerateContentRequest, Error> {
+        self.check_requests()?;
+        let batch_requests: Vec<BatchRequestItem> = resolve_keys(self.requests)
             .into_iter()
-            .enumerate()
             .map(|(key, request)| BatchRequestItem {
                 request,
                 metadata: RequestMetadata { key },
             })
             .collect();
 
-        BatchGenerateContentRequest {
+        Ok(BatchGenerateContentRequest {
             batch: BatchConfig {
                 display_name: self.display_name,
                 input_config: InputConfig::Requests(RequestsContainer {
                     requests: batch_requests,
                 }),
             },
+        })
+    }
+
+    /// Checks that keys are unique and that every request's safety settings are valid.
+    fn check_requests(&self) -> Result<(), Error> {
+        ensure_unique_keys(&self.requests)?;
+        let keys = resolved_keys(&self.requests);
+        for (key, (_, request)) in keys.into_iter().zip(&self.requests) {
+            let settings = request.safety_settings.as_deref().unwrap_or_default();
+            validate_settings(settings).context(InvalidSafetySettingsSnafu { key })?;
         }
+        Ok(())
     }
 
     /// Submits the batch request to the Gemini API and returns a `Batch` handle.
@@ -80,12 +165,24 @@ impl BatchBuilder {
     ))]
     pub async fn execute(self) -> Result<BatchHandle, Error> {
         let client = self.client.clone();
-        let request = self.build();
+        let journal = self.journal.clone();
+        let display_name = self.display_name.clone();
+        let keys = resolved_keys(&self.requests);
+        let request = self.build()?;
         let response = client
             .batch_generate_content(request)
             .await
             .context(ClientSnafu)?;
-        Ok(BatchHandle::new(response.name, client))
+        record_submitted(
+            journal.as_ref(),
+            &response.name,
+            &display_name,
+            None,
+            &keys,
+            &client,
+        )
+        .await?;
+        Ok(BatchHandle::new(response.name, client).with_order(keys))
     }
 
     /// Executes the batch request by first uploading the requests as a JSON file.
@@ -98,45 +195,287 @@ impl BatchBuilder {
         batch.size = self.requests.len()
     ))]
     pub async fn execute_as_file(self) -> Result<BatchHandle, Error> {
-        let mut json_lines = String::new();
-        for (index, item) in self.requests.into_iter().enumerate() {
-            let item = BatchRequestFileItem {
-                request: item,
-                key: index,
-            };
+        self.check_requests()?;
+        let client = self.client.clone();
+        let display_name = self.display_name;
+        let keys = resolved_keys(&self.requests);
 
-            let line = serde_json::to_string(&item).context(SerializeSnafu)?;
-            json_lines.push_str(&line);
-            json_lines.push('\n');
-        }
-        let json_bytes = json_lines.into_bytes();
-        Span::current().record("file.size", json_bytes.len());
-
-        let file_display_name = format!("{}-input.jsonl", self.display_name);
-        let file = crate::files::builder::FileBuilder::new(self.client.clone(), json_bytes)
-            .display_name(file_display_name)
-            .with_mime_type(
-                "application/jsonl"
-                    .parse()
-                    .expect("failed to parse MIME type 'application/jsonl'"),
-            )
-            .upload()
-            .await
-            .context(FileSnafu)?;
+        let items = resolve_keys(self.requests)
+            .into_iter()
+            .map(|(key, request)| BatchRequestFileItem { request, key });
+        let file = upload_jsonl(&client, &display_name, items).await?;
 
         let request = BatchGenerateContentRequest {
             batch: BatchConfig {
-                display_name: self.display_name,
+                display_name: display_name.clone(),
                 input_config: InputConfig::FileName(file.name().to_string()),
             },
         };
 
-        let client = self.client.clone();
         let response = client
             .batch_generate_content(request)
             .await
             .context(ClientSnafu)?;
+        record_submitted(
+            self.journal.as_ref(),
+            &response.name,
+            &display_name,
+            Some(file.name()),
+            &keys,
+            &client,
+        )
+        .await?;
 
-        Ok(BatchHandle::new(response.name, client))
+        Ok(BatchHandle::new(response.name, client).with_order(keys))
     }
 }
+
+impl BatchBuilder {
+    /// Submits the batch in whichever form fits its size.
+    ///
+    /// The requests are measured as JSON Lines. A batch within
+    /// [`ShardLimits::max_inline_bytes`] is sent inline; anything larger is uploaded as one or
+    /// more input files, each within [`ShardLimits::max_file_bytes`] (and
+    /// [`ShardLimits::max_requests_per_batch`], if set). Each file becomes its own batch job,
+    /// named `{display_name}-{n}`.
+    ///
+    /// The returned [`ShardedBatchHandle`] tracks all jobs and merges their results in the
+    /// order the requests were added. A single request larger than the file limit fails with
+    /// [`Error::RequestTooLarge`] before anything is submitted. If a later job cannot be
+    /// submitted, the jobs already running are returned in
+    /// [`Error::PartialSubmission`], so they can be awaited or cancelled.
+    #[instrument(skip_all, fields(
+        batch.display_name = self.display_name,
+        batch.size = self.requests.len(),
+        batch.shards,
+    ))]
+    pub async fn execute_auto(self) -> Result<ShardedBatchHandle, Error> {
+        self.check_requests()?;
+        let client = self.client.clone();
+        let display_name = self.display_name;
+        let limits = self.shard_limits;
+        let journal = self.journal;
+
+        let items = resolve_keys(self.requests)
+            .into_iter()
+            .map(|(key, request)| BatchRequestFileItem { request, key })
+            .collect::<Vec<_>>();
+        let mut sizes = Vec::with_capacity(items.len());
+        for item in &items {
+            let size = serde_json::to_vec(item).context(SerializeSnafu)?.len() + 1;
+            ensure!(
+                size <= limits.max_file_bytes,
+                RequestTooLargeSnafu {
+                    key: item.key.clone(),
+                    size,
+                    limit: limits.max_file_bytes,
+                }
+            );
+            sizes.push(size);
+        }
+
+        let total: usize = sizes.iter().sum();
+        let fits_inline = total <= limits.max_inline_bytes
+            && limits
+                .max_requests_per_batch
+                .is_none_or(|max| items.len() <= max);
+        let order = items.iter().map(|item| item.key.clone()).collect();
+
+        if fits_inline {
+            Span::current().record("batch.shards", 1);
+            let keys: Vec<String> = items.iter().map(|item| item.key.clone()).collect();
+            let requests = items
+                .into_iter()
+                .map(|item| BatchRequestItem {
+                    request: item.request,
+                    metadata: RequestMetadata { key: item.key },
+                })
+                .collect();
+            let request = BatchGenerateContentRequest {
+                batch: BatchConfig {
+                    display_name: display_name.clone(),
+                    input_config: InputConfig::Requests(RequestsContainer { requests }),
+                },
+            };
+            let response = client
+                .batch_generate_content(request)
+                .await
+                .context(ClientSnafu)?;
+            record_submitted(
+                journal.as_ref(),
+                &response.name,
+                &display_name,
+                None,
+                &keys,
+                &client,
+            )
+            .await?;
+            let shard = Shard::new(response.name, keys);
+            return Ok(ShardedBatchHandle::new(vec![shard], order, client));
+        }
+
+        let ranges = plan_shards(&sizes, limits.max_file_bytes, limits.max_requests_per_batch);
+        Span::current().record("batch.shards", ranges.len());
+
+        let mut shards = Vec::with_capacity(ranges.len());
+        let mut items = items.into_iter();
+        for (index, range) in ranges.into_iter().enumerate() {
+            let shard_items = items.by_ref().take(range.len()).collect::<Vec<_>>();
+            let keys: Vec<String> = shard_items.iter().map(|item| item.key.clone()).collect();
+            let shard_name = format!("{display_name}-{}", index + 1);
+
+            let submitted = async {
+                let file = upload_jsonl(&client, &shard_name, shard_items).await?;
+                let request = BatchGenerateContentRequest {
+                    batch: BatchConfig {
+                        display_name: shard_name.clone(),
+                        input_config: InputConfig::FileName(file.name().to_string()),
+                    },
+                };
+                let response = client
+                    .batch_generate_content(request)
+                    .await
+                    .context(ClientSnafu)?;
+                Ok::<_, Error>((response.name, file))
+            }
+            .await;
+            let (name, file) = match submitted {
+                Ok(submitted) => submitted,
+                Err(e) if shards.is_empty() => return Err(e),
+                Err(e) => {
+                    let submitted = ShardedBatchHandle::new(shards, order, client);
+                    return Err(e).context(PartialSubmissionSnafu {
+                        shard: index + 1,
+                        submitted: Box::new(submitted),
+                    });
+                }
+            };
+            shards.push(Shard::new(name.clone(), keys.clone()));
+            if let Some(journal) = &journal {
+                let recorded = journal
+                    .record_submitted(&name, &shard_name, Some(file.name()), keys)
+                    .await;
+                if let Err(e) = recorded {
+                    let submitted = ShardedBatchHandle::new(shards, order, client);
+                    return Err(e).context(UnjournaledSnafu {
+                        name,
+                        submitted: Box::new(submitted),
+                    });
+                }
+            }
+        }
+
+        Ok(ShardedBatchHandle::new(shards, order, client))
+    }
+}
+
+/// Records a single submitted job in `journal`, if any.
+///
+/// The job is already running, so a failed write is reported as [`Error::Unjournaled`] with a
+/// handle to the job.
+async fn record_submitted(
+    journal: Option<&BatchJournal>,
+    name: &str,
+    display_name: &str,
+    input_file: Option<&str>,
+    keys: &[String],
+    client: &Arc<GeminiClient>,
+) -> Result<(), Error> {
+    let Some(journal) = journal else {
+        return Ok(());
+    };
+    journal
+        .record_submitted(name, display_name, input_file, keys.to_vec())
+        .await
+        .with_context(|_| UnjournaledSnafu {
+            name,
+            submitted: Box::new(ShardedBatchHandle::new(
+                vec![Shard::new(name.to_string(), keys.to_vec())],
+                keys.to_vec(),
+                client.clone(),
+            )),
+        })
+}
+
+/// Splits consecutive items into ranges whose total size and count stay within the limits.
+///
+/// Every item must individually fit within `max_bytes`.
+pub(crate) fn plan_shards(
+    sizes: &[usize],
+    max_bytes: usize,
+    max_requests: Option<usize>,
+) -> Vec<Range<usize>> {
+    let max_requests = max_requests.unwrap_or(usize::MAX).max(1);
+    let mut ranges = Vec::new();
+    let mut start = 0;
+    let mut bytes = 0;
+
+    for (index, &size) in sizes.iter().enumerate() {
+        if index > start && (bytes + size > max_bytes || index - start >= max_requests) {
+            ranges.push(start..index);
+            start = index;
+            bytes = 0;
+        }
+        bytes += size;
+    }
+    if start < sizes.len() {
+        ranges.push(start..sizes.len());
+    }
+    ranges
+}
+
+/// Resolves every request to its final key, falling back to the request's index in the batch.
+pub(crate) fn resolve_keys<R>(requests: Vec<(Option<String>, R)>) -> Vec<(String, R)> {
+    requests
+        .into_iter()
+        .enumerate()
+        .map(|(index, (key, request))| (key.unwrap_or_else(|| index.to_string()), request))
+        .collect()
+}
+
+/// Returns the final key of every request without consuming them.
+pub(crate) fn resolved_keys<R>(requests: &[(Option<String>, R)]) -> Vec<String> {
+    requests
+        .iter()
+        .enumerate()
+        .map(|(index, (key, _))| key.clone().unwrap_or_else(|| index.to_string()))
+        .collect()
+}
+
+/// Fails with [`Error::DuplicateKey`] if two requests resolve to the same key.
+pub(crate) fn ensure_unique_keys<R>(requests: &[(Option<String>, R)]) -> Result<(), Error> {
+    let mut seen = HashSet::with_capacity(requests.len());
+    for (index, (key, _)) in requests.iter().enumerate() {
+        let key = key.clone().unwrap_or_else(|| index.to_string());
+        ensure!(!seen.contains(&key), DuplicateKeySnafu { key });
+        seen.insert(key);
+    }
+    Ok(())
+}
+
+/// Serializes batch input items as JSON Lines and uploads them through the Files API.
+pub(crate) async fn upload_jsonl<T: serde::Serialize>(
+    client: &Arc<GeminiClient>,
+    display_name: &str,
+    items: impl IntoIterator<Item = T>,
+) -> Result<FileHandle, Error> {
+    let mut json_lines = String::new();
+    for item in items {
+        let line = serde_json::to_string(&item).context(SerializeSnafu)?;
+        json_lines.push_str(&line);
+        json_lines.push('\n');
+    }
+    let json_bytes = json_lines.into_bytes();
+    Span::current().record("file.size", json_bytes.len());
+
+    FileBuilder::new(client.clone(), json_bytes)
+        .display_name(format!("{display_name}-input.jsonl"))
+        .with_mime_type(
+            "application/jsonl"
+                .parse()
+                .expect("failed to parse MIME type 'application/jsonl'"),
+        )
+        .upload()
+        .await
+        .context(FileSnafu)
+}
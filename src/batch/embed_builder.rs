@@ -0,0 +1,154 @@
+use snafu::ResultExt;
+use std::sync::Arc;
+use tracing::instrument;
+
+use super::builder::{ensure_unique_keys, resolve_keys, resolved_keys, upload_jsonl};
+use super::embed_handle::EmbedBatchHandle;
+use super::model::*;
+use super::*;
+use crate::{client::GeminiClient, embedding::EmbedContentRequest, Model};
+
+/// A builder for embedding batches submitted through `asyncBatchEmbedContent`.
+///
+/// Embedding batches run asynchronously at the reduced batch price and are suited to
+/// large offline jobs. Requests can be sent inline with [`execute`](Self::execute) or
+/// uploaded as a JSON Lines file with [`execute_as_file`](Self::execute_as_file).
+///
+/// The batch is submitted to the model set with [`with_model`](Self::with_model), or else to
+/// the model of the first request, falling back to the client's model.
+#[derive(Clone)]
+pub struct EmbedBatchBuilder {
+    client: Arc<GeminiClient>,
+    model: Option<Model>,
+    display_name: String,
+    /// Requests paired with their caller-supplied key, if any.
+    requests: Vec<(Option<String>, EmbedContentRequest)>,
+}
+
+impl EmbedBatchBuilder {
+    /// Create a new embedding batch builder
+    pub(crate) fn new(client: Arc<GeminiClient>) -> Self {
+        Self {
+            client,
+            model: None,
+            display_name: "RustEmbedBatch".to_string(),
+            requests: Vec::new(),
+        }
+    }
+
+    /// Submits the batch to `model`, which should match the model of the requests.
+    pub fn with_model<M: Into<Model>>(mut self, model: M) -> Self {
+        self.model = Some(model.into());
+        self
+    }
+
+    /// Returns the model the batch is submitted to.
+    fn model(&self) -> Model {
+        self.model
+            .clone()
+            .or_else(|| self.requests.first().map(|(_, r)| r.model.clone()))
+            .unwrap_or_else(|| self.client.model.clone())
+    }
+
+    /// Sets the user-friendly display name for the batch request.
+    pub fn with_name(mut self, name: String) -> Self {
+        self.display_name = name;
+        self
+    }
+
+    /// Sets all requests for the batch operation, replacing any existing requests.
+    pub fn with_requests(mut self, requests: Vec<EmbedContentRequest>) -> Self {
+        self.requests = requests.into_iter().map(|r| (None, r)).collect();
+        self
+    }
+
+    /// Adds a single `EmbedContentRequest` to the batch, keyed by its position.
+    pub fn with_request(mut self, request: EmbedContentRequest) -> Self {
+        self.requests.push((None, request));
+        self
+    }
+
+    /// Adds an `EmbedContentRequest` identified by a caller-supplied key.
+    ///
+    /// Keys must be unique within the batch and are returned with the matching embedding.
+    pub fn with_keyed_request(
+        mut self,
+        key: impl Into<String>,
+        request: EmbedContentRequest,
+    ) -> Self {
+        self.requests.push((Some(key.into()), request));
+        self
+    }
+
+    /// Constructs the final `AsyncBatchEmbedContentRequest` from the builder's configuration.
+    ///
+    /// This method consumes the builder. Fails with [`Error::DuplicateKey`] if two requests
+    /// share a key.
+    pub fn build(self) -> Result<AsyncBatchEmbedContentRequest, Error> {
+        ensure_unique_keys(&self.requests)?;
+        let requests = resolve_keys(self.requests)
+            .into_iter()
+            .map(|(key, request)| EmbedBatchRequestItem {
+                request,
+                metadata: RequestMetadata { key },
+            })
+            .collect();
+
+        Ok(AsyncBatchEmbedContentRequest {
+            batch: EmbedBatchConfig {
+                display_name: self.display_name,
+                input_config: EmbedInputConfig::Requests(EmbedRequestsContainer { requests }),
+            },
+        })
+    }
+
+    /// Submits the embedding batch with inline requests and returns a handle to it.
+    #[instrument(skip_all, fields(
+        batch.display_name = self.display_name,
+        batch.size = self.requests.len()
+    ))]
+    pub async fn execute(self) -> Result<EmbedBatchHandle, Error> {
+        let client = self.client.clone();
+        let model = self.model();
+        let keys = resolved_keys(&self.requests);
+        let request = self.build()?;
+        let response = client
+            .async_batch_embed_content(&model, request)
+            .await
+            .context(ClientSnafu)?;
+        Ok(EmbedBatchHandle::new(response.name, client).with_order(keys))
+    }
+
+    /// Submits the embedding batch by first uploading the requests as a JSON Lines file.
+    ///
+    /// Use this for batches whose inline payload would exceed the request size limit.
+    #[instrument(skip_all, fields(
+        batch.display_name = self.display_name,
+        batch.size = self.requests.len()
+    ))]
+    pub async fn execute_as_file(self) -> Result<EmbedBatchHandle, Error> {
+        ensure_unique_keys(&self.requests)?;
+        let client = self.client.clone();
+        let model = self.model();
+        let display_name = self.display_name;
+        let keys = resolved_keys(&self.requests);
+
+        let items = resolve_keys(self.requests)
+            .into_iter()
+            .map(|(key, request)| EmbedBatchRequestFileItem { request, key });
+        let file = upload_jsonl(&client, &display_name, items).await?;
+
+        let request = AsyncBatchEmbedContentRequest {
+            batch: EmbedBatchConfig {
+                display_name,
+                input_config: EmbedInputConfig::FileName(file.name().to_string()),
+            },
+        };
+        let response = client
+            .async_batch_embed_content(&model, request)
+            .await
+            .context(ClientSnafu)?;
+
+        Ok(EmbedBatchHandle::new(response.name, client).with_order(keys))
+    }
+}
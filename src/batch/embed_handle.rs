@@ -0,0 +1,258 @@
+//! Handle for embedding batch operations created through `asyncBatchEmbedContent`.
+//!
+//! An [`EmbedBatchHandle`] behaves like [`BatchHandle`]: it can report
+//! the batch status, cancel the batch or delete it. Once the batch succeeds, the
+//! [`EmbedBatchStatus::Succeeded`] variant carries one [`BatchEmbeddingResponseItem`] per
+//! input request, tagged with that request's key and sorted the same way as generation
+//! batch results.
+
+use snafu::{OptionExt, ResultExt};
+use std::{collections::HashMap, result::Result, sync::Arc};
+use tracing::instrument;
+
+use super::handle::*;
+use super::model::*;
+use crate::{
+    client::{Error as ClientError, GeminiClient},
+    common::poll::PollPolicy,
+    embedding::ContentEmbedding,
+};
+
+/// The outcome of a single request within an embedding batch.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BatchEmbeddingResponseItem {
+    pub response: Result<ContentEmbedding, IndividualRequestError>,
+    pub meta: RequestMetadata,
+}
+
+/// Represents the overall status of an embedding batch operation.
+#[derive(Debug, Clone, PartialEq)]
+pub enum EmbedBatchStatus {
+    /// The operation is waiting to be processed.
+    Pending,
+    /// The operation is currently being processed.
+    Running {
+        pending_count: i64,
+        completed_count: i64,
+        failed_count: i64,
+        total_count: i64,
+    },
+    /// The operation has completed successfully.
+    Succeeded {
+        results: Vec<BatchEmbeddingResponseItem>,
+    },
+    /// The operation was cancelled by the user.
+    Cancelled,
+    /// The operation has expired.
+    Expired,
+}
+
+impl From<EmbedBatchStatus> for PollState<BatchEmbeddingResponseItem> {
+    fn from(status: EmbedBatchStatus) -> Self {
+        match status {
+            EmbedBatchStatus::Pending => PollState::InProgress(None),
+            EmbedBatchStatus::Running {
+                pending_count,
+                completed_count,
+                failed_count,
+                total_count,
+            } => PollState::InProgress(Some(BatchProgress {
+                pending_count,
+                completed_count,
+                failed_count,
+                total_count,
+            })),
+            EmbedBatchStatus::Succeeded { results } => {
+                PollState::Finished(BatchOutcome::Succeeded { results })
+            }
+            EmbedBatchStatus::Cancelled => PollState::Finished(BatchOutcome::Cancelled),
+            EmbedBatchStatus::Expired => PollState::Finished(BatchOutcome::Expired),
+        }
+    }
+}
+
+impl EmbedBatchStatus {
+    /// Consumes an [`EmbedBatchStatus::Succeeded`] status and indexes its results by request key.
+    ///
+    /// Returns `None` for any other status.
+    pub fn into_results_by_key(self) -> Option<HashMap<String, BatchEmbeddingResponseItem>> {
+        match self {
+            EmbedBatchStatus::Succeeded { results } => Some(
+                results
+                    .into_iter()
+                    .map(|item| (item.meta.key.clone(), item))
+                    .collect(),
+            ),
+            _ => None,
+        }
+    }
+
+    async fn process_successful_response(
+        response: EmbedBatchOperationResponse,
+        client: Arc<GeminiClient>,
+    ) -> Result<Vec<BatchEmbeddingResponseItem>, Error> {
+        match response {
+            EmbedBatchOperationResponse::InlinedResponses { inlined_responses } => {
+                Ok(inlined_responses
+                    .inlined_responses
+                    .into_iter()
+                    .map(|item| BatchEmbeddingResponseItem {
+                        response: item.result.into(),
+                        meta: item.metadata,
+                    })
+                    .collect())
+            }
+            EmbedBatchOperationResponse::ResponsesFile { responses_file } => {
+                let file_content = download_response_file(responses_file, client).await?;
+
+                let mut results = vec![];
+                for line in file_content.lines() {
+                    if line.trim().is_empty() {
+                        continue;
+                    }
+                    let item: EmbedBatchResponseFileItem =
+                        serde_json::from_str(line).context(FileParseSnafu {
+                            line: line.to_string(),
+                        })?;
+
+                    results.push(BatchEmbeddingResponseItem {
+                        response: item.response.into(),
+                        meta: RequestMetadata { key: item.key },
+                    });
+                }
+                Ok(results)
+            }
+        }
+    }
+
+    async fn from_operation(
+        operation: EmbedBatchOperation,
+        order: Option<&[String]>,
+        client: Arc<GeminiClient>,
+    ) -> Result<Self, Error> {
+        if !operation.done {
+            return Ok(match operation.metadata.state {
+                BatchState::BatchStateRunning => {
+                    let (pending_count, completed_count, failed_count, total_count) =
+                        running_counts(&operation.metadata.batch_stats);
+                    EmbedBatchStatus::Running {
+                        pending_count,
+                        completed_count,
+                        failed_count,
+                        total_count,
+                    }
+                }
+                _ => EmbedBatchStatus::Pending,
+            });
+        }
+
+        let result = operation.result.context(MissingResultSnafu {
+            name: operation.name.clone(),
+        })?;
+        let response = Result::from(result).context(BatchFailedSnafu {
+            name: operation.name,
+        })?;
+
+        let mut results = Self::process_successful_response(response, client).await?;
+        sort_results(&mut results, order, |item| &item.meta.key);
+
+        match operation.metadata.state {
+            BatchState::BatchStateCancelled => Ok(EmbedBatchStatus::Cancelled),
+            BatchState::BatchStateExpired => Ok(EmbedBatchStatus::Expired),
+            _ => Ok(EmbedBatchStatus::Succeeded { results }),
+        }
+    }
+}
+
+/// A handle to a long-running embedding batch operation.
+pub struct EmbedBatchHandle {
+    /// The unique resource name of the batch operation, e.g., `batches/xxxxxxxx`.
+    pub name: String,
+    /// Request keys in submission order, if known.
+    order: Option<Vec<String>>,
+    client: Arc<GeminiClient>,
+}
+
+impl EmbedBatchHandle {
+    /// Creates a new embedding batch handle.
+    pub(crate) fn new(name: String, client: Arc<GeminiClient>) -> Self {
+        Self {
+            name,
+            order: None,
+            client,
+        }
+    }
+
+    /// Returns results in the order of `keys` instead of sorted by key.
+    pub(crate) fn with_order(mut self, keys: Vec<String>) -> Self {
+        self.order = Some(keys);
+        self
+    }
+
+    /// Returns the unique resource name of the batch operation.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Retrieves the current status of the embedding batch by making an API call.
+    pub async fn status(&self) -> Result<EmbedBatchStatus, Error> {
+        let operation: EmbedBatchOperation = self
+            .client
+            .get_batch_operation(&self.name)
+            .await
+            .map_err(Box::new)
+            .context(ClientSnafu)?;
+
+        EmbedBatchStatus::from_operation(operation, self.order.as_deref(), self.client.clone())
+            .await
+    }
+
+    /// Polls the embedding batch until it succeeds, is cancelled or expires.
+    ///
+    /// Behaves like [`BatchHandle::wait`](super::BatchHandle::wait).
+    pub async fn wait(
+        &self,
+        policy: PollPolicy,
+    ) -> Result<BatchOutcome<BatchEmbeddingResponseItem>, Error> {
+        self.wait_with_progress(policy, |_| {}).await
+    }
+
+    /// Like [`wait`](Self::wait), but calls `on_progress` after every check that finds the
+    /// batch running.
+    #[instrument(skip_all, fields(batch.name = %self.name))]
+    pub async fn wait_with_progress(
+        &self,
+        policy: PollPolicy,
+        on_progress: impl FnMut(&BatchProgress),
+    ) -> Result<BatchOutcome<BatchEmbeddingResponseItem>, Error> {
+        wait_for_outcome(
+            &self.name,
+            &policy,
+            || async { self.status().await.map(PollState::from) },
+            on_progress,
+        )
+        .await
+    }
+
+    /// Sends a request to the API to cancel the embedding batch.
+    ///
+    /// Consumes the handle. If cancellation fails, returns the handle and error information
+    /// so it can be retried.
+    pub async fn cancel(self) -> Result<(), (Self, ClientError)> {
+        match self.client.cancel_batch_operation(&self.name).await {
+            Ok(()) => Ok(()),
+            Err(e) => Err((self, e)),
+        }
+    }
+
+    /// Deletes the embedding batch resource from the server.
+    ///
+    /// This does not cancel a running batch. Consumes the handle. If deletion fails,
+    /// returns the handle and error information so it can be retried.
+    pub async fn delete(self) -> Result<(), (Self, ClientError)> {
+        match self.client.delete_batch_operation(&self.name).await {
+            Ok(()) => Ok(()),
+            Err(e) => Err((self, e)),
+        }
+    }
+}
@@ -23,8 +23,14 @@
 //!     handles the downloading and parsing of this file automatically when you call
 //!     `status()` on a completed batch.
 //!
-//! The results are automatically sorted by their original request key (as a number) to ensure
-//! a consistent and predictable order.
+//! Each result carries the key of the request it answers: either the caller-supplied key from
+//! [`BatchBuilder::with_keyed_request`](super::BatchBuilder::with_keyed_request) or the request's
+//! index in the batch. Handles returned by the builders, by
+//! [`ShardedBatchHandle`](super::ShardedBatchHandle) and by
+//! [`Gemini::resume_batches`](crate::Gemini::resume_batches) know the submission order and
+//! return results in it. A handle obtained from a batch name alone does not, so its results are
+//! sorted by key instead: index-based keys numerically first, then custom keys lexically.
+//! [`BatchStatus::into_results_by_key`] indexes the results by key.
 //!
 //! For more information, see the official Google AI documentation:
 //! - [Batch Mode Guide](https://ai.google.dev/gemini-api/docs/batch-mode)
@@ -65,16 +71,19 @@
 //! ```
 
 use snafu::{OptionExt, ResultExt, Snafu};
-use std::{result::Result, sync::Arc};
+use std::{collections::HashMap, future::Future, result::Result, sync::Arc};
+use tracing::instrument;
 
 use super::model::*;
 use crate::{
     client::{Error as ClientError, GeminiClient},
+    common::poll::{PollPolicy, PollStop},
     files::handle::FileHandle,
     GenerationResponse,
 };
 
 #[derive(Debug, Snafu)]
+#[snafu(visibility(pub(crate)))]
 pub enum Error {
     #[snafu(display("batch '{name}' expired before finishing"))]
     BatchExpired {
@@ -117,6 +126,96 @@ pub enum Error {
         /// Batch name.
         name: String,
     },
+
+    #[snafu(display("timed out waiting for batch '{name}' to finish"))]
+    WaitTimeout {
+        /// Batch name.
+        name: String,
+    },
+
+    #[snafu(display("stopped waiting for batch '{name}': cancelled"))]
+    WaitCancelled {
+        /// Batch name.
+        name: String,
+    },
+}
+
+/// Progress counters of a running batch, as reported to `wait_with_progress` callbacks.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BatchProgress {
+    pub pending_count: i64,
+    pub completed_count: i64,
+    pub failed_count: i64,
+    pub total_count: i64,
+}
+
+/// The terminal state of a batch, as returned by [`BatchHandle::wait`].
+///
+/// The type parameter is the per-request result type, which differs between generation,
+/// embedding and interaction batches.
+#[derive(Debug, Clone, PartialEq)]
+pub enum BatchOutcome<T = BatchGenerationResponseItem> {
+    /// The batch finished and produced one result per request.
+    Succeeded { results: Vec<T> },
+    /// The batch was cancelled before it finished.
+    Cancelled,
+    /// The batch expired before it finished.
+    Expired,
+}
+
+impl<T> BatchOutcome<T> {
+    /// Returns the results of a succeeded batch, or `None` if it was cancelled or expired.
+    pub fn into_results(self) -> Option<Vec<T>> {
+        match self {
+            BatchOutcome::Succeeded { results } => Some(results),
+            BatchOutcome::Cancelled | BatchOutcome::Expired => None,
+        }
+    }
+
+    /// Converts the result type of a succeeded batch.
+    pub(crate) fn map_results<U>(self, f: impl FnMut(T) -> U) -> BatchOutcome<U> {
+        match self {
+            BatchOutcome::Succeeded { results } => BatchOutcome::Succeeded {
+                results: results.into_iter().map(f).collect(),
+            },
+            BatchOutcome::Cancelled => BatchOutcome::Cancelled,
+            BatchOutcome::Expired => BatchOutcome::Expired,
+        }
+    }
+}
+
+/// Where a batch stands after a single status check.
+pub(crate) enum PollState<T> {
+    /// Still queued (`None`) or running with the given counters.
+    InProgress(Option<BatchProgress>),
+    Finished(BatchOutcome<T>),
+}
+
+/// Polls `check` according to `policy` until the batch reaches a terminal state.
+pub(crate) async fn wait_for_outcome<T, Fut>(
+    name: &str,
+    policy: &PollPolicy,
+    mut check: impl FnMut() -> Fut,
+    mut on_progress: impl FnMut(&BatchProgress),
+) -> Result<BatchOutcome<T>, Error>
+where
+    Fut: Future<Output = Result<PollState<T>, Error>>,
+{
+    let stopped = |stop: PollStop| match stop {
+        PollStop::TimedOut => WaitTimeoutSnafu { name }.build(),
+        PollStop::Cancelled => WaitCancelledSnafu { name }.build(),
+    };
+
+    let mut poller = policy.start();
+    poller.check_cancelled().map_err(stopped)?;
+    loop {
+        match check().await? {
+            PollState::Finished(outcome) => return Ok(outcome),
+            PollState::InProgress(Some(progress)) => on_progress(&progress),
+            PollState::InProgress(None) => {}
+        }
+        poller.wait().await.map_err(stopped)?;
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -147,16 +246,51 @@ pub enum BatchStatus {
     Expired,
 }
 
+impl From<BatchStatus> for PollState<BatchGenerationResponseItem> {
+    fn from(status: BatchStatus) -> Self {
+        match status {
+            BatchStatus::Pending => PollState::InProgress(None),
+            BatchStatus::Running {
+                pending_count,
+                completed_count,
+                failed_count,
+                total_count,
+            } => PollState::InProgress(Some(BatchProgress {
+                pending_count,
+                completed_count,
+                failed_count,
+                total_count,
+            })),
+            BatchStatus::Succeeded { results } => {
+                PollState::Finished(BatchOutcome::Succeeded { results })
+            }
+            BatchStatus::Cancelled => PollState::Finished(BatchOutcome::Cancelled),
+            BatchStatus::Expired => PollState::Finished(BatchOutcome::Expired),
+        }
+    }
+}
+
 impl BatchStatus {
+    /// Consumes a [`BatchStatus::Succeeded`] status and indexes its results by request key.
+    ///
+    /// Returns `None` for any other status.
+    pub fn into_results_by_key(self) -> Option<HashMap<String, BatchGenerationResponseItem>> {
+        match self {
+            BatchStatus::Succeeded { results } => Some(
+                results
+                    .into_iter()
+                    .map(|item| (item.meta.key.clone(), item))
+                    .collect(),
+            ),
+            _ => None,
+        }
+    }
+
     async fn parse_response_file(
-        response_file: crate::files::model::File,
+        responses_file: String,
         client: Arc<GeminiClient>,
     ) -> Result<Vec<BatchGenerationResponseItem>, Error> {
-        let file = FileHandle::new(client.clone(), response_file);
-        let file_content_bytes = file.download().await.context(FileDownloadSnafu {
-            file_name: file.name(),
-        })?;
-        let file_content = String::from_utf8(file_content_bytes).context(FileDecodeSnafu)?;
+        let file_content = download_response_file(responses_file, client).await?;
 
         let mut results = vec![];
         for line in file_content.lines() {
@@ -190,11 +324,7 @@ impl BatchStatus {
                 })
                 .collect(),
             BatchOperationResponse::ResponsesFile { responses_file } => {
-                let file = crate::files::model::File {
-                    name: responses_file,
-                    ..Default::default()
-                };
-                Self::parse_response_file(file, client).await?
+                Self::parse_response_file(responses_file, client).await?
             }
         };
         Ok(results)
@@ -202,6 +332,7 @@ impl BatchStatus {
 
     async fn from_operation(
         operation: BatchOperation,
+        order: Option<&[String]>,
         client: Arc<GeminiClient>,
     ) -> Result<Self, Error> {
         if operation.done {
@@ -215,7 +346,7 @@ impl BatchStatus {
             })?;
 
             let mut results = Self::process_successful_response(response, client).await?;
-            results.sort_by_key(|r| r.meta.key);
+            sort_results(&mut results, order, |item| &item.meta.key);
 
             // Handle terminal states based on metadata for edge cases
             match operation.metadata.state {
@@ -228,22 +359,8 @@ impl BatchStatus {
             match operation.metadata.state {
                 BatchState::BatchStatePending => Ok(BatchStatus::Pending),
                 BatchState::BatchStateRunning => {
-                    let total_count = operation.metadata.batch_stats.request_count;
-                    let pending_count = operation
-                        .metadata
-                        .batch_stats
-                        .pending_request_count
-                        .unwrap_or(total_count);
-                    let completed_count = operation
-                        .metadata
-                        .batch_stats
-                        .completed_request_count
-                        .unwrap_or(0);
-                    let failed_count = operation
-                        .metadata
-                        .batch_stats
-                        .failed_request_count
-                        .unwrap_or(0);
+                    let (pending_count, completed_count, failed_count, total_count) =
+                        running_counts(&operation.metadata.batch_stats);
                     Ok(BatchStatus::Running {
                         pending_count,
                         completed_count,
@@ -258,6 +375,31 @@ impl BatchStatus {
     }
 }
 
+/// Downloads a batch result file and decodes it as UTF-8 JSON Lines text.
+pub(crate) async fn download_response_file(
+    responses_file: String,
+    client: Arc<GeminiClient>,
+) -> Result<String, Error> {
+    let file = crate::files::model::File {
+        name: responses_file,
+        ..Default::default()
+    };
+    let file = FileHandle::new(client, file);
+    let file_content_bytes = file.download().await.context(FileDownloadSnafu {
+        file_name: file.name(),
+    })?;
+    String::from_utf8(file_content_bytes).context(FileDecodeSnafu)
+}
+
+/// Extracts `(pending, completed, failed, total)` request counts from running batch statistics.
+pub(crate) fn running_counts(stats: &BatchStats) -> (i64, i64, i64, i64) {
+    let total_count = stats.request_count;
+    let pending_count = stats.pending_request_count.unwrap_or(total_count);
+    let completed_count = stats.completed_request_count.unwrap_or(0);
+    let failed_count = stats.failed_request_count.unwrap_or(0);
+    (pending_count, completed_count, failed_count, total_count)
+}
+
 /// Represents a long-running batch operation, providing methods to manage its lifecycle.
 ///
 /// A `Batch` object is a handle to a batch operation on the Gemini API. It allows you to
@@ -265,13 +407,25 @@ impl BatchStatus {
 pub struct BatchHandle {
     /// The unique resource name of the batch operation, e.g., `operations/batch-xxxxxxxx`.
     pub name: String,
+    /// Request keys in submission order, if known.
+    order: Option<Vec<String>>,
     client: Arc<GeminiClient>,
 }
 
 impl BatchHandle {
     /// Creates a new Batch instance.
     pub(crate) fn new(name: String, client: Arc<GeminiClient>) -> Self {
-        Self { name, client }
+        Self {
+            name,
+            order: None,
+            client,
+        }
+    }
+
+    /// Returns results in the order of `keys` instead of sorted by key.
+    pub(crate) fn with_order(mut self, keys: Vec<String>) -> Self {
+        self.order = Some(keys);
+        self
     }
 
     /// Returns the unique resource name of the batch operation.
@@ -290,7 +444,33 @@ impl BatchHandle {
             .map_err(Box::new)
             .context(ClientSnafu)?;
 
-        BatchStatus::from_operation(operation, self.client.clone()).await
+        BatchStatus::from_operation(operation, self.order.as_deref(), self.client.clone()).await
+    }
+
+    /// Polls the batch until it succeeds, is cancelled or expires.
+    ///
+    /// See [`PollPolicy`] for the backoff, timeout and cancellation options. Stopping early
+    /// because of the timeout or cancellation token returns [`Error::WaitTimeout`] or
+    /// [`Error::WaitCancelled`] and leaves the batch running.
+    pub async fn wait(&self, policy: PollPolicy) -> Result<BatchOutcome, Error> {
+        self.wait_with_progress(policy, |_| {}).await
+    }
+
+    /// Like [`wait`](Self::wait), but calls `on_progress` after every check that finds the
+    /// batch running.
+    #[instrument(skip_all, fields(batch.name = %self.name))]
+    pub async fn wait_with_progress(
+        &self,
+        policy: PollPolicy,
+        on_progress: impl FnMut(&BatchProgress),
+    ) -> Result<BatchOutcome, Error> {
+        wait_for_outcome(
+            &self.name,
+            &policy,
+            || async { self.status().await.map(PollState::from) },
+            on_progress,
+        )
+        .await
     }
 
     /// Sends a request to the API to cancel the batch operation.
@@ -0,0 +1,154 @@
+use snafu::{ensure, ResultExt};
+use std::sync::Arc;
+use tracing::instrument;
+
+use super::builder::{ensure_unique_keys, resolve_keys};
+use super::interaction_handle::InteractionBatchHandle;
+use super::*;
+use crate::{
+    client::GeminiClient,
+    generation::GenerateContentRequest,
+    interactions::{CreateInteractionRequest, InteractionBuilder},
+};
+
+/// An interaction request that has not been converted to the batch wire format yet.
+#[derive(Clone)]
+enum PendingInteraction {
+    Request(CreateInteractionRequest),
+    Builder(InteractionBuilder),
+}
+
+/// A builder for batches of Interactions API requests.
+///
+/// The batch endpoint only understands the `generateContent` request format, so every
+/// request is converted before submission (see [`crate::interactions::convert`]). Requests
+/// that use features with no equivalent, such as agents or `previous_interaction_id`, are
+/// rejected with [`Error::Conversion`]. Results come back as
+/// [`Interaction`](crate::interactions::Interaction) values through
+/// [`InteractionBatchHandle::status`].
+///
+/// A batch runs on a single model: the client's. Requests naming another model fail with
+/// [`Error::ModelMismatch`].
+#[derive(Clone)]
+pub struct InteractionBatchBuilder {
+    client: Arc<GeminiClient>,
+    display_name: String,
+    /// Requests paired with their caller-supplied key, if any.
+    requests: Vec<(Option<String>, PendingInteraction)>,
+}
+
+impl InteractionBatchBuilder {
+    /// Create a new interaction batch builder
+    pub(crate) fn new(client: Arc<GeminiClient>) -> Self {
+        Self {
+            client,
+            display_name: "RustInteractionBatch".to_string(),
+            requests: Vec::new(),
+        }
+    }
+
+    /// Sets the user-friendly display name for the batch request.
+    pub fn with_name(mut self, name: String) -> Self {
+        self.display_name = name;
+        self
+    }
+
+    /// Adds a `CreateInteractionRequest` to the batch, keyed by its position.
+    pub fn with_request(mut self, request: CreateInteractionRequest) -> Self {
+        self.requests
+            .push((None, PendingInteraction::Request(request)));
+        self
+    }
+
+    /// Adds a `CreateInteractionRequest` identified by a caller-supplied key.
+    pub fn with_keyed_request(
+        mut self,
+        key: impl Into<String>,
+        request: CreateInteractionRequest,
+    ) -> Self {
+        self.requests
+            .push((Some(key.into()), PendingInteraction::Request(request)));
+        self
+    }
+
+    /// Adds an [`InteractionBuilder`] to the batch, keyed by its position.
+    ///
+    /// The builder is built when the batch is executed, running its content filters; build
+    /// errors and rejected content are reported as [`Error::BuildInteraction`].
+    pub fn with_interaction(mut self, interaction: InteractionBuilder) -> Self {
+        self.requests
+            .push((None, PendingInteraction::Builder(interaction)));
+        self
+    }
+
+    /// Adds an [`InteractionBuilder`] identified by a caller-supplied key.
+    pub fn with_keyed_interaction(
+        mut self,
+        key: impl Into<String>,
+        interaction: InteractionBuilder,
+    ) -> Self {
+        self.requests
+            .push((Some(key.into()), PendingInteraction::Builder(interaction)));
+        self
+    }
+
+    /// Converts every request into a keyed `BatchBuilder` for the client's model.
+    fn into_batch(self) -> Result<BatchBuilder, Error> {
+        ensure_unique_keys(&self.requests)?;
+        let expected = self
+            .client
+            .model
+            .as_str()
+            .trim_start_matches("models/")
+            .to_string();
+
+        let mut batch = BatchBuilder::new(self.client.clone()).with_name(self.display_name);
+        for (key, pending) in resolve_keys(self.requests) {
+            let request = match pending {
+                PendingInteraction::Request(request) => request,
+                PendingInteraction::Builder(builder) => builder
+                    .filtered_request()
+                    .context(BuildInteractionSnafu { key: key.clone() })?,
+            };
+
+            if let Some(model) = &request.model {
+                let model = model.trim_start_matches("models/");
+                ensure!(
+                    model == expected,
+                    ModelMismatchSnafu {
+                        key,
+                        model,
+                        expected,
+                    }
+                );
+            }
+
+            let request = GenerateContentRequest::try_from(&request)
+                .context(ConversionSnafu { key: key.clone() })?;
+            batch = batch.with_keyed_request(key, request);
+        }
+        Ok(batch)
+    }
+
+    /// Converts the requests and submits them as an inline batch.
+    #[instrument(skip_all, fields(
+        batch.display_name = self.display_name,
+        batch.size = self.requests.len()
+    ))]
+    pub async fn execute(self) -> Result<InteractionBatchHandle, Error> {
+        let handle = self.into_batch()?.execute().await?;
+        Ok(InteractionBatchHandle::new(handle))
+    }
+
+    /// Converts the requests and submits them by uploading a JSON Lines input file.
+    ///
+    /// Use this for batches whose inline payload would exceed the request size limit.
+    #[instrument(skip_all, fields(
+        batch.display_name = self.display_name,
+        batch.size = self.requests.len()
+    ))]
+    pub async fn execute_as_file(self) -> Result<InteractionBatchHandle, Error> {
+        let handle = self.into_batch()?.execute_as_file().await?;
+        Ok(InteractionBatchHandle::new(handle))
+    }
+}
@@ -0,0 +1,163 @@
+//! Handle for batches submitted through [`InteractionBatchBuilder`](super::InteractionBatchBuilder).
+//!
+//! Interaction batches run on the regular batch endpoint; this handle wraps a
+//! [`BatchHandle`] and converts each successful `generateContent` response into an
+//! [`Interaction`]. Function calls in the converted interactions get sequential ids
+//! (`call_1`, `call_2`, ...) since the batch endpoint does not assign any.
+
+use std::{collections::HashMap, result::Result};
+
+use super::handle::*;
+use super::model::*;
+use crate::{client::Error as ClientError, common::poll::PollPolicy, interactions::Interaction};
+
+/// The outcome of a single request within an interaction batch.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BatchInteractionResponseItem {
+    pub response: Result<Interaction, IndividualRequestError>,
+    pub meta: RequestMetadata,
+}
+
+impl From<BatchGenerationResponseItem> for BatchInteractionResponseItem {
+    fn from(item: BatchGenerationResponseItem) -> Self {
+        Self {
+            response: item.response.map(Interaction::from),
+            meta: item.meta,
+        }
+    }
+}
+
+/// Represents the overall status of an interaction batch operation.
+#[derive(Debug, Clone, PartialEq)]
+pub enum InteractionBatchStatus {
+    /// The operation is waiting to be processed.
+    Pending,
+    /// The operation is currently being processed.
+    Running {
+        pending_count: i64,
+        completed_count: i64,
+        failed_count: i64,
+        total_count: i64,
+    },
+    /// The operation has completed successfully.
+    Succeeded {
+        results: Vec<BatchInteractionResponseItem>,
+    },
+    /// The operation was cancelled by the user.
+    Cancelled,
+    /// The operation has expired.
+    Expired,
+}
+
+impl InteractionBatchStatus {
+    /// Consumes an [`InteractionBatchStatus::Succeeded`] status and indexes its results by
+    /// request key.
+    ///
+    /// Returns `None` for any other status.
+    pub fn into_results_by_key(self) -> Option<HashMap<String, BatchInteractionResponseItem>> {
+        match self {
+            InteractionBatchStatus::Succeeded { results } => Some(
+                results
+                    .into_iter()
+                    .map(|item| (item.meta.key.clone(), item))
+                    .collect(),
+            ),
+            _ => None,
+        }
+    }
+}
+
+impl From<BatchStatus> for InteractionBatchStatus {
+    fn from(status: BatchStatus) -> Self {
+        match status {
+            BatchStatus::Pending => InteractionBatchStatus::Pending,
+            BatchStatus::Running {
+                pending_count,
+                completed_count,
+                failed_count,
+                total_count,
+            } => InteractionBatchStatus::Running {
+                pending_count,
+                completed_count,
+                failed_count,
+                total_count,
+            },
+            BatchStatus::Succeeded { results } => InteractionBatchStatus::Succeeded {
+                results: results.into_iter().map(Into::into).collect(),
+            },
+            BatchStatus::Cancelled => InteractionBatchStatus::Cancelled,
+            BatchStatus::Expired => InteractionBatchStatus::Expired,
+        }
+    }
+}
+
+/// A handle to a long-running batch of interaction requests.
+pub struct InteractionBatchHandle {
+    inner: BatchHandle,
+}
+
+impl InteractionBatchHandle {
+    /// Wraps a batch handle whose requests were converted from interaction requests.
+    pub(crate) fn new(inner: BatchHandle) -> Self {
+        Self { inner }
+    }
+
+    /// Returns the unique resource name of the batch operation.
+    pub fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    /// Retrieves the current status of the batch, converting results into interactions.
+    pub async fn status(&self) -> Result<InteractionBatchStatus, Error> {
+        self.inner.status().await.map(Into::into)
+    }
+
+    /// Polls the batch until it succeeds, is cancelled or expires.
+    ///
+    /// Behaves like [`BatchHandle::wait`].
+    pub async fn wait(
+        &self,
+        policy: PollPolicy,
+    ) -> Result<BatchOutcome<BatchInteractionResponseItem>, Error> {
+        self.wait_with_progress(policy, |_| {}).await
+    }
+
+    /// Like [`wait`](Self::wait), but calls `on_progress` after every check that finds the
+    /// batch running.
+    pub async fn wait_with_progress(
+        &self,
+        policy: PollPolicy,
+        on_progress: impl FnMut(&BatchProgress),
+    ) -> Result<BatchOutcome<BatchInteractionResponseItem>, Error> {
+        let outcome = self.inner.wait_with_progress(policy, on_progress).await?;
+        Ok(outcome.map_results(Into::into))
+    }
+
+    /// Sends a request to the API to cancel the batch operation.
+    ///
+    /// Consumes the handle. If cancellation fails, returns the handle and error information
+    /// so it can be retried.
+    pub async fn cancel(self) -> Result<(), (Self, ClientError)> {
+        self.inner
+            .cancel()
+            .await
+            .map_err(|(inner, e)| (Self { inner }, e))
+    }
+
+    /// Deletes the batch operation resource from the server.
+    ///
+    /// This does not cancel a running batch. Consumes the handle. If deletion fails,
+    /// returns the handle and error information so it can be retried.
+    pub async fn delete(self) -> Result<(), (Self, ClientError)> {
+        self.inner
+            .delete()
+            .await
+            .map_err(|(inner, e)| (Self { inner }, e))
+    }
+
+    /// Returns the underlying batch handle, whose status reports raw `generateContent`
+    /// responses.
+    pub fn into_inner(self) -> BatchHandle {
+        self.inner
+    }
+}
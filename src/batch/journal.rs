@@ -0,0 +1,236 @@
+//! An append-only, on-disk journal of submitted batches.
+//!
+//! A [`BatchJournal`] records every batch submitted through a [`BatchBuilder`] configured with
+//! [`with_journal`](super::BatchBuilder::with_journal): the batch resource name, its display
+//! name, the uploaded input file (for file-based batches) and the keys of its requests. If the
+//! submitting process dies, a new process can call
+//! [`Gemini::resume_batches`](crate::Gemini::resume_batches) to reattach to every batch whose
+//! results have not been collected yet.
+//!
+//! The journal is a JSON Lines file with one event per line, so it can be inspected and
+//! repaired by hand. Events are only ever appended; the state of each batch is obtained by
+//! replaying the file. A final line left incomplete by a crash is ignored, and cut off before
+//! the next event is appended.
+//!
+//! Collecting results is made idempotent by [`BatchJournal::mark_collected`]: once a batch
+//! is marked, later resumes skip it.
+
+use serde::{Deserialize, Serialize};
+use snafu::ResultExt;
+use std::path::{Path, PathBuf};
+use time::OffsetDateTime;
+use tokio::io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt};
+
+use super::handle::{BatchHandle, BatchStatus, Error as HandleError};
+use super::*;
+
+/// A batch recorded in a [`BatchJournal`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct BatchRecord {
+    /// The batch resource name, e.g. `batches/xxxxxxxx`.
+    pub name: String,
+    /// The display name the batch was submitted with.
+    pub display_name: String,
+    /// The uploaded input file, for file-based batches.
+    pub input_file: Option<String>,
+    /// The keys of the batch's requests, in submission order.
+    pub keys: Vec<String>,
+    /// When the batch was submitted.
+    pub submitted_at: OffsetDateTime,
+    /// Whether the batch's results have been marked as collected.
+    pub collected: bool,
+}
+
+/// A single line of the journal file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+enum JournalEvent {
+    Submitted {
+        name: String,
+        display_name: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        input_file: Option<String>,
+        keys: Vec<String>,
+        #[serde(with = "time::serde::rfc3339")]
+        at: OffsetDateTime,
+    },
+    Collected {
+        name: String,
+        #[serde(with = "time::serde::rfc3339")]
+        at: OffsetDateTime,
+    },
+}
+
+/// An append-only journal of submitted batches, stored as a JSON Lines file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BatchJournal {
+    path: PathBuf,
+}
+
+impl BatchJournal {
+    /// Uses the journal file at `path`, which is created on the first write.
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    /// Returns the path of the journal file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Replays the journal and returns every recorded batch in submission order.
+    ///
+    /// A missing journal file is treated as empty.
+    pub async fn records(&self) -> Result<Vec<BatchRecord>, Error> {
+        let content = match tokio::fs::read_to_string(&self.path).await {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => {
+                return Err(e).context(JournalIoSnafu {
+                    path: self.path.clone(),
+                })
+            }
+        };
+
+        let mut records: Vec<BatchRecord> = Vec::new();
+        let mut lines = content.split_inclusive('\n').enumerate().peekable();
+        while let Some((index, line)) = lines.next() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let event = match serde_json::from_str::<JournalEvent>(line) {
+                Ok(event) => event,
+                // The last write was interrupted before its newline reached the disk.
+                Err(_) if lines.peek().is_none() && !line.ends_with('\n') => break,
+                Err(source) => {
+                    return Err(source).context(JournalParseSnafu {
+                        path: self.path.clone(),
+                        line: index + 1,
+                    })
+                }
+            };
+
+            match event {
+                JournalEvent::Submitted {
+                    name,
+                    display_name,
+                    input_file,
+                    keys,
+                    at,
+                } => {
+                    records.retain(|record| record.name != name);
+                    records.push(BatchRecord {
+                        name,
+                        display_name,
+                        input_file,
+                        keys,
+                        submitted_at: at,
+                        collected: false,
+                    });
+                }
+                JournalEvent::Collected { name, .. } => {
+                    if let Some(record) = records.iter_mut().find(|r| r.name == name) {
+                        record.collected = true;
+                    }
+                }
+            }
+        }
+        Ok(records)
+    }
+
+    /// Returns the recorded batches whose results have not been collected yet.
+    pub async fn pending(&self) -> Result<Vec<BatchRecord>, Error> {
+        let mut records = self.records().await?;
+        records.retain(|record| !record.collected);
+        Ok(records)
+    }
+
+    /// Records a newly submitted batch.
+    pub(crate) async fn record_submitted(
+        &self,
+        name: &str,
+        display_name: &str,
+        input_file: Option<&str>,
+        keys: Vec<String>,
+    ) -> Result<(), Error> {
+        self.append(&JournalEvent::Submitted {
+            name: name.to_string(),
+            display_name: display_name.to_string(),
+            input_file: input_file.map(str::to_string),
+            keys,
+            at: OffsetDateTime::now_utc(),
+        })
+        .await
+    }
+
+    /// Marks a batch's results as collected so that later resumes skip it.
+    ///
+    /// Marking a batch more than once has no further effect.
+    pub async fn mark_collected(&self, name: &str) -> Result<(), Error> {
+        self.append(&JournalEvent::Collected {
+            name: name.to_string(),
+            at: OffsetDateTime::now_utc(),
+        })
+        .await
+    }
+
+    async fn append(&self, event: &JournalEvent) -> Result<(), Error> {
+        let mut line = serde_json::to_vec(event).context(SerializeSnafu)?;
+        line.push(b'\n');
+
+        let context = || JournalIoSnafu {
+            path: self.path.clone(),
+        };
+        let mut file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .read(true)
+            .append(true)
+            .open(&self.path)
+            .await
+            .with_context(|_| context())?;
+        Self::truncate_torn_line(&mut file)
+            .await
+            .with_context(|_| context())?;
+        file.write_all(&line).await.with_context(|_| context())?;
+        file.sync_data().await.with_context(|_| context())
+    }
+
+    /// Cuts off a final line left without its newline by an interrupted write, so the next
+    /// event starts on a line of its own.
+    async fn truncate_torn_line(file: &mut tokio::fs::File) -> std::io::Result<()> {
+        let len = file.metadata().await?.len();
+        if len == 0 {
+            return Ok(());
+        }
+        file.seek(std::io::SeekFrom::Start(len - 1)).await?;
+        if file.read_u8().await? == b'\n' {
+            return Ok(());
+        }
+
+        let mut content = Vec::new();
+        file.rewind().await?;
+        file.read_to_end(&mut content).await?;
+        let end = content
+            .iter()
+            .rposition(|&byte| byte == b'\n')
+            .map_or(0, |newline| newline + 1);
+        tracing::warn!(
+            bytes = len as usize - end,
+            "dropping torn line from batch journal"
+        );
+        file.set_len(end as u64).await
+    }
+}
+
+/// An uncollected batch from a journal, reattached by
+/// [`Gemini::resume_batches`](crate::Gemini::resume_batches).
+pub struct ResumedBatch {
+    /// What the journal recorded about the batch.
+    pub record: BatchRecord,
+    /// A handle to the batch.
+    pub handle: BatchHandle,
+    /// The batch status at the time of resuming.
+    ///
+    /// Failing to fetch one batch's status does not prevent the others from resuming.
+    pub status: Result<BatchStatus, HandleError>,
+}
@@ -3,14 +3,99 @@
 use snafu::Snafu;
 
 pub mod builder;
-pub use builder::BatchBuilder;
+pub use builder::{BatchBuilder, ShardLimits};
+pub mod embed_builder;
+pub use embed_builder::EmbedBatchBuilder;
+pub mod embed_handle;
+pub use embed_handle::{BatchEmbeddingResponseItem, EmbedBatchHandle, EmbedBatchStatus};
 pub mod handle;
 pub use handle::*;
+pub mod journal;
+pub use journal::{BatchJournal, BatchRecord, ResumedBatch};
+pub mod interaction_builder;
+pub use interaction_builder::InteractionBatchBuilder;
+pub mod interaction_handle;
+pub use interaction_handle::{
+    BatchInteractionResponseItem, InteractionBatchHandle, InteractionBatchStatus,
+};
 pub mod model;
+pub mod sharded_handle;
+pub use sharded_handle::ShardedBatchHandle;
 
 #[derive(Debug, Snafu)]
 pub enum Error {
-    Client { source: crate::client::Error },
-    File { source: crate::files::Error },
-    Serialize { source: serde_json::Error },
+    Client {
+        source: crate::client::Error,
+    },
+    File {
+        source: crate::files::Error,
+    },
+    Serialize {
+        source: serde_json::Error,
+    },
+    #[snafu(display("duplicate batch request key '{key}'"))]
+    DuplicateKey {
+        key: String,
+    },
+    #[snafu(display("batch request '{key}' has invalid safety settings"))]
+    InvalidSafetySettings {
+        source: crate::safety::Error,
+        key: String,
+    },
+    #[snafu(display("failed to access batch journal '{}'", path.display()))]
+    JournalIo {
+        source: std::io::Error,
+        path: std::path::PathBuf,
+    },
+    #[snafu(display("failed to parse line {line} of batch journal '{}'", path.display()))]
+    JournalParse {
+        source: serde_json::Error,
+        path: std::path::PathBuf,
+        line: usize,
+    },
+    #[snafu(display("batch request '{key}' is {size} bytes, above the {limit} byte limit"))]
+    RequestTooLarge {
+        key: String,
+        size: usize,
+        limit: usize,
+    },
+    #[snafu(display(
+        "failed to submit batch job {shard}; {} earlier jobs are running",
+        submitted.shard_count()
+    ))]
+    PartialSubmission {
+        #[snafu(source(from(Error, Box::new)))]
+        source: Box<Error>,
+        /// The 1-based number of the job that failed.
+        shard: usize,
+        /// The jobs submitted before the failure.
+        submitted: Box<ShardedBatchHandle>,
+    },
+    #[snafu(display("batch job '{name}' is running but could not be journaled"))]
+    Unjournaled {
+        #[snafu(source(from(Error, Box::new)))]
+        source: Box<Error>,
+        /// The job missing from the journal.
+        name: String,
+        /// Every job submitted so far, including `name`.
+        submitted: Box<ShardedBatchHandle>,
+    },
+    #[snafu(display("failed to build interaction request '{key}'"))]
+    BuildInteraction {
+        source: crate::client::Error,
+        key: String,
+    },
+    #[snafu(display("interaction request '{key}' cannot be batched"))]
+    Conversion {
+        source: crate::interactions::ConversionError,
+        key: String,
+    },
+    #[snafu(display(
+        "interaction request '{key}' targets '{model}' but the batch runs on '{expected}'"
+    ))]
+    ModelMismatch {
+        key: String,
+        model: String,
+        expected: String,
+    },
 }
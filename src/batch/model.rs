@@ -1,8 +1,10 @@
 use serde::{Deserialize, Serialize};
 use snafu::Snafu;
+use std::collections::HashMap;
 use time::OffsetDateTime;
 
 use crate::common::serde::*;
+use crate::embedding::{ContentEmbedding, ContentEmbeddingResponse, EmbedContentRequest};
 use crate::generation::{GenerateContentRequest, GenerationResponse};
 use crate::Model;
 
@@ -12,8 +14,7 @@ pub struct BatchRequestFileItem {
     /// Batch generation request (wrapped in request field for API compatibility)
     pub request: GenerateContentRequest,
     /// Batch request unique identifier
-    #[serde(with = "key_as_string")]
-    pub key: usize,
+    pub key: String,
 }
 
 /// Batch file response line JSON representation.
@@ -23,8 +24,7 @@ pub struct BatchResponseFileItem {
     #[serde(flatten)]
     pub response: BatchGenerateContentResponseItem,
     /// Batch response unique identifier
-    #[serde(with = "key_as_string")]
-    pub key: usize,
+    pub key: String,
 }
 
 impl From<BatchGenerateContentResponseItem> for Result<GenerationResponse, IndividualRequestError> {
@@ -287,7 +287,204 @@ pub struct RequestsContainer {
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct RequestMetadata {
-    /// Key for the request
-    #[serde(with = "key_as_string")]
-    pub key: usize,
+    /// Key for the request, either supplied by the caller or the request's index in the batch
+    pub key: String,
+}
+
+/// Orders request keys so that index-based keys sort numerically and custom keys lexically.
+///
+/// Numeric keys always sort before custom ones, so this only matches submission order for
+/// batches built purely with [`BatchBuilder::with_request`](super::BatchBuilder::with_request).
+pub(crate) fn compare_keys(a: &str, b: &str) -> std::cmp::Ordering {
+    match (a.parse::<u64>(), b.parse::<u64>()) {
+        (Ok(a), Ok(b)) => a.cmp(&b),
+        (Ok(_), Err(_)) => std::cmp::Ordering::Less,
+        (Err(_), Ok(_)) => std::cmp::Ordering::Greater,
+        (Err(_), Err(_)) => a.cmp(b),
+    }
+}
+
+/// Sorts batch results by the position of their key in `order`, the keys in submission order.
+///
+/// Without an order, or for keys missing from it, results fall back to [`compare_keys`].
+pub(crate) fn sort_results<T>(
+    results: &mut [T],
+    order: Option<&[String]>,
+    key: impl Fn(&T) -> &str,
+) {
+    let position: HashMap<&str, usize> = order
+        .unwrap_or_default()
+        .iter()
+        .enumerate()
+        .map(|(index, key)| (key.as_str(), index))
+        .collect();
+    let rank = |key: &str| position.get(key).copied().unwrap_or(usize::MAX);
+    results.sort_by(|a, b| {
+        let (a, b) = (key(a), key(b));
+        rank(a).cmp(&rank(b)).then_with(|| compare_keys(a, b))
+    });
+}
+
+/// Embedding batch file request line JSON representation.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EmbedBatchRequestFileItem {
+    /// Embedding request (wrapped in request field for API compatibility)
+    pub request: EmbedContentRequest,
+    /// Batch request unique identifier
+    pub key: String,
+}
+
+/// Embedding batch file response line JSON representation.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EmbedBatchResponseFileItem {
+    /// Embedding response (wrapped in response field for API compatibility)
+    #[serde(flatten)]
+    pub response: EmbedContentResponseItem,
+    /// Batch response unique identifier
+    pub key: String,
+}
+
+/// An item in an embedding batch response.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum EmbedContentResponseItem {
+    /// Successful response item
+    Response(ContentEmbeddingResponse),
+    /// Error response item
+    Error(IndividualRequestError),
+}
+
+impl From<EmbedContentResponseItem> for Result<ContentEmbedding, IndividualRequestError> {
+    fn from(response: EmbedContentResponseItem) -> Self {
+        match response {
+            EmbedContentResponseItem::Response(r) => Ok(r.embedding),
+            EmbedContentResponseItem::Error(err) => Err(err),
+        }
+    }
+}
+
+/// Represents a single response item within an inlined embedding batch response.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InlinedEmbedContentResponseItem {
+    /// Request metadata containing the original key
+    pub metadata: RequestMetadata,
+    /// The embedding or error for this batch item
+    #[serde(flatten)]
+    pub result: EmbedContentResponseItem,
+}
+
+/// A container for inlined embedding responses.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InlinedEmbedContentResponses {
+    /// The list of embedding batch response items
+    pub inlined_responses: Vec<InlinedEmbedContentResponseItem>,
+}
+
+/// Represents the response of an embedding batch operation.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum EmbedBatchOperationResponse {
+    /// Response with inlined responses
+    #[serde(rename_all = "camelCase")]
+    InlinedResponses {
+        inlined_responses: InlinedEmbedContentResponses,
+    },
+    /// Response with a file containing results
+    #[serde(rename_all = "camelCase")]
+    ResponsesFile { responses_file: String },
+}
+
+/// Represents the result of a completed embedding batch operation.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum EmbedOperationResult {
+    /// Successful operation result
+    Response(EmbedBatchOperationResponse),
+    /// Failed operation result
+    Error(OperationError),
+}
+
+impl From<EmbedOperationResult> for Result<EmbedBatchOperationResponse, OperationError> {
+    fn from(operation: EmbedOperationResult) -> Self {
+        match operation {
+            EmbedOperationResult::Response(response) => Ok(response),
+            EmbedOperationResult::Error(error) => Err(error),
+        }
+    }
+}
+
+/// Represents a long-running embedding batch operation from the Gemini API.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EmbedBatchOperation {
+    /// The resource name of the operation
+    pub name: String,
+    /// Metadata about the batch operation
+    pub metadata: BatchMetadata,
+    /// Whether the operation is complete
+    #[serde(default)]
+    pub done: bool,
+    /// The result of the operation (if complete)
+    #[serde(flatten)]
+    pub result: Option<EmbedOperationResult>,
+}
+
+/// Individual embedding batch request item
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EmbedBatchRequestItem {
+    /// The actual request
+    pub request: EmbedContentRequest,
+    /// Metadata for the request
+    pub metadata: RequestMetadata,
+}
+
+/// Container for embedding requests
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EmbedRequestsContainer {
+    /// List of requests
+    pub requests: Vec<EmbedBatchRequestItem>,
+}
+
+/// Input configuration for embedding batch requests
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum EmbedInputConfig {
+    /// The requests to be processed in the batch.
+    Requests(EmbedRequestsContainer),
+    /// The name of the File containing the input requests.
+    FileName(String),
+}
+
+impl EmbedInputConfig {
+    /// Returns the batch size of the input configuration.
+    ///
+    /// Returns `None` if the input configuration is a file name.
+    pub fn batch_size(&self) -> Option<usize> {
+        match self {
+            EmbedInputConfig::Requests(container) => Some(container.requests.len()),
+            EmbedInputConfig::FileName(_) => None,
+        }
+    }
+}
+
+/// Configuration for an embedding batch
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EmbedBatchConfig {
+    /// Display name of the batch
+    pub display_name: String,
+    /// Input configuration
+    pub input_config: EmbedInputConfig,
+}
+
+/// Request body for `asyncBatchEmbedContent`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AsyncBatchEmbedContentRequest {
+    /// The embedding batch configuration
+    pub batch: EmbedBatchConfig,
 }
@@ -0,0 +1,235 @@
+//! Handle for batches submitted through [`BatchBuilder::execute_auto`](super::BatchBuilder::execute_auto).
+//!
+//! A large batch may be split into several batch jobs ("shards"). A [`ShardedBatchHandle`]
+//! tracks all of them and presents a single [`BatchStatus`]:
+//!
+//! - while any shard is queued or running, the status is `Running` with counters summed across
+//!   shards (or `Pending` if no shard has started);
+//! - once every shard has finished, the status is `Expired` if any shard expired, `Cancelled`
+//!   if any shard was cancelled, and `Succeeded` otherwise, with the results of all shards
+//!   merged back into the order in which the requests were added to the builder.
+//!
+//! Use [`ShardedBatchHandle::shard_statuses`] to inspect shards individually, for example to
+//! salvage the results of the shards that did succeed.
+
+use futures::future::try_join_all;
+use std::{result::Result, sync::Arc};
+use tracing::instrument;
+
+use super::handle::*;
+use super::model::sort_results;
+use crate::{
+    client::{Error as ClientError, GeminiClient},
+    common::poll::PollPolicy,
+};
+
+/// One batch job within a sharded batch.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Shard {
+    name: String,
+    keys: Vec<String>,
+}
+
+impl Shard {
+    pub(crate) fn new(name: String, keys: Vec<String>) -> Self {
+        Self { name, keys }
+    }
+}
+
+/// A handle to a batch that may have been split into several batch jobs.
+#[derive(Debug)]
+pub struct ShardedBatchHandle {
+    shards: Vec<Shard>,
+    /// Request keys in the order the requests were added.
+    order: Vec<String>,
+    client: Arc<GeminiClient>,
+}
+
+impl ShardedBatchHandle {
+    pub(crate) fn new(shards: Vec<Shard>, order: Vec<String>, client: Arc<GeminiClient>) -> Self {
+        Self {
+            shards,
+            order,
+            client,
+        }
+    }
+
+    /// Returns the resource names of the underlying batch jobs.
+    pub fn names(&self) -> Vec<&str> {
+        self.shards
+            .iter()
+            .map(|shard| shard.name.as_str())
+            .collect()
+    }
+
+    /// Returns the number of batch jobs the requests were split into.
+    pub fn shard_count(&self) -> usize {
+        self.shards.len()
+    }
+
+    /// Returns the keys of the requests submitted in the batch job called `name`.
+    pub fn shard_keys(&self, name: &str) -> Option<&[String]> {
+        self.shards
+            .iter()
+            .find(|shard| shard.name == name)
+            .map(|shard| shard.keys.as_slice())
+    }
+
+    /// Returns handles to the underlying batch jobs.
+    pub fn handles(&self) -> Vec<BatchHandle> {
+        self.shards
+            .iter()
+            .map(|shard| {
+                BatchHandle::new(shard.name.clone(), self.client.clone())
+                    .with_order(shard.keys.clone())
+            })
+            .collect()
+    }
+
+    /// Retrieves the status of every batch job, paired with the job's name.
+    pub async fn shard_statuses(&self) -> Result<Vec<(String, BatchStatus)>, Error> {
+        let statuses = try_join_all(self.handles().into_iter().map(|handle| async move {
+            let status = handle.status().await?;
+            Ok::<_, Error>((handle.name, status))
+        }))
+        .await?;
+        Ok(statuses)
+    }
+
+    /// Retrieves the combined status of all batch jobs.
+    #[instrument(skip_all, fields(batch.shards = self.shards.len()))]
+    pub async fn status(&self) -> Result<BatchStatus, Error> {
+        let statuses = self.shard_statuses().await?;
+        Ok(combine_statuses(
+            &self.shards,
+            &self.order,
+            statuses.into_iter().map(|(_, status)| status),
+        ))
+    }
+
+    /// Polls all batch jobs until every one of them has finished.
+    ///
+    /// Behaves like [`BatchHandle::wait`], using the combined status.
+    pub async fn wait(&self, policy: PollPolicy) -> Result<BatchOutcome, Error> {
+        self.wait_with_progress(policy, |_| {}).await
+    }
+
+    /// Like [`wait`](Self::wait), but calls `on_progress` with the summed counters after every
+    /// check that finds a job still running.
+    pub async fn wait_with_progress(
+        &self,
+        policy: PollPolicy,
+        on_progress: impl FnMut(&BatchProgress),
+    ) -> Result<BatchOutcome, Error> {
+        let name = self.names().join(",");
+        wait_for_outcome(
+            &name,
+            &policy,
+            || async { self.status().await.map(PollState::from) },
+            on_progress,
+        )
+        .await
+    }
+
+    /// Sends a cancellation request for every batch job.
+    ///
+    /// Consumes the handle. If a request fails, returns a handle to the jobs that have not
+    /// been cancelled yet, together with the error, so it can be retried.
+    pub async fn cancel(mut self) -> Result<(), (Self, ClientError)> {
+        while let Some(shard) = self.shards.first() {
+            if let Err(e) = self.client.cancel_batch_operation(&shard.name).await {
+                return Err((self, e));
+            }
+            self.shards.remove(0);
+        }
+        Ok(())
+    }
+
+    /// Deletes every batch job from the server.
+    ///
+    /// Consumes the handle. If a request fails, returns a handle to the jobs that have not
+    /// been deleted yet, together with the error, so it can be retried.
+    pub async fn delete(mut self) -> Result<(), (Self, ClientError)> {
+        while let Some(shard) = self.shards.first() {
+            if let Err(e) = self.client.delete_batch_operation(&shard.name).await {
+                return Err((self, e));
+            }
+            self.shards.remove(0);
+        }
+        Ok(())
+    }
+}
+
+/// Combines per-shard statuses, given in shard order, into a single status.
+pub(crate) fn combine_statuses(
+    shards: &[Shard],
+    order: &[String],
+    statuses: impl IntoIterator<Item = BatchStatus>,
+) -> BatchStatus {
+    let mut progress = BatchProgress::default();
+    let mut started = false;
+    let mut unfinished = false;
+    let mut cancelled = false;
+    let mut expired = false;
+    let mut results = Vec::with_capacity(order.len());
+
+    for (shard, status) in shards.iter().zip(statuses) {
+        let size = shard.keys.len() as i64;
+        progress.total_count += size;
+        match status {
+            BatchStatus::Pending => {
+                unfinished = true;
+                progress.pending_count += size;
+            }
+            BatchStatus::Running {
+                pending_count,
+                completed_count,
+                failed_count,
+                ..
+            } => {
+                unfinished = true;
+                started = true;
+                progress.pending_count += pending_count;
+                progress.completed_count += completed_count;
+                progress.failed_count += failed_count;
+            }
+            BatchStatus::Succeeded {
+                results: shard_results,
+            } => {
+                started = true;
+                for item in &shard_results {
+                    if item.response.is_ok() {
+                        progress.completed_count += 1;
+                    } else {
+                        progress.failed_count += 1;
+                    }
+                }
+                results.extend(shard_results);
+            }
+            BatchStatus::Cancelled => cancelled = true,
+            BatchStatus::Expired => expired = true,
+        }
+    }
+
+    if unfinished {
+        return if started {
+            BatchStatus::Running {
+                pending_count: progress.pending_count,
+                completed_count: progress.completed_count,
+                failed_count: progress.failed_count,
+                total_count: progress.total_count,
+            }
+        } else {
+            BatchStatus::Pending
+        };
+    }
+    if expired {
+        return BatchStatus::Expired;
+    }
+    if cancelled {
+        return BatchStatus::Cancelled;
+    }
+
+    sort_results(&mut results, Some(order), |item| &item.meta.key);
+    BatchStatus::Succeeded { results }
+}
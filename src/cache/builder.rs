@@ -2,15 +2,24 @@ use std::sync::Arc;
 use std::time::Duration;
 use tracing::instrument;
 
-use snafu::ResultExt;
+use sha2::{Digest, Sha256};
+use snafu::{OptionExt, ResultExt};
 
-use crate::client::GeminiClient;
+use crate::client::{GeminiClient, Model};
+#[allow(deprecated)]
+use crate::generation::GenerateContentRequest;
+use crate::interactions::{
+    convert::{contents_to_content, steps_to_contents, tools_to_tools},
+    InteractionContent, InteractionTool, Step,
+};
 use crate::models::Content;
 
+use super::estimate::{CacheEstimate, CachePricing};
 use super::handle::*;
 use super::model::*;
 use super::*;
 
+use crate::safety::SafetyPolicy;
 use crate::tools::Tool;
 use crate::tools::ToolConfig;
 
@@ -18,12 +27,14 @@ use crate::tools::ToolConfig;
 #[derive(Clone)]
 pub struct CacheBuilder {
     client: Arc<GeminiClient>,
+    model: Option<Model>,
     display_name: Option<String>,
     contents: Vec<Content>,
     system_instruction: Option<Content>,
     tools: Vec<Tool>,
     tool_config: Option<ToolConfig>,
     expiration: Option<CacheExpirationRequest>,
+    safety_policy: Option<SafetyPolicy>,
 }
 
 impl CacheBuilder {
@@ -31,12 +42,14 @@ impl CacheBuilder {
     pub(crate) fn new(client: Arc<GeminiClient>) -> Self {
         Self {
             client,
+            model: None,
             display_name: None,
             contents: Vec::new(),
             system_instruction: None,
             tools: Vec::new(),
             tool_config: None,
             expiration: None,
+            safety_policy: None,
         }
     }
 
@@ -56,6 +69,14 @@ impl CacheBuilder {
         Ok(self)
     }
 
+    /// Set the model the cache is created for, instead of the client's model.
+    ///
+    /// Caches can only be used by requests to the same model.
+    pub fn with_model<M: Into<Model>>(mut self, model: M) -> Self {
+        self.model = Some(model.into());
+        self
+    }
+
     /// Set the system instruction for the cached content.
     pub fn with_system_instruction<S: Into<String>>(mut self, instruction: S) -> Self {
         self.system_instruction = Some(Content::text(instruction.into()));
@@ -100,12 +121,57 @@ impl CacheBuilder {
         self
     }
 
+    /// Add Interactions API content to the cached content as a single user turn.
+    pub fn with_interaction_content(
+        mut self,
+        content: Vec<InteractionContent>,
+    ) -> Result<Self, Error> {
+        let content = contents_to_content(&content).context(ConversionSnafu)?;
+        self.contents.push(content);
+        Ok(self)
+    }
+
+    /// Add an Interactions API step history to the cached content.
+    ///
+    /// User input and function results become user turns; model output, thoughts, function
+    /// calls and code execution become model turns. Server-side tool steps are skipped.
+    pub fn with_steps(mut self, steps: Vec<Step>) -> Result<Self, Error> {
+        let contents = steps_to_contents(&steps).context(ConversionSnafu)?;
+        self.contents.extend(contents);
+        Ok(self)
+    }
+
+    /// Add Interactions API tools to the cached content.
+    ///
+    /// Fails for tools that only exist in the Interactions API, such as MCP servers.
+    pub fn with_interaction_tools(mut self, tools: Vec<InteractionTool>) -> Result<Self, Error> {
+        let (tools, tool_config) = tools_to_tools(&tools).context(ConversionSnafu)?;
+        self.tools.extend(tools);
+        if let Some(tool_config) = tool_config {
+            self.tool_config = Some(tool_config);
+        }
+        Ok(self)
+    }
+
     /// Set the tool configuration.
     pub fn with_tool_config(mut self, tool_config: ToolConfig) -> Self {
         self.tool_config = Some(tool_config);
         self
     }
 
+    /// Attach a safety policy to the cache.
+    ///
+    /// Cached content does not store safety settings, so the policy is not sent when the
+    /// cache is created. Instead, the returned [`CachedContentHandle`] carries it, and
+    /// requests that use the handle apply it unless they set their own safety settings.
+    /// Interactions executed with such a handle fail with
+    /// [`ClientError::SafetySettingsUnsupported`](crate::ClientError::SafetySettingsUnsupported);
+    /// batch them instead.
+    pub fn with_safety_policy(mut self, policy: SafetyPolicy) -> Self {
+        self.safety_policy = Some(policy);
+        self
+    }
+
     /// Set the TTL (Time To Live) for the cached content.
     /// The cache will automatically expire after this duration.
     pub fn with_ttl(mut self, ttl: Duration) -> Self {
@@ -119,18 +185,99 @@ impl CacheBuilder {
         self
     }
 
-    /// Execute the cache creation request.
-    #[instrument(skip_all, fields(
-        display.name = self.display_name,
-        messages.count = self.contents.len(),
-        tools.count = self.tools.len(),
-        system_instruction.present = self.system_instruction.is_some(),
-    ))]
-    pub async fn execute(self) -> Result<CachedContentHandle, Error> {
-        let model = self.client.model.clone();
+    /// Returns the model the cache will be created for.
+    pub(crate) fn model(&self) -> Model {
+        self.model
+            .clone()
+            .unwrap_or_else(|| self.client.model.clone())
+    }
+
+    /// Computes a stable fingerprint of everything that determines the cache's content.
+    ///
+    /// The display name and expiration are not part of the fingerprint.
+    pub(crate) fn fingerprint(&self) -> String {
+        let identity = serde_json::json!({
+            "model": self.model().as_str(),
+            "contents": self.contents,
+            "systemInstruction": self.system_instruction,
+            "tools": self.tools,
+            "toolConfig": self.tool_config,
+        });
+        let digest = Sha256::digest(identity.to_string().as_bytes());
+        digest.iter().map(|byte| format!("{byte:02x}")).collect()
+    }
+
+    /// Estimates whether caching the builder's content pays off over `reuses` requests.
+    ///
+    /// Tokens are counted with the `countTokens` endpoint of the cache's model and priced with
+    /// [`CachePricing::for_model`]. The TTL set on the builder is used for storage costs,
+    /// defaulting to one hour. Fails with [`Error::UnknownPricing`] for models without list
+    /// prices; use [`estimate_with_pricing`](Self::estimate_with_pricing) for those.
+    pub async fn estimate(&self, reuses: u32) -> Result<CacheEstimate, Error> {
+        let model = self.model();
+        let pricing = CachePricing::for_model(model.as_str()).context(UnknownPricingSnafu {
+            model: model.as_str().to_string(),
+        })?;
+        self.estimate_with_pricing(reuses, pricing).await
+    }
+
+    /// Like [`estimate`](Self::estimate), with explicit prices.
+    #[allow(deprecated)]
+    #[instrument(skip_all, fields(cache.reuses = reuses, cache.tokens))]
+    pub async fn estimate_with_pricing(
+        &self,
+        reuses: u32,
+        pricing: CachePricing,
+    ) -> Result<CacheEstimate, Error> {
+        let model = self.model();
+        let request = GenerateContentRequest {
+            contents: self.contents.clone(),
+            generation_config: None,
+            safety_settings: None,
+            tools: (!self.tools.is_empty()).then(|| self.tools.clone()),
+            tool_config: self.tool_config.clone(),
+            system_instruction: self.system_instruction.clone(),
+            cached_content: None,
+        };
+        let counted = self
+            .client
+            .count_tokens_for_model(&model, request)
+            .await
+            .map_err(Box::new)
+            .context(ClientSnafu)?;
+        tracing::Span::current().record("cache.tokens", counted.total_tokens);
+
+        let ttl = match &self.expiration {
+            Some(CacheExpirationRequest::Ttl { ttl }) => ttl
+                .trim_end_matches('s')
+                .parse::<f64>()
+                .ok()
+                .and_then(|secs| Duration::try_from_secs_f64(secs).ok()),
+            Some(CacheExpirationRequest::ExpireTime { expire_time }) => (*expire_time
+                - time::OffsetDateTime::now_utc())
+            .try_into()
+            .ok(),
+            None => None,
+        }
+        .unwrap_or(Duration::from_secs(3600));
+
+        Ok(CacheEstimate::new(
+            model.as_str().to_string(),
+            counted.total_tokens,
+            reuses,
+            ttl,
+            pricing,
+        ))
+    }
+
+    /// Constructs the `CreateCachedContentRequest` without sending it.
+    ///
+    /// Fails if no TTL or expire time was set.
+    pub fn build(self) -> Result<CreateCachedContentRequest, Error> {
+        let model = self.model();
         let expiration = self.expiration.ok_or(Error::MissingExpiration)?;
 
-        let cached_content = CreateCachedContentRequest {
+        Ok(CreateCachedContentRequest {
             display_name: self.display_name,
             model,
             contents: if self.contents.is_empty() {
@@ -146,17 +293,30 @@ impl CacheBuilder {
             system_instruction: self.system_instruction,
             tool_config: self.tool_config,
             expiration,
-        };
+        })
+    }
 
-        let response = self
-            .client
-            .create_cached_content(cached_content)
+    /// Execute the cache creation request.
+    #[instrument(skip_all, fields(
+        display.name = self.display_name,
+        messages.count = self.contents.len(),
+        tools.count = self.tools.len(),
+        system_instruction.present = self.system_instruction.is_some(),
+    ))]
+    pub async fn execute(self) -> Result<CachedContentHandle, Error> {
+        let client = self.client.clone();
+        let safety_policy = self.safety_policy.clone();
+        let response = client
+            .create_cached_content(self.build()?)
             .await
             .map_err(Box::new)
             .context(ClientSnafu)?;
 
-        let cache_name = response.name;
-
-        Ok(CachedContentHandle::new(cache_name, self.client))
+        let mut handle = CachedContentHandle::new(response.name, client)
+            .with_model(response.model.as_str().to_string());
+        if let Some(policy) = safety_policy {
+            handle = handle.with_safety_policy(policy);
+        }
+        Ok(handle)
     }
 }
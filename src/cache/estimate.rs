@@ -0,0 +1,134 @@
+//! Cost/benefit estimates for cached content, produced by
+//! [`CacheBuilder::estimate`](super::CacheBuilder::estimate).
+//!
+//! Caching a prompt trades a one-off creation charge and an hourly storage charge for a
+//! discounted rate every time the cached tokens are reused. The estimate compares:
+//!
+//! - **without a cache**: every use pays the regular input rate for the prompt;
+//! - **with a cache**: the prompt is paid once at the regular input rate when the cache is
+//!   created, every use pays the cached input rate, and storage is paid for the whole TTL.
+//!
+//! Output tokens and the non-cached part of each request cost the same either way and are
+//! left out.
+
+use std::time::Duration;
+
+/// Per-million-token prices, in US dollars, used to estimate caching costs.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CachePricing {
+    /// Price of regular input tokens.
+    pub input_per_million: f64,
+    /// Price of input tokens served from a cache.
+    pub cached_input_per_million: f64,
+    /// Price of keeping tokens cached for one hour.
+    pub storage_per_million_per_hour: f64,
+}
+
+impl CachePricing {
+    /// Returns list prices for the model, for prompts up to 200k tokens.
+    ///
+    /// These are the published prices at the time of writing and may change; pass your own
+    /// [`CachePricing`] to [`CacheBuilder::estimate_with_pricing`](super::CacheBuilder::estimate_with_pricing)
+    /// when they do, or for models not listed here.
+    pub fn for_model(model: &str) -> Option<Self> {
+        let pricing = |input, cached, storage| CachePricing {
+            input_per_million: input,
+            cached_input_per_million: cached,
+            storage_per_million_per_hour: storage,
+        };
+        match model.trim_start_matches("models/") {
+            "gemini-2.5-pro" => Some(pricing(1.25, 0.125, 4.50)),
+            "gemini-2.5-flash" => Some(pricing(0.30, 0.03, 1.00)),
+            "gemini-2.5-flash-lite" => Some(pricing(0.10, 0.01, 1.00)),
+            "gemini-3-pro-preview" => Some(pricing(2.00, 0.20, 4.50)),
+            "gemini-3-flash-preview" => Some(pricing(0.50, 0.05, 1.00)),
+            _ => None,
+        }
+    }
+}
+
+/// Returns the smallest number of tokens the API accepts for a cache on `model`.
+///
+/// Unknown models get the largest known minimum.
+pub fn min_cache_tokens(model: &str) -> u32 {
+    let model = model.trim_start_matches("models/");
+    if model.contains("flash") {
+        1024
+    } else if model.starts_with("gemini-3-pro") {
+        2048
+    } else {
+        4096
+    }
+}
+
+/// The projected costs of caching a prompt, in US dollars.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CacheEstimate {
+    /// The model the cache would be created for.
+    pub model: String,
+    /// Tokens the cache would hold.
+    pub tokens: u32,
+    /// Smallest number of tokens the model accepts for a cache.
+    pub min_tokens: u32,
+    /// Number of requests expected to use the cache.
+    pub reuses: u32,
+    /// How long the cache would be kept.
+    pub ttl: Duration,
+    /// Cost of sending the prompt with every request instead.
+    pub cost_without_cache: f64,
+    /// Cost of creating, storing and reusing the cache.
+    pub cost_with_cache: f64,
+    /// Smallest number of reuses at which caching is cheaper, if any.
+    pub break_even_reuses: Option<u32>,
+}
+
+impl CacheEstimate {
+    pub(crate) fn new(
+        model: String,
+        tokens: u32,
+        reuses: u32,
+        ttl: Duration,
+        pricing: CachePricing,
+    ) -> Self {
+        let millions = f64::from(tokens) / 1_000_000.0;
+        let hours = ttl.as_secs_f64() / 3600.0;
+        let creation = millions * pricing.input_per_million;
+        let storage = millions * pricing.storage_per_million_per_hour * hours;
+        let per_use_saving =
+            millions * (pricing.input_per_million - pricing.cached_input_per_million);
+
+        // Caching pays off once n * per_use_saving exceeds creation + storage.
+        let break_even_reuses = (per_use_saving > 0.0)
+            .then(|| ((creation + storage) / per_use_saving).floor() + 1.0)
+            .filter(|n| *n <= f64::from(u32::MAX))
+            .map(|n| n as u32);
+
+        Self {
+            min_tokens: min_cache_tokens(&model),
+            model,
+            tokens,
+            reuses,
+            ttl,
+            cost_without_cache: f64::from(reuses) * millions * pricing.input_per_million,
+            cost_with_cache: creation
+                + storage
+                + f64::from(reuses) * millions * pricing.cached_input_per_million,
+            break_even_reuses,
+        }
+    }
+
+    /// Whether the prompt is large enough for the API to accept it as a cache.
+    pub fn meets_minimum(&self) -> bool {
+        self.tokens >= self.min_tokens
+    }
+
+    /// Money saved by caching; negative when caching costs more.
+    pub fn savings(&self) -> f64 {
+        self.cost_without_cache - self.cost_with_cache
+    }
+
+    /// Whether the cache can be created and is expected to save money.
+    pub fn is_worthwhile(&self) -> bool {
+        self.meets_minimum() && self.savings() > 0.0
+    }
+}
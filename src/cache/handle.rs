@@ -4,6 +4,7 @@ use std::sync::Arc;
 use super::model::*;
 use super::*;
 use crate::client::GeminiClient;
+use crate::safety::SafetyPolicy;
 
 /// Represents a cached content resource, providing methods to manage its lifecycle.
 ///
@@ -13,13 +14,34 @@ use crate::client::GeminiClient;
 pub struct CachedContentHandle {
     /// The unique resource name of the cached content, e.g., `cachedContents/cache-xxxxxxxx`.
     pub name: String,
+    /// The model the cache was created for, when known.
+    model: Option<String>,
+    /// The safety policy requests using the cache apply by default.
+    safety_policy: Option<SafetyPolicy>,
     client: Arc<GeminiClient>,
 }
 
 impl CachedContentHandle {
     /// Creates a new CachedContentHandle instance.
     pub(crate) fn new(name: String, client: Arc<GeminiClient>) -> Self {
-        Self { name, client }
+        Self {
+            name,
+            model: None,
+            safety_policy: None,
+            client,
+        }
+    }
+
+    /// Records the model the cache belongs to.
+    pub(crate) fn with_model(mut self, model: String) -> Self {
+        self.model = Some(model);
+        self
+    }
+
+    /// Records the safety policy the cache was created with.
+    pub(crate) fn with_safety_policy(mut self, policy: SafetyPolicy) -> Self {
+        self.safety_policy = Some(policy);
+        self
     }
 
     /// Returns the unique resource name of the cached content.
@@ -27,6 +49,28 @@ impl CachedContentHandle {
         &self.name
     }
 
+    /// Returns the model the cache was created for, e.g. `models/gemini-2.5-flash`.
+    ///
+    /// This is known for handles returned by [`CacheBuilder::execute`](super::CacheBuilder::execute)
+    /// and [`resolve_model`](Self::resolve_model), and `None` for handles created from a bare
+    /// name with [`Gemini::get_cached_content`](crate::Gemini::get_cached_content).
+    pub fn model(&self) -> Option<&str> {
+        self.model.as_deref()
+    }
+
+    /// Returns the safety policy set with
+    /// [`CacheBuilder::with_safety_policy`](super::CacheBuilder::with_safety_policy), if any.
+    pub fn safety_policy(&self) -> Option<&SafetyPolicy> {
+        self.safety_policy.as_ref()
+    }
+
+    /// Fetches the cache to learn which model it belongs to.
+    pub async fn resolve_model(mut self) -> Result<Self, Error> {
+        let cached = self.get().await?;
+        self.model = Some(cached.model.as_str().to_string());
+        Ok(self)
+    }
+
     /// Retrieves the cached content configuration by making an API call.
     pub async fn get(&self) -> Result<CachedContent, Error> {
         self.client
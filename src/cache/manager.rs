@@ -0,0 +1,499 @@
+//! Automatic lifecycle management for cached content.
+//!
+//! A [`CacheManager`] turns a [`CacheBuilder`] into a cache on demand and keeps it alive for
+//! as long as it is used:
+//!
+//! - **Deduplication.** Each cache is identified by a fingerprint of its model, contents,
+//!   system instruction and tools, and named `{prefix}-{fingerprint}`. Acquiring the same
+//!   content again reuses the cache, whether it was created by this manager or found through
+//!   the list endpoint (for example one created by an earlier process).
+//! - **Leases.** [`CacheManager::acquire`] returns a [`CacheLease`]. While any lease on a
+//!   cache is alive, [`CacheManager::maintain`] extends its TTL before it expires.
+//! - **Idle cleanup.** Caches without leases are deleted once they have been idle for
+//!   [`CacheManager::with_idle_timeout`].
+//! - **Savings.** Feeding response usage to [`CacheManager::record_usage`] or
+//!   [`CacheManager::record_interaction_usage`] accumulates how many prompt tokens were
+//!   served from cache.
+//!
+//! Maintenance runs whenever [`CacheManager::maintain`] is called, or periodically in the
+//! background via [`CacheManager::spawn_maintenance`].
+//!
+//! ```no_run
+//! # use gemini_rust::Gemini;
+//! # use std::time::Duration;
+//! # async fn example(gemini: &Gemini, corpus: &str) -> Result<(), Box<dyn std::error::Error>> {
+//! let manager = gemini.cache_manager().with_ttl(Duration::from_secs(600));
+//! let _maintenance = manager.spawn_maintenance(Duration::from_secs(60));
+//!
+//! let lease = manager
+//!     .acquire(gemini.create_cache().with_user_message(corpus))
+//!     .await?;
+//! let response = gemini
+//!     .create_interaction()
+//!     .with_cache(lease.handle())
+//!     .with_text("Summarize chapter 3")
+//!     .execute()
+//!     .await?;
+//! if let Some(usage) = &response.usage {
+//!     manager.record_interaction_usage(usage);
+//! }
+//! println!("{:?}", manager.savings());
+//! # Ok(())
+//! # }
+//! ```
+
+use snafu::ResultExt;
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+use time::OffsetDateTime;
+use tokio::task::JoinHandle;
+use tracing::{instrument, warn};
+
+use super::builder::CacheBuilder;
+use super::handle::CachedContentHandle;
+use super::model::*;
+use super::*;
+use crate::{client::GeminiClient, generation::UsageMetadata, interactions::InteractionUsage};
+
+/// A cache tracked by a [`CacheManager`].
+#[derive(Debug, Clone)]
+struct ManagedCache {
+    name: String,
+    model: String,
+    expire_time: Option<OffsetDateTime>,
+    leases: usize,
+    last_used: Instant,
+}
+
+#[derive(Debug, Default)]
+struct ManagerState {
+    /// Managed caches by fingerprint.
+    caches: HashMap<String, ManagedCache>,
+    savings: CacheSavings,
+}
+
+/// Prompt token usage accumulated by a [`CacheManager`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheSavings {
+    /// Number of responses recorded.
+    pub requests: u64,
+    /// Total prompt tokens across recorded responses, including cached ones.
+    pub prompt_tokens: u64,
+    /// Prompt tokens served from cached content.
+    pub cached_tokens: u64,
+}
+
+impl CacheSavings {
+    /// Fraction of prompt tokens served from cache, between `0.0` and `1.0`.
+    pub fn cached_ratio(&self) -> f64 {
+        if self.prompt_tokens == 0 {
+            0.0
+        } else {
+            self.cached_tokens as f64 / self.prompt_tokens as f64
+        }
+    }
+}
+
+/// What a [`CacheManager::maintain`] pass did.
+#[derive(Debug, Default)]
+pub struct MaintenanceReport {
+    /// Caches whose TTL was extended.
+    pub refreshed: Vec<String>,
+    /// Idle caches that were deleted.
+    pub deleted: Vec<String>,
+    /// Caches dropped from tracking because they expired or no longer exist on the server.
+    pub expired: Vec<String>,
+    /// Caches that could not be refreshed or deleted, with the error. They stay tracked
+    /// and are retried on the next pass.
+    pub failed: Vec<(String, Error)>,
+}
+
+/// Creates, reuses, refreshes and deletes cached content automatically.
+///
+/// `CacheManager` is cheap to clone; clones share the same set of managed caches.
+#[derive(Clone)]
+pub struct CacheManager {
+    client: Arc<GeminiClient>,
+    prefix: String,
+    ttl: Duration,
+    refresh_margin: Duration,
+    idle_timeout: Duration,
+    state: Arc<Mutex<ManagerState>>,
+    /// Serializes cache creation so concurrent acquires of the same content share one cache.
+    create_lock: Arc<tokio::sync::Mutex<()>>,
+}
+
+impl CacheManager {
+    /// Creates a manager with a 1 hour TTL, refreshed 5 minutes before expiry, and a
+    /// 10 minute idle timeout.
+    pub(crate) fn new(client: Arc<GeminiClient>) -> Self {
+        Self {
+            client,
+            prefix: "gemini-rust".to_string(),
+            ttl: Duration::from_secs(60 * 60),
+            refresh_margin: Duration::from_secs(5 * 60),
+            idle_timeout: Duration::from_secs(10 * 60),
+            state: Arc::default(),
+            create_lock: Arc::default(),
+        }
+    }
+
+    /// Sets the display name prefix of managed caches.
+    ///
+    /// Use distinct prefixes for unrelated applications sharing an API key, so that they do
+    /// not adopt each other's caches.
+    pub fn with_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.prefix = prefix.into();
+        self
+    }
+
+    /// Sets the TTL used when creating and refreshing caches.
+    ///
+    /// The refresh margin is capped at half the TTL, see
+    /// [`with_refresh_margin`](Self::with_refresh_margin).
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    /// Sets how long before expiry a leased cache is refreshed.
+    ///
+    /// Maintenance must run at least this often for leased caches to stay alive. A margin of
+    /// half the TTL or more is reduced to half the TTL, so a fresh cache is never due for a
+    /// refresh.
+    pub fn with_refresh_margin(mut self, margin: Duration) -> Self {
+        self.refresh_margin = margin;
+        self
+    }
+
+    /// Sets how long a cache without leases is kept before it is deleted.
+    pub fn with_idle_timeout(mut self, timeout: Duration) -> Self {
+        self.idle_timeout = timeout;
+        self
+    }
+
+    /// Returns the display name a cache with the given fingerprint is created under.
+    fn display_name(&self, fingerprint: &str) -> String {
+        format!("{}-{}", self.prefix, &fingerprint[..32])
+    }
+
+    /// Returns a lease on a cache holding the builder's content, creating it if needed.
+    ///
+    /// The builder's display name and expiration are replaced by the manager's.
+    #[instrument(skip_all, fields(cache.name, cache.reused))]
+    pub async fn acquire(&self, builder: CacheBuilder) -> Result<CacheLease, Error> {
+        let fingerprint = builder.fingerprint();
+        if let Some(lease) = self.lease_existing(&fingerprint) {
+            tracing::Span::current().record("cache.reused", true);
+            return Ok(lease);
+        }
+
+        let _guard = self.create_lock.lock().await;
+        // Another task may have created the cache while we waited for the lock.
+        if let Some(lease) = self.lease_existing(&fingerprint) {
+            tracing::Span::current().record("cache.reused", true);
+            return Ok(lease);
+        }
+
+        let display_name = self.display_name(&fingerprint);
+        let model = builder.model().as_str().to_string();
+        let (cache, reused) = match self.find_remote(&display_name, &model).await? {
+            Some(cache) => (cache, true),
+            None => {
+                let request = builder
+                    .with_display_name(display_name)?
+                    .with_ttl(self.ttl)
+                    .build()?;
+                let created = self
+                    .client
+                    .create_cached_content(request)
+                    .await
+                    .map_err(Box::new)
+                    .context(ClientSnafu)?;
+                let cache = ManagedCache {
+                    name: created.name,
+                    model: created.model.as_str().to_string(),
+                    expire_time: created.expiration.expire_time,
+                    leases: 0,
+                    last_used: Instant::now(),
+                };
+                (cache, false)
+            }
+        };
+        tracing::Span::current().record("cache.name", cache.name.as_str());
+        tracing::Span::current().record("cache.reused", reused);
+
+        // Leased even if it expires within the refresh margin: it was just created or found
+        // alive, and maintenance refreshes it.
+        let mut state = self.state.lock().expect("cache manager state poisoned");
+        let cache = state.caches.entry(fingerprint.clone()).insert_entry(cache);
+        Ok(self.lease(&fingerprint, cache.into_mut()))
+    }
+
+    /// Leases a tracked cache that is not about to expire.
+    fn lease_existing(&self, fingerprint: &str) -> Option<CacheLease> {
+        let mut state = self.state.lock().expect("cache manager state poisoned");
+        let cache = state.caches.get_mut(fingerprint)?;
+        if !self.outlives_margin(cache.expire_time) {
+            return None;
+        }
+        Some(self.lease(fingerprint, cache))
+    }
+
+    fn lease(&self, fingerprint: &str, cache: &mut ManagedCache) -> CacheLease {
+        cache.leases += 1;
+        cache.last_used = Instant::now();
+        CacheLease {
+            handle: CachedContentHandle::new(cache.name.clone(), self.client.clone())
+                .with_model(cache.model.clone()),
+            fingerprint: fingerprint.to_string(),
+            state: self.state.clone(),
+        }
+    }
+
+    /// The refresh margin, capped at half the TTL.
+    fn margin(&self) -> Duration {
+        self.refresh_margin.min(self.ttl / 2)
+    }
+
+    /// Whether a cache expiring at `expire_time` is still usable for another refresh margin.
+    fn outlives_margin(&self, expire_time: Option<OffsetDateTime>) -> bool {
+        expire_time
+            .is_none_or(|expire_time| expire_time - OffsetDateTime::now_utc() > self.margin())
+    }
+
+    /// Looks for a live cache with the given display name and model on the server.
+    async fn find_remote(
+        &self,
+        display_name: &str,
+        model: &str,
+    ) -> Result<Option<ManagedCache>, Error> {
+        let mut page_token = None;
+        loop {
+            let page = self
+                .client
+                .list_cached_contents(Some(1000), page_token)
+                .await
+                .map_err(Box::new)
+                .context(ClientSnafu)?;
+            let found = page.cached_contents.into_iter().find(|summary| {
+                summary.display_name.as_deref() == Some(display_name)
+                    && summary.model.as_str() == model
+                    && self.outlives_margin(summary.expiration.expire_time)
+            });
+            if let Some(summary) = found {
+                return Ok(Some(ManagedCache {
+                    name: summary.name,
+                    model: summary.model.as_str().to_string(),
+                    expire_time: summary.expiration.expire_time,
+                    leases: 0,
+                    last_used: Instant::now(),
+                }));
+            }
+            match page.next_page_token {
+                Some(token) => page_token = Some(token),
+                None => return Ok(None),
+            }
+        }
+    }
+
+    /// Refreshes leased caches that are close to expiry and deletes idle ones.
+    ///
+    /// A failure on one cache does not stop the pass; it is recorded in
+    /// [`MaintenanceReport::failed`]. Caches the server no longer knows are forgotten.
+    #[instrument(skip_all)]
+    pub async fn maintain(&self) -> MaintenanceReport {
+        let mut report = MaintenanceReport::default();
+        let snapshot: Vec<(String, ManagedCache)> = {
+            let state = self.state.lock().expect("cache manager state poisoned");
+            state
+                .caches
+                .iter()
+                .map(|(fingerprint, cache)| (fingerprint.clone(), cache.clone()))
+                .collect()
+        };
+
+        for (fingerprint, cache) in snapshot {
+            let expired = cache
+                .expire_time
+                .is_some_and(|expire_time| expire_time <= OffsetDateTime::now_utc());
+            if expired {
+                // A lease on an expired cache is useless; the next acquire recreates it.
+                self.forget(&fingerprint, &cache.name, true);
+                report.expired.push(cache.name);
+            } else if cache.leases > 0 {
+                if self.outlives_margin(cache.expire_time) {
+                    continue;
+                }
+                let result = self
+                    .client
+                    .update_cached_content(&cache.name, CacheExpirationRequest::from_ttl(self.ttl))
+                    .await;
+                match result {
+                    Ok(updated) => {
+                        let mut state = self.state.lock().expect("cache manager state poisoned");
+                        if let Some(tracked) = state.caches.get_mut(&fingerprint) {
+                            tracked.expire_time = updated.expiration.expire_time;
+                        }
+                        report.refreshed.push(cache.name);
+                    }
+                    Err(e) if is_not_found(&e) => {
+                        self.forget(&fingerprint, &cache.name, true);
+                        report.expired.push(cache.name);
+                    }
+                    Err(e) => report.failed.push((
+                        cache.name,
+                        Error::Client {
+                            source: Box::new(e),
+                        },
+                    )),
+                }
+            } else if cache.last_used.elapsed() >= self.idle_timeout {
+                match self.client.delete_cached_content(&cache.name).await {
+                    Ok(_) => {
+                        self.forget(&fingerprint, &cache.name, false);
+                        report.deleted.push(cache.name);
+                    }
+                    Err(e) if is_not_found(&e) => {
+                        self.forget(&fingerprint, &cache.name, false);
+                        report.expired.push(cache.name);
+                    }
+                    Err(e) => report.failed.push((
+                        cache.name,
+                        Error::Client {
+                            source: Box::new(e),
+                        },
+                    )),
+                }
+            }
+        }
+        report
+    }
+
+    /// Stops tracking a cache, unless it has been replaced or (without `even_if_leased`)
+    /// leased in the meantime.
+    fn forget(&self, fingerprint: &str, name: &str, even_if_leased: bool) {
+        let mut state = self.state.lock().expect("cache manager state poisoned");
+        if state
+            .caches
+            .get(fingerprint)
+            .is_some_and(|cache| cache.name == name && (even_if_leased || cache.leases == 0))
+        {
+            state.caches.remove(fingerprint);
+        }
+    }
+
+    /// Runs [`maintain`](Self::maintain) every `interval` on the Tokio runtime.
+    ///
+    /// Errors are logged and retried on the next tick. Abort the returned task to stop.
+    pub fn spawn_maintenance(&self, interval: Duration) -> JoinHandle<()> {
+        let manager = self.clone();
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(interval).await;
+                for (cache, error) in manager.maintain().await.failed {
+                    warn!(cache, %error, "cache maintenance failed");
+                }
+            }
+        })
+    }
+
+    /// Deletes every cache tracked by this manager, regardless of leases.
+    pub async fn delete_all(&self) -> Result<(), Error> {
+        let caches: Vec<(String, String)> = {
+            let state = self.state.lock().expect("cache manager state poisoned");
+            state
+                .caches
+                .iter()
+                .map(|(fingerprint, cache)| (fingerprint.clone(), cache.name.clone()))
+                .collect()
+        };
+        for (fingerprint, name) in caches {
+            self.client
+                .delete_cached_content(&name)
+                .await
+                .map_err(Box::new)
+                .context(ClientSnafu)?;
+            self.state
+                .lock()
+                .expect("cache manager state poisoned")
+                .caches
+                .remove(&fingerprint);
+        }
+        Ok(())
+    }
+
+    /// Records the prompt usage of a `generateContent` response.
+    pub fn record_usage(&self, usage: &UsageMetadata) {
+        self.record(
+            usage.prompt_token_count.unwrap_or(0).max(0) as u64,
+            usage.cached_content_token_count.unwrap_or(0).max(0) as u64,
+        );
+    }
+
+    /// Records the prompt usage of an interaction.
+    pub fn record_interaction_usage(&self, usage: &InteractionUsage) {
+        self.record(
+            usage.total_input_tokens.unwrap_or(0).max(0) as u64,
+            usage.total_cached_tokens.unwrap_or(0).max(0) as u64,
+        );
+    }
+
+    fn record(&self, prompt_tokens: u64, cached_tokens: u64) {
+        let mut state = self.state.lock().expect("cache manager state poisoned");
+        state.savings.requests += 1;
+        state.savings.prompt_tokens += prompt_tokens;
+        state.savings.cached_tokens += cached_tokens;
+    }
+
+    /// Returns the usage accumulated so far.
+    pub fn savings(&self) -> CacheSavings {
+        self.state
+            .lock()
+            .expect("cache manager state poisoned")
+            .savings
+    }
+}
+
+/// A claim on a managed cache that keeps it refreshed until dropped.
+pub struct CacheLease {
+    handle: CachedContentHandle,
+    fingerprint: String,
+    state: Arc<Mutex<ManagerState>>,
+}
+
+impl CacheLease {
+    /// Returns the handle of the leased cache, for use with
+    /// [`InteractionBuilder::with_cache`](crate::InteractionBuilder::with_cache).
+    pub fn handle(&self) -> &CachedContentHandle {
+        &self.handle
+    }
+
+    /// Returns the resource name of the leased cache.
+    pub fn name(&self) -> &str {
+        self.handle.name()
+    }
+}
+
+impl Drop for CacheLease {
+    fn drop(&mut self) {
+        if let Ok(mut state) = self.state.lock() {
+            let cache = state
+                .caches
+                .get_mut(&self.fingerprint)
+                .filter(|cache| cache.name == self.handle.name);
+            if let Some(cache) = cache {
+                cache.leases = cache.leases.saturating_sub(1);
+                cache.last_used = Instant::now();
+            }
+        }
+    }
+}
+
+/// Whether the server reported that the resource does not exist.
+fn is_not_found(error: &crate::client::Error) -> bool {
+    matches!(error, crate::client::Error::BadResponse { code: 404, .. })
+}
@@ -2,8 +2,12 @@ use snafu::Snafu;
 
 pub mod builder;
 pub use builder::CacheBuilder;
+pub mod estimate;
+pub use estimate::{min_cache_tokens, CacheEstimate, CachePricing};
 pub mod handle;
 pub use handle::CachedContentHandle;
+pub mod manager;
+pub use manager::{CacheLease, CacheManager, CacheSavings, MaintenanceReport};
 pub mod model;
 
 #[derive(Debug, Snafu)]
@@ -18,4 +22,12 @@ pub enum Error {
 
     #[snafu(display("expiration (TTL or expire time) is required for cache creation"))]
     MissingExpiration,
+
+    #[snafu(display("no list prices known for model '{model}'"))]
+    UnknownPricing { model: String },
+
+    #[snafu(display("interaction input cannot be cached"))]
+    Conversion {
+        source: crate::interactions::ConversionError,
+    },
 }
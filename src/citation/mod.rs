@@ -0,0 +1,205 @@
+//! Resolving citations in model output.
+//!
+//! Grounded responses and interactions describe their citations as offsets into the output
+//! text plus a list of sources. [`CitationResolver`] turns either form into an
+//! [`AnnotatedText`]: the text, the spans that cite something as byte ranges that are safe
+//! to slice, and the sources they cite.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # use gemini_rust::prelude::*;
+//! # async fn example(gemini: &Gemini) -> Result<(), Box<dyn std::error::Error>> {
+//! let interaction = gemini
+//!     .create_interaction()
+//!     .with_model("gemini-2.5-flash")
+//!     .with_text("Who won the 2024 Tour de France?")
+//!     .execute()
+//!     .await?;
+//!
+//! println!("{}", interaction.annotated_text().render_with_references());
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::ops::Range;
+
+mod offset;
+mod resolver;
+
+pub use offset::OffsetUnit;
+pub use resolver::CitationResolver;
+
+/// Where a cited source comes from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SourceKind {
+    /// A web page.
+    Web,
+    /// A place on Google Maps.
+    Place,
+    /// A document, e.g. from a file search store.
+    Document,
+    /// A source the response did not describe further.
+    Other,
+}
+
+/// A source cited by the output.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CitedSource {
+    pub kind: SourceKind,
+    pub title: Option<String>,
+    pub uri: Option<String>,
+    /// The resource name of the document, for document sources in a file search store.
+    pub document_name: Option<String>,
+}
+
+impl CitedSource {
+    /// A short human readable label: the title, document name or URI, whichever is known.
+    pub fn label(&self) -> &str {
+        self.title
+            .as_deref()
+            .or(self.document_name.as_deref())
+            .or(self.uri.as_deref())
+            .unwrap_or("Unknown source")
+    }
+}
+
+impl fmt::Display for CitedSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label = self.label();
+        f.write_str(label)?;
+        match self.uri.as_deref() {
+            Some(uri) if uri != label => write!(f, " ({uri})"),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// A span of the output that cites one or more sources.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CitedSpan {
+    /// The byte offset of the start of the span in [`AnnotatedText::text`].
+    pub start: usize,
+    /// The byte offset of the end of the span in [`AnnotatedText::text`].
+    pub end: usize,
+    /// Indices into [`AnnotatedText::sources`].
+    pub sources: Vec<usize>,
+}
+
+impl CitedSpan {
+    pub fn range(&self) -> Range<usize> {
+        self.start..self.end
+    }
+}
+
+/// Output text with its citations resolved.
+///
+/// Span offsets are always UTF-8 byte offsets on character boundaries, whatever unit the
+/// API reported them in, so `&text[span.range()]` never panics.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AnnotatedText {
+    pub text: String,
+    /// The cited spans, ordered by position.
+    pub spans: Vec<CitedSpan>,
+    pub sources: Vec<CitedSource>,
+}
+
+impl AnnotatedText {
+    pub fn has_citations(&self) -> bool {
+        !self.spans.is_empty()
+    }
+
+    /// The text covered by a span.
+    pub fn span_text(&self, span: &CitedSpan) -> &str {
+        &self.text[span.range()]
+    }
+
+    /// The sources a span cites.
+    pub fn span_sources<'a>(
+        &'a self,
+        span: &'a CitedSpan,
+    ) -> impl Iterator<Item = &'a CitedSource> + 'a {
+        span.sources
+            .iter()
+            .filter_map(|&index| self.sources.get(index))
+    }
+
+    /// The range of a span in another offset unit, e.g. UTF-16 code units for a UI toolkit.
+    pub fn span_range(&self, span: &CitedSpan, unit: OffsetUnit) -> Range<usize> {
+        unit.from_byte_offset(&self.text, span.start)..unit.from_byte_offset(&self.text, span.end)
+    }
+
+    /// The cited sources with their reference numbers, starting at 1.
+    ///
+    /// Sources are numbered in the order they are first cited in the text; sources no span
+    /// cites are left out.
+    pub fn references(&self) -> Vec<(usize, &CitedSource)> {
+        let mut numbered = Vec::new();
+        for span in &self.spans {
+            for &index in &span.sources {
+                if !numbered.contains(&index) {
+                    numbered.push(index);
+                }
+            }
+        }
+        numbered
+            .into_iter()
+            .filter_map(|index| self.sources.get(index))
+            .enumerate()
+            .map(|(position, source)| (position + 1, source))
+            .collect()
+    }
+
+    /// The text with `[n]` markers after every cited span, numbered as in
+    /// [`references`](Self::references).
+    ///
+    /// Spans ending at the same position share one group of markers, e.g. `[1][3]`.
+    pub fn render_markers(&self) -> String {
+        let mut numbers: Vec<usize> = Vec::new();
+        let mut markers: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+        for span in &self.spans {
+            let at = markers.entry(span.end).or_default();
+            for &index in &span.sources {
+                let number = match numbers.iter().position(|&n| n == index) {
+                    Some(position) => position + 1,
+                    None => {
+                        numbers.push(index);
+                        numbers.len()
+                    }
+                };
+                if !at.contains(&number) {
+                    at.push(number);
+                }
+            }
+        }
+
+        let mut out = String::with_capacity(self.text.len() + markers.len() * 4);
+        let mut last = 0;
+        for (end, mut group) in markers {
+            group.sort_unstable();
+            out.push_str(&self.text[last..end]);
+            for number in group {
+                out.push_str(&format!("[{number}]"));
+            }
+            last = end;
+        }
+        out.push_str(&self.text[last..]);
+        out
+    }
+
+    /// The text with markers followed by a numbered list of the cited sources.
+    pub fn render_with_references(&self) -> String {
+        let mut out = self.render_markers();
+        let references = self.references();
+        if references.is_empty() {
+            return out;
+        }
+        out.push_str("\n\nReferences:\n");
+        for (number, source) in references {
+            out.push_str(&format!("[{number}] {source}\n"));
+        }
+        out
+    }
+}
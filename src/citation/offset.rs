@@ -0,0 +1,76 @@
+/// The unit in which the API reports text offsets.
+///
+/// Rust strings are indexed by UTF-8 byte, but offsets produced by services written in
+/// other languages are often counted in UTF-16 code units. The two agree on ASCII text and
+/// drift apart as soon as the text contains accents, CJK characters or emoji.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum OffsetUnit {
+    /// Offsets count UTF-8 bytes.
+    Utf8Bytes,
+    /// Offsets count UTF-16 code units.
+    Utf16CodeUnits,
+    /// Offsets count Unicode scalar values.
+    Chars,
+}
+
+impl OffsetUnit {
+    pub(crate) const ALL: [OffsetUnit; 3] = [
+        OffsetUnit::Utf8Bytes,
+        OffsetUnit::Utf16CodeUnits,
+        OffsetUnit::Chars,
+    ];
+
+    /// Converts an offset in this unit into a byte offset into `text`.
+    ///
+    /// Offsets falling inside a character are rounded down to its start. Returns `None`
+    /// when the offset lies beyond the end of the text.
+    pub fn to_byte_offset(self, text: &str, offset: usize) -> Option<usize> {
+        match self {
+            OffsetUnit::Utf8Bytes => {
+                if offset > text.len() {
+                    return None;
+                }
+                let mut offset = offset;
+                while !text.is_char_boundary(offset) {
+                    offset -= 1;
+                }
+                Some(offset)
+            }
+            OffsetUnit::Utf16CodeUnits => {
+                let mut units = 0;
+                for (index, c) in text.char_indices() {
+                    if units == offset {
+                        return Some(index);
+                    }
+                    units += c.len_utf16();
+                    if units > offset {
+                        return Some(index);
+                    }
+                }
+                (units == offset).then_some(text.len())
+            }
+            OffsetUnit::Chars => text
+                .char_indices()
+                .map(|(index, _)| index)
+                .chain(std::iter::once(text.len()))
+                .nth(offset),
+        }
+    }
+
+    /// Converts a byte offset into `text` into an offset in this unit.
+    ///
+    /// Byte offsets falling inside a character are rounded down to its start, and offsets
+    /// beyond the end of the text are clamped to it.
+    pub fn from_byte_offset(self, text: &str, offset: usize) -> usize {
+        let mut offset = offset.min(text.len());
+        while !text.is_char_boundary(offset) {
+            offset -= 1;
+        }
+        let prefix = &text[..offset];
+        match self {
+            OffsetUnit::Utf8Bytes => offset,
+            OffsetUnit::Utf16CodeUnits => prefix.encode_utf16().count(),
+            OffsetUnit::Chars => prefix.chars().count(),
+        }
+    }
+}
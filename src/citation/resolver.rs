@@ -0,0 +1,313 @@
+use std::ops::Range;
+
+use crate::citation::{AnnotatedText, CitedSource, CitedSpan, OffsetUnit, SourceKind};
+use crate::generation::model::{GroundingChunk, GroundingMetadata};
+use crate::interactions::model::{Annotation, InteractionContent};
+
+/// Maps citation offsets reported by the API onto the output text.
+///
+/// By default offsets are read as UTF-8 bytes, and grounding segments that carry their own
+/// text are checked against it: when the offsets do not select that text, the resolver tries
+/// UTF-16 code units and characters, and finally searches for the text itself. Pinning a
+/// unit with [`with_offset_unit`](Self::with_offset_unit) turns that detection off.
+#[derive(Debug, Clone)]
+pub struct CitationResolver {
+    unit: OffsetUnit,
+    detect: bool,
+}
+
+impl Default for CitationResolver {
+    fn default() -> Self {
+        Self {
+            unit: OffsetUnit::Utf8Bytes,
+            detect: true,
+        }
+    }
+}
+
+impl CitationResolver {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reads every offset in the given unit, without checking it against segment text.
+    pub fn with_offset_unit(mut self, unit: OffsetUnit) -> Self {
+        self.unit = unit;
+        self.detect = false;
+        self
+    }
+
+    /// Resolves grounding metadata against the parts of the candidate it belongs to.
+    ///
+    /// `parts` holds the text of every part of the candidate's content, with an empty string
+    /// for parts without text, so that segment part indices line up. Sources correspond
+    /// one-to-one to the grounding chunks.
+    pub fn resolve_grounding(&self, parts: &[&str], metadata: &GroundingMetadata) -> AnnotatedText {
+        let text = parts.concat();
+        let bases: Vec<usize> = parts
+            .iter()
+            .scan(0, |base, part| {
+                let start = *base;
+                *base += part.len();
+                Some(start)
+            })
+            .collect();
+        let sources: Vec<CitedSource> = metadata
+            .grounding_chunks
+            .iter()
+            .flatten()
+            .map(grounding_source)
+            .collect();
+
+        let mut spans = Vec::new();
+        for support in metadata.grounding_supports.iter().flatten() {
+            let segment = &support.segment;
+            let (part, base) = match segment.part_index {
+                Some(index) => match parts.get(index as usize) {
+                    Some(part) => (*part, bases[index as usize]),
+                    None => continue,
+                },
+                None => (text.as_str(), 0),
+            };
+            let Some(range) = self.locate(
+                part,
+                segment.start_index.unwrap_or(0) as usize,
+                segment.end_index.map(|end| end as usize),
+                segment.text.as_deref(),
+            ) else {
+                continue;
+            };
+            let cited = support
+                .grounding_chunk_indices
+                .iter()
+                .map(|&index| index as usize)
+                .filter(|&index| index < sources.len())
+                .collect();
+            spans.push(CitedSpan {
+                start: base + range.start,
+                end: base + range.end,
+                sources: cited,
+            });
+        }
+
+        finish(text, spans, sources)
+    }
+
+    /// Resolves the annotations of an interaction's text content.
+    ///
+    /// Text items are joined in order and each annotation's offsets are read relative to
+    /// the item carrying it. Identical sources are merged, and annotations without offsets
+    /// are ignored.
+    pub fn resolve_annotations(&self, content: &[InteractionContent]) -> AnnotatedText {
+        let mut text = String::new();
+        let mut spans = Vec::new();
+        let mut sources: Vec<CitedSource> = Vec::new();
+
+        for item in content {
+            let InteractionContent::Text {
+                text: item_text,
+                annotations,
+            } = item
+            else {
+                continue;
+            };
+            let base = text.len();
+            text.push_str(item_text);
+
+            for annotation in annotations {
+                let (start, end) = annotation_offsets(annotation);
+                let (Some(start), Some(end)) = (start, end) else {
+                    continue;
+                };
+                let (Ok(start), Ok(end)) = (usize::try_from(start), usize::try_from(end)) else {
+                    continue;
+                };
+                let Some(range) = self.locate(item_text, start, Some(end), None) else {
+                    continue;
+                };
+                let source = annotation_source(annotation);
+                let index = match sources.iter().position(|s| *s == source) {
+                    Some(index) => index,
+                    None => {
+                        sources.push(source);
+                        sources.len() - 1
+                    }
+                };
+                spans.push(CitedSpan {
+                    start: base + range.start,
+                    end: base + range.end,
+                    sources: vec![index],
+                });
+            }
+        }
+
+        finish(text, spans, sources)
+    }
+
+    /// Finds the byte range a segment covers in `text`.
+    fn locate(
+        &self,
+        text: &str,
+        start: usize,
+        end: Option<usize>,
+        expected: Option<&str>,
+    ) -> Option<Range<usize>> {
+        let expected = expected.filter(|_| self.detect);
+        if let Some(end) = end {
+            let units = std::iter::once(self.unit).chain(
+                OffsetUnit::ALL
+                    .into_iter()
+                    .filter(|unit| *unit != self.unit),
+            );
+            for unit in units {
+                let range = convert(unit, text, start, end);
+                match (range, expected) {
+                    (Some(range), None) => return Some(range),
+                    (Some(range), Some(expected)) if text[range.clone()] == *expected => {
+                        return Some(range)
+                    }
+                    _ => {}
+                }
+                if expected.is_none() {
+                    break;
+                }
+            }
+        }
+
+        let expected = expected.filter(|expected| !expected.is_empty())?;
+        let near = self.unit.to_byte_offset(text, start).unwrap_or(text.len());
+        text.match_indices(expected)
+            .map(|(index, _)| index)
+            .min_by_key(|index| index.abs_diff(near))
+            .map(|index| index..index + expected.len())
+    }
+}
+
+/// Converts a range of offsets in `unit`, clamping an end past the text to its length.
+fn convert(unit: OffsetUnit, text: &str, start: usize, end: usize) -> Option<Range<usize>> {
+    let start = unit.to_byte_offset(text, start)?;
+    let end = unit.to_byte_offset(text, end).unwrap_or(text.len());
+    (start < end).then_some(start..end)
+}
+
+/// Orders the spans and merges those covering the same range.
+fn finish(text: String, mut spans: Vec<CitedSpan>, sources: Vec<CitedSource>) -> AnnotatedText {
+    spans.retain(|span| !span.sources.is_empty());
+    spans.sort_by_key(|span| (span.start, span.end));
+    let mut merged: Vec<CitedSpan> = Vec::with_capacity(spans.len());
+    for span in spans {
+        match merged.last_mut() {
+            Some(last) if last.range() == span.range() => {
+                for index in span.sources {
+                    if !last.sources.contains(&index) {
+                        last.sources.push(index);
+                    }
+                }
+            }
+            _ => merged.push(span),
+        }
+    }
+    for span in &mut merged {
+        let mut seen = Vec::with_capacity(span.sources.len());
+        span.sources.retain(|index| {
+            let first = !seen.contains(index);
+            seen.push(*index);
+            first
+        });
+    }
+
+    AnnotatedText {
+        text,
+        spans: merged,
+        sources,
+    }
+}
+
+fn grounding_source(chunk: &GroundingChunk) -> CitedSource {
+    if let Some(web) = &chunk.web {
+        return CitedSource {
+            kind: SourceKind::Web,
+            title: Some(web.title.clone()),
+            uri: Some(web.uri.to_string()),
+            document_name: None,
+        };
+    }
+    if let Some(maps) = &chunk.maps {
+        return CitedSource {
+            kind: SourceKind::Place,
+            title: Some(maps.title.clone()),
+            uri: Some(maps.uri.to_string()),
+            document_name: None,
+        };
+    }
+    if let Some(context) = &chunk.retrieved_context {
+        return CitedSource {
+            kind: SourceKind::Document,
+            title: context.title.clone(),
+            uri: context.uri.clone(),
+            document_name: context
+                .document_name
+                .clone()
+                .or_else(|| document_resource(context.uri.as_deref())),
+        };
+    }
+    CitedSource {
+        kind: SourceKind::Other,
+        title: None,
+        uri: None,
+        document_name: None,
+    }
+}
+
+fn annotation_offsets(annotation: &Annotation) -> (Option<i64>, Option<i64>) {
+    match annotation {
+        Annotation::UrlCitation {
+            start_index,
+            end_index,
+            ..
+        }
+        | Annotation::FileCitation {
+            start_index,
+            end_index,
+            ..
+        }
+        | Annotation::PlaceCitation {
+            start_index,
+            end_index,
+            ..
+        } => (*start_index, *end_index),
+    }
+}
+
+fn annotation_source(annotation: &Annotation) -> CitedSource {
+    match annotation {
+        Annotation::UrlCitation { url, title, .. } => CitedSource {
+            kind: SourceKind::Web,
+            title: title.clone(),
+            uri: url.clone(),
+            document_name: None,
+        },
+        Annotation::FileCitation {
+            document_uri,
+            file_name,
+            ..
+        } => CitedSource {
+            kind: SourceKind::Document,
+            title: file_name.clone(),
+            uri: document_uri.clone(),
+            document_name: document_resource(document_uri.as_deref()),
+        },
+        Annotation::PlaceCitation { name, url, .. } => CitedSource {
+            kind: SourceKind::Place,
+            title: name.clone(),
+            uri: url.clone(),
+            document_name: None,
+        },
+    }
+}
+
+/// The URI itself when it names a file search document.
+fn document_resource(uri: Option<&str>) -> Option<String> {
+    uri.filter(|uri| uri.starts_with("fileSearchStores/") && uri.contains("/documents/"))
+        .map(str::to_string)
+}
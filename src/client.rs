@@ -1,10 +1,13 @@
 #[allow(deprecated)]
 use crate::{
-    batch::{BatchBuilder, BatchHandle},
-    cache::{CacheBuilder, CachedContentHandle},
+    batch::{
+        BatchBuilder, BatchHandle, BatchJournal, EmbedBatchBuilder, EmbedBatchHandle,
+        InteractionBatchBuilder, InteractionBatchHandle, ResumedBatch,
+    },
+    cache::{CacheBuilder, CacheManager, CachedContentHandle},
     embedding::{
-        BatchContentEmbeddingResponse, BatchEmbedContentsRequest, ContentEmbeddingResponse,
-        EmbedBuilder, EmbedContentRequest,
+        BatchContentEmbeddingResponse, BatchEmbedContentsRequest, BulkEmbedder,
+        ContentEmbeddingResponse, EmbedBuilder, EmbedContentRequest,
     },
     files::{
         handle::FileHandle,
@@ -71,6 +74,8 @@ pub enum Model {
     Gemini3ProImage,
     #[serde(rename = "models/text-embedding-004")]
     TextEmbedding004,
+    #[serde(rename = "models/gemini-embedding-001")]
+    GeminiEmbedding001,
     #[serde(untagged)]
     Custom(String),
 }
@@ -86,6 +91,7 @@ impl Model {
             Model::Gemini3Pro => "models/gemini-3-pro-preview",
             Model::Gemini3ProImage => "models/gemini-3-pro-image-preview",
             Model::TextEmbedding004 => "models/text-embedding-004",
+            Model::GeminiEmbedding001 => "models/gemini-embedding-001",
             Model::Custom(model) => model,
         }
     }
@@ -108,6 +114,7 @@ impl fmt::Display for Model {
             Model::Gemini3Pro => write!(f, "models/gemini-3-pro-preview"),
             Model::Gemini3ProImage => write!(f, "models/gemini-3-pro-image-preview"),
             Model::TextEmbedding004 => write!(f, "models/text-embedding-004"),
+            Model::GeminiEmbedding001 => write!(f, "models/gemini-embedding-001"),
             Model::Custom(model) => write!(f, "{model}"),
         }
     }
@@ -182,6 +189,17 @@ pub enum Error {
         name: String,
     },
 
+    #[snafu(display("invalid glob pattern '{pattern}'"))]
+    InvalidGlob {
+        source: glob::PatternError,
+        pattern: String,
+    },
+
+    #[snafu(display("operation cancelled: {name}"))]
+    OperationCancelled {
+        name: String,
+    },
+
     #[snafu(display("operation failed: {name}, code: {code}, message: {message}"))]
     OperationFailed {
         name: String,
@@ -189,10 +207,71 @@ pub enum Error {
         message: String,
     },
 
+    #[snafu(display("operation {name} has not finished"))]
+    OperationPending {
+        name: String,
+    },
+
+    #[snafu(display("operation {name} finished without a document"))]
+    OperationMissingDocument {
+        name: String,
+    },
+
+    #[snafu(display("invalid metadata filter"))]
+    InvalidMetadataFilter {
+        source: crate::file_search::FilterError,
+    },
+
     #[snafu(display("invalid resource name: {name}"))]
     InvalidResourceName {
         name: String,
     },
+
+    #[snafu(display("cached content '{cache}' belongs to '{cache_model}', not '{model}'"))]
+    CacheModelMismatch {
+        cache: String,
+        cache_model: String,
+        model: String,
+    },
+
+    #[snafu(display("invalid safety settings"))]
+    InvalidSafetySettings {
+        source: crate::safety::Error,
+    },
+
+    #[snafu(display(
+        "the Interactions API does not accept safety settings; batch the interaction instead"
+    ))]
+    SafetySettingsUnsupported,
+
+    #[snafu(display("{direction} content rejected by filter '{filter}': {reason}"))]
+    ContentRejected {
+        filter: String,
+        reason: String,
+        direction: crate::safety::FilterDirection,
+    },
+
+    #[snafu(display("model still calling functions after {rounds} rounds of results"))]
+    FunctionRoundsExceeded {
+        rounds: usize,
+    },
+
+    #[snafu(display("request blocked: {outcome}"))]
+    Blocked {
+        outcome: crate::safety::SafetyOutcome,
+    },
+}
+
+impl Error {
+    /// Whether the request may succeed if retried: rate limiting, server errors and failures
+    /// to reach the server.
+    pub(crate) fn is_transient(&self) -> bool {
+        match self {
+            Error::BadResponse { code, .. } => *code == 429 || *code >= 500,
+            Error::PerformRequest { .. } | Error::PerformRequestNew { .. } => true,
+            _ => false,
+        }
+    }
 }
 
 /// Internal client for making requests to the Gemini API
@@ -451,12 +530,22 @@ impl GeminiClient {
         &self,
         request: GenerateContentRequest,
     ) -> Result<crate::generation::CountTokensResponse, Error> {
-        let url = self.build_url("countTokens")?;
+        self.count_tokens_for_model(&self.model, request).await
+    }
+
+    /// Count tokens for content as seen by `model`, which may differ from the client's model
+    #[allow(deprecated)]
+    pub(crate) async fn count_tokens_for_model(
+        &self,
+        model: &Model,
+        request: GenerateContentRequest,
+    ) -> Result<crate::generation::CountTokensResponse, Error> {
+        let url = self.build_url_with_suffix(&format!("{model}:countTokens"))?;
         // Wrap the request in a "generateContentRequest" field and explicitly add the model.
         // The countTokens API requires the model to be specified within generateContentRequest.
         let body = json!({
             "generateContentRequest": {
-                "model": self.model.as_str(),
+                "model": model.as_str(),
                 "contents": request.contents,
                 "generationConfig": request.generation_config,
                 "safetySettings": request.safety_settings,
@@ -480,17 +569,18 @@ impl GeminiClient {
         &self,
         request: EmbedContentRequest,
     ) -> Result<ContentEmbeddingResponse, Error> {
-        let url = self.build_url("embedContent")?;
+        let url = self.build_url_with_suffix(&format!("{}:embedContent", request.model))?;
         self.post_json(url, &request).await
     }
 
-    /// Batch Embed content
-    #[instrument(skip_all, fields(batch.size = request.requests.len()))]
+    /// Batch Embed content with `model`, which may differ from the client's model
+    #[instrument(skip_all, fields(model = %model, batch.size = request.requests.len()))]
     pub(crate) async fn embed_content_batch(
         &self,
+        model: &Model,
         request: BatchEmbedContentsRequest,
     ) -> Result<BatchContentEmbeddingResponse, Error> {
-        let url = self.build_url("batchEmbedContents")?;
+        let url = self.build_url_with_suffix(&format!("{model}:batchEmbedContents"))?;
         self.post_json(url, &request).await
     }
 
@@ -507,6 +597,22 @@ impl GeminiClient {
         self.post_json(url, &request).await
     }
 
+    /// Submit an embedding batch to `model` (asynchronous API that returns a long-running
+    /// operation)
+    #[instrument(skip_all, fields(
+        model = %model,
+        batch.display_name = request.batch.display_name,
+        batch.size = request.batch.input_config.batch_size(),
+    ))]
+    pub(crate) async fn async_batch_embed_content(
+        &self,
+        model: &Model,
+        request: AsyncBatchEmbedContentRequest,
+    ) -> Result<BatchGenerateContentResponse, Error> {
+        let url = self.build_url_with_suffix(&format!("{model}:asyncBatchEmbedContent"))?;
+        self.post_json(url, &request).await
+    }
+
     /// Get a batch operation
     #[instrument(skip_all, fields(
         operation.name = name,
@@ -996,6 +1102,24 @@ impl GeminiClient {
             .await
     }
 
+    /// Renames a file search store. Only `displayName` is sent, under an `updateMask`.
+    #[instrument(skip_all, fields(store.name = %name))]
+    pub async fn update_file_search_store_display_name(
+        &self,
+        name: &str,
+        display_name: &str,
+    ) -> Result<crate::file_search::FileSearchStore, Error> {
+        let mut url = self.build_url_with_suffix(name)?;
+        url.query_pairs_mut()
+            .append_pair("updateMask", "displayName");
+        let payload = json!({ "displayName": display_name });
+        self.perform_request(
+            |c| c.patch(url.clone()).json(&payload),
+            async |r| r.json().await.context(DecodeResponseSnafu),
+        )
+        .await
+    }
+
     // Upload operation (resumable protocol)
 
     #[instrument(skip_all, fields(
@@ -1107,6 +1231,25 @@ impl GeminiClient {
             .await
     }
 
+    /// Replaces a document's custom metadata. Only `customMetadata` is sent, under an
+    /// `updateMask`.
+    #[instrument(skip_all, fields(document.name = %name))]
+    pub async fn update_document_metadata(
+        &self,
+        name: &str,
+        custom_metadata: &[crate::file_search::CustomMetadata],
+    ) -> Result<crate::file_search::Document, Error> {
+        let mut url = self.build_url_with_suffix(name)?;
+        url.query_pairs_mut()
+            .append_pair("updateMask", "customMetadata");
+        let payload = json!({ "customMetadata": custom_metadata });
+        self.perform_request(
+            |c| c.patch(url.clone()).json(&payload),
+            async |r| r.json().await.context(DecodeResponseSnafu),
+        )
+        .await
+    }
+
     // Operation operations
 
     #[instrument(skip_all, fields(operation.name = %name))]
@@ -1356,6 +1499,11 @@ impl Gemini {
         EmbedBuilder::new(self.client.clone())
     }
 
+    /// Start configuring a bulk embedding job for many documents
+    pub fn bulk_embed(&self) -> BulkEmbedder {
+        BulkEmbedder::new(self.client.clone())
+    }
+
     /// Start building a batch content generation request
     pub fn batch_generate_content(&self) -> BatchBuilder {
         BatchBuilder::new(self.client.clone())
@@ -1366,6 +1514,48 @@ impl Gemini {
         BatchHandle::new(name.to_string(), self.client.clone())
     }
 
+    /// Reattaches to every batch in `journal` whose results have not been collected yet.
+    ///
+    /// Each batch's current status is fetched; call
+    /// [`BatchJournal::mark_collected`] once its results are safely stored so that the next
+    /// resume skips it.
+    pub async fn resume_batches(
+        &self,
+        journal: &BatchJournal,
+    ) -> Result<Vec<ResumedBatch>, crate::batch::Error> {
+        let records = journal.pending().await?;
+        let resumed = records.into_iter().map(|record| async move {
+            let handle = self.get_batch(&record.name).with_order(record.keys.clone());
+            let status = handle.status().await;
+            ResumedBatch {
+                record,
+                handle,
+                status,
+            }
+        });
+        Ok(futures::future::join_all(resumed).await)
+    }
+
+    /// Start building an asynchronous embedding batch request
+    pub fn batch_embed_content(&self) -> EmbedBatchBuilder {
+        EmbedBatchBuilder::new(self.client.clone())
+    }
+
+    /// Get a handle to an embedding batch operation by its name.
+    pub fn get_embed_batch(&self, name: &str) -> EmbedBatchHandle {
+        EmbedBatchHandle::new(name.to_string(), self.client.clone())
+    }
+
+    /// Start building a batch of Interactions API requests
+    pub fn batch_create_interactions(&self) -> InteractionBatchBuilder {
+        InteractionBatchBuilder::new(self.client.clone())
+    }
+
+    /// Get a handle to an interaction batch operation by its name.
+    pub fn get_interaction_batch(&self, name: &str) -> InteractionBatchHandle {
+        InteractionBatchHandle::new(self.get_batch(name))
+    }
+
     /// Lists batch operations.
     ///
     /// This method returns a stream that handles pagination automatically.
@@ -1400,6 +1590,11 @@ impl Gemini {
         CacheBuilder::new(self.client.clone())
     }
 
+    /// Create a manager that creates, reuses, refreshes and deletes caches automatically.
+    pub fn cache_manager(&self) -> CacheManager {
+        CacheManager::new(self.client.clone())
+    }
+
     /// Get a handle to cached content by its name.
     pub fn get_cached_content(&self, name: &str) -> CachedContentHandle {
         CachedContentHandle::new(name.to_string(), self.client.clone())
@@ -1474,6 +1669,15 @@ impl Gemini {
         }
     }
 
+    /// Get a handle to a file search operation saved earlier, for example by serializing
+    /// [`OperationHandle::operation`](crate::file_search::OperationHandle::operation).
+    pub fn resume_file_search_operation(
+        &self,
+        operation: crate::file_search::Operation,
+    ) -> crate::file_search::OperationHandle {
+        crate::file_search::OperationHandle::new(self.client.clone(), operation)
+    }
+
     /// Start building a file search store
     pub fn create_file_search_store(&self) -> crate::file_search::FileSearchStoreBuilder {
         crate::file_search::FileSearchStoreBuilder {
@@ -1 +1,4 @@
+pub mod poll;
 pub(crate) mod serde;
+
+pub use poll::PollPolicy;
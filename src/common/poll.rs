@@ -0,0 +1,172 @@
+//! Polling configuration for long-running operations.
+
+use std::time::{Duration, Instant};
+use tokio_util::sync::CancellationToken;
+
+/// Controls how long-running operations are polled until they finish.
+///
+/// The first status check happens immediately. After that the delay between checks starts at
+/// `initial_interval` and grows by `multiplier` after every check, up to `max_interval`.
+/// Waiting stops with an error once `timeout` elapses or the cancellation token fires; the
+/// remote operation itself keeps running in both cases.
+///
+/// ```
+/// # use gemini_rust::PollPolicy;
+/// # use std::time::Duration;
+/// let policy = PollPolicy::default()
+///     .with_initial_interval(Duration::from_secs(10))
+///     .with_max_interval(Duration::from_secs(120))
+///     .with_timeout(Duration::from_secs(24 * 60 * 60));
+/// ```
+#[derive(Debug, Clone)]
+pub struct PollPolicy {
+    /// Delay before the second status check.
+    pub initial_interval: Duration,
+    /// Upper bound for the delay between checks.
+    pub max_interval: Duration,
+    /// Factor applied to the delay after every check.
+    pub multiplier: f64,
+    /// Total time to wait before giving up, if any.
+    pub timeout: Option<Duration>,
+    /// Token that stops waiting when cancelled.
+    pub cancellation: Option<CancellationToken>,
+}
+
+impl Default for PollPolicy {
+    /// Polls every 5 seconds at first, backing off by 1.5x up to once a minute, with no timeout.
+    fn default() -> Self {
+        Self {
+            initial_interval: Duration::from_secs(5),
+            max_interval: Duration::from_secs(60),
+            multiplier: 1.5,
+            timeout: None,
+            cancellation: None,
+        }
+    }
+}
+
+impl PollPolicy {
+    /// Creates a policy that polls at a fixed interval.
+    pub fn fixed(interval: Duration) -> Self {
+        Self {
+            initial_interval: interval,
+            max_interval: interval,
+            multiplier: 1.0,
+            ..Default::default()
+        }
+    }
+
+    /// Sets the delay before the second status check.
+    pub fn with_initial_interval(mut self, interval: Duration) -> Self {
+        self.initial_interval = interval;
+        self
+    }
+
+    /// Sets the upper bound for the delay between checks.
+    pub fn with_max_interval(mut self, interval: Duration) -> Self {
+        self.max_interval = interval;
+        self
+    }
+
+    /// Sets the backoff factor. Values below `1.0` and NaN are treated as `1.0`, infinity as
+    /// the largest finite factor.
+    pub fn with_multiplier(mut self, multiplier: f64) -> Self {
+        self.multiplier = if multiplier.is_nan() {
+            1.0
+        } else {
+            multiplier.clamp(1.0, f64::MAX)
+        };
+        self
+    }
+
+    /// Gives up waiting after `timeout`.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Stops waiting as soon as `token` is cancelled.
+    pub fn with_cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancellation = Some(token);
+        self
+    }
+
+    /// The delay that follows `interval`, saturating at `max_interval` instead of overflowing.
+    pub(crate) fn next_interval(&self, interval: Duration) -> Duration {
+        let max = self.max_interval.max(self.initial_interval);
+        let multiplier = if self.multiplier.is_nan() {
+            1.0
+        } else {
+            self.multiplier.max(1.0)
+        };
+        Duration::try_from_secs_f64(interval.as_secs_f64() * multiplier)
+            .map_or(max, |next| next.min(max))
+    }
+
+    /// Starts a polling session governed by this policy.
+    pub(crate) fn start(&self) -> Poller<'_> {
+        Poller {
+            policy: self,
+            started: Instant::now(),
+            interval: self.initial_interval,
+        }
+    }
+}
+
+/// Why a polling session stopped before the operation finished.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum PollStop {
+    TimedOut,
+    Cancelled,
+}
+
+/// Tracks the elapsed time and current delay of one polling session.
+pub(crate) struct Poller<'a> {
+    policy: &'a PollPolicy,
+    started: Instant,
+    interval: Duration,
+}
+
+impl Poller<'_> {
+    /// Sleeps until the next check is due.
+    ///
+    /// The sleep is shortened so it never overshoots the timeout, and is interrupted by
+    /// cancellation.
+    pub(crate) async fn wait(&mut self) -> Result<(), PollStop> {
+        let mut delay = self.interval;
+        if let Some(timeout) = self.policy.timeout {
+            let remaining = timeout.saturating_sub(self.started.elapsed());
+            if remaining.is_zero() {
+                return Err(PollStop::TimedOut);
+            }
+            delay = delay.min(remaining);
+        }
+
+        self.interval = self.policy.next_interval(self.interval);
+
+        match &self.policy.cancellation {
+            Some(token) => tokio::select! {
+                _ = token.cancelled() => Err(PollStop::Cancelled),
+                _ = tokio::time::sleep(delay) => Ok(()),
+            },
+            None => {
+                tokio::time::sleep(delay).await;
+                Ok(())
+            }
+        }
+    }
+
+    /// Goes back to the initial delay, for when progress suggests the operation is close to
+    /// finishing.
+    pub(crate) fn reset_interval(&mut self) {
+        self.interval = self.policy.initial_interval;
+    }
+
+    /// Returns an error if the session was cancelled before the first check.
+    pub(crate) fn check_cancelled(&self) -> Result<(), PollStop> {
+        match &self.policy.cancellation {
+            Some(token) if token.is_cancelled() => Err(PollStop::Cancelled),
+            _ => Ok(()),
+        }
+    }
+}
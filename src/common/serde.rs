@@ -47,33 +47,6 @@ pub(crate) mod i64_as_string {
     }
 }
 
-/// Custom serialization/deserialization for the request key.
-pub(crate) mod key_as_string {
-    use serde::{self, Deserialize, Deserializer, Serializer};
-
-    /// Serializes a `usize` key as a string.
-    pub fn serialize<S>(key: &usize, serializer: S) -> Result<S::Ok, S::Error>
-    where
-        S: Serializer,
-    {
-        serializer.serialize_str(&key.to_string())
-    }
-
-    /// Deserializes a string key into a `usize`.
-    pub fn deserialize<'de, D>(deserializer: D) -> Result<usize, D::Error>
-    where
-        D: Deserializer<'de>,
-    {
-        let s = String::deserialize(deserializer)?;
-        s.parse::<usize>().map_err(|e| {
-            let error_message = format!(
-                "Failed to parse key '{s}' as a number: {e}. This crate uses index-based keys for batch requests; custom string keys are not supported."
-            );
-            serde::de::Error::custom(error_message)
-        })
-    }
-}
-
 /// Deserializes a string into an `i64`.
 pub fn deserialize_string_to_i64<'de, D>(deserializer: D) -> Result<i64, D::Error>
 where
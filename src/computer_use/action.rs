@@ -0,0 +1,273 @@
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use snafu::ResultExt;
+
+use crate::computer_use::{Error, InvalidArgumentsSnafu, UnknownActionSnafu};
+
+/// The names of the predefined computer-use functions, as used in
+/// `excluded_predefined_functions`.
+pub const PREDEFINED_FUNCTIONS: [&str; 13] = [
+    "open_web_browser",
+    "wait_5_seconds",
+    "go_back",
+    "go_forward",
+    "search",
+    "navigate",
+    "click_at",
+    "hover_at",
+    "type_text_at",
+    "key_combination",
+    "scroll_document",
+    "scroll_at",
+    "drag_and_drop",
+];
+
+/// A point on the screen in the model's normalized coordinates, from 0 to 999 on both axes
+/// whatever the screen size.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Point {
+    pub x: u32,
+    pub y: u32,
+}
+
+impl Point {
+    /// The point in pixels on a screen of the given size.
+    pub fn to_pixels(self, width: u32, height: u32) -> (u32, u32) {
+        let scale =
+            |value: u32, extent: u32| (u64::from(value.min(999)) * u64::from(extent) / 1000) as u32;
+        (scale(self.x, width), scale(self.y, height))
+    }
+}
+
+/// A direction to scroll in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScrollDirection {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+/// A predefined UI action requested by the model.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ComputerAction {
+    /// Open the web browser.
+    OpenWebBrowser,
+    /// Wait five seconds, e.g. for a page to load.
+    Wait5Seconds,
+    /// Go back to the previous page.
+    GoBack,
+    /// Go forward to the next page.
+    GoForward,
+    /// Go to the start page of a search engine.
+    Search,
+    /// Go to a URL.
+    Navigate { url: String },
+    /// Click at a point.
+    ClickAt { at: Point },
+    /// Move the pointer to a point.
+    HoverAt { at: Point },
+    /// Click at a point and type text.
+    TypeTextAt {
+        at: Point,
+        text: String,
+        /// Press Enter after typing.
+        press_enter: bool,
+        /// Clear the field before typing.
+        clear_before_typing: bool,
+    },
+    /// Press keys together, e.g. `["Control", "C"]`.
+    KeyCombination { keys: Vec<String> },
+    /// Scroll the whole page.
+    ScrollDocument { direction: ScrollDirection },
+    /// Scroll the element at a point, by a distance in normalized coordinates.
+    ScrollAt {
+        at: Point,
+        direction: ScrollDirection,
+        magnitude: u32,
+    },
+    /// Drag from one point and drop at another.
+    DragAndDrop { from: Point, to: Point },
+}
+
+#[derive(Deserialize)]
+struct NavigateArgs {
+    url: String,
+}
+
+#[derive(Deserialize)]
+struct TypeTextArgs {
+    #[serde(flatten)]
+    at: Point,
+    text: String,
+    #[serde(default = "default_true")]
+    press_enter: bool,
+    #[serde(default = "default_true")]
+    clear_before_typing: bool,
+}
+
+#[derive(Deserialize)]
+struct KeysArgs {
+    keys: String,
+}
+
+#[derive(Deserialize)]
+struct ScrollDocumentArgs {
+    direction: ScrollDirection,
+}
+
+#[derive(Deserialize)]
+struct ScrollAtArgs {
+    #[serde(flatten)]
+    at: Point,
+    direction: ScrollDirection,
+    #[serde(default = "default_magnitude")]
+    magnitude: u32,
+}
+
+#[derive(Deserialize)]
+struct DragAndDropArgs {
+    x: u32,
+    y: u32,
+    destination_x: u32,
+    destination_y: u32,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_magnitude() -> u32 {
+    800
+}
+
+impl ComputerAction {
+    /// Parses a call to a predefined function.
+    ///
+    /// Arguments the action does not use, such as `safety_decision`, are ignored.
+    pub fn from_call(name: &str, arguments: &Value) -> Result<Self, Error> {
+        fn args<T: DeserializeOwned>(name: &str, arguments: &Value) -> Result<T, Error> {
+            serde_json::from_value(arguments.clone()).context(InvalidArgumentsSnafu { name })
+        }
+
+        Ok(match name {
+            "open_web_browser" => Self::OpenWebBrowser,
+            "wait_5_seconds" => Self::Wait5Seconds,
+            "go_back" => Self::GoBack,
+            "go_forward" => Self::GoForward,
+            "search" => Self::Search,
+            "navigate" => Self::Navigate {
+                url: args::<NavigateArgs>(name, arguments)?.url,
+            },
+            "click_at" => Self::ClickAt {
+                at: args(name, arguments)?,
+            },
+            "hover_at" => Self::HoverAt {
+                at: args(name, arguments)?,
+            },
+            "type_text_at" => {
+                let a: TypeTextArgs = args(name, arguments)?;
+                Self::TypeTextAt {
+                    at: a.at,
+                    text: a.text,
+                    press_enter: a.press_enter,
+                    clear_before_typing: a.clear_before_typing,
+                }
+            }
+            "key_combination" => Self::KeyCombination {
+                keys: args::<KeysArgs>(name, arguments)?
+                    .keys
+                    .split('+')
+                    .map(|key| key.trim().to_string())
+                    .filter(|key| !key.is_empty())
+                    .collect(),
+            },
+            "scroll_document" => Self::ScrollDocument {
+                direction: args::<ScrollDocumentArgs>(name, arguments)?.direction,
+            },
+            "scroll_at" => {
+                let a: ScrollAtArgs = args(name, arguments)?;
+                Self::ScrollAt {
+                    at: a.at,
+                    direction: a.direction,
+                    magnitude: a.magnitude,
+                }
+            }
+            "drag_and_drop" => {
+                let a: DragAndDropArgs = args(name, arguments)?;
+                Self::DragAndDrop {
+                    from: Point { x: a.x, y: a.y },
+                    to: Point {
+                        x: a.destination_x,
+                        y: a.destination_y,
+                    },
+                }
+            }
+            _ => return UnknownActionSnafu { name }.fail(),
+        })
+    }
+
+    /// The name of the predefined function for this action.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::OpenWebBrowser => "open_web_browser",
+            Self::Wait5Seconds => "wait_5_seconds",
+            Self::GoBack => "go_back",
+            Self::GoForward => "go_forward",
+            Self::Search => "search",
+            Self::Navigate { .. } => "navigate",
+            Self::ClickAt { .. } => "click_at",
+            Self::HoverAt { .. } => "hover_at",
+            Self::TypeTextAt { .. } => "type_text_at",
+            Self::KeyCombination { .. } => "key_combination",
+            Self::ScrollDocument { .. } => "scroll_document",
+            Self::ScrollAt { .. } => "scroll_at",
+            Self::DragAndDrop { .. } => "drag_and_drop",
+        }
+    }
+}
+
+/// What the service decided about the safety of an action.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SafetyDecision {
+    pub decision: SafetyDecisionKind,
+    #[serde(default)]
+    pub explanation: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SafetyDecisionKind {
+    /// The action may run.
+    Regular,
+    /// The action may only run once the user confirmed it.
+    RequireConfirmation,
+    /// A decision this version does not know, or one that could not be read.
+    #[serde(other)]
+    Unknown,
+}
+
+impl SafetyDecision {
+    /// The safety decision attached to a call's arguments, if any.
+    ///
+    /// A `safety_decision` that cannot be read is returned as
+    /// [`Unknown`](SafetyDecisionKind::Unknown), with the raw value as explanation, so it
+    /// still asks for confirmation.
+    pub fn from_arguments(arguments: &Value) -> Option<Self> {
+        let value = arguments.get("safety_decision")?;
+        Some(
+            serde_json::from_value(value.clone()).unwrap_or_else(|_| Self {
+                decision: SafetyDecisionKind::Unknown,
+                explanation: format!("unreadable safety decision: {value}"),
+            }),
+        )
+    }
+
+    /// Whether the user must confirm the action. Only [`Regular`](SafetyDecisionKind::Regular)
+    /// decisions let it run unconfirmed.
+    pub fn requires_confirmation(&self) -> bool {
+        self.decision != SafetyDecisionKind::Regular
+    }
+}
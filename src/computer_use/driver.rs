@@ -0,0 +1,268 @@
+use serde_json::{json, Value};
+use snafu::ResultExt;
+use std::sync::Arc;
+use tracing::instrument;
+
+use crate::computer_use::action::{ComputerAction, SafetyDecision};
+use crate::computer_use::executor::ActionExecutor;
+use crate::computer_use::{ClientSnafu, Error, ObserveSnafu};
+use crate::interactions::functions::{self, FunctionHandler};
+use crate::interactions::model::{
+    ComputerUseEnvironment, Interaction, InteractionContent, InteractionTool, Step, StepResult,
+    StepResultContent,
+};
+use crate::interactions::InteractionBuilder;
+
+/// Why a [`ComputerUseDriver`] run ended.
+#[derive(Debug, Clone, PartialEq)]
+pub enum StopReason {
+    /// The model answered without requesting another action.
+    Completed,
+    /// The model was still requesting actions after the maximum number of turns.
+    TurnLimit,
+    /// The user did not confirm an action the service flagged.
+    Declined {
+        action: ComputerAction,
+        decision: SafetyDecision,
+    },
+}
+
+/// An action the driver ran.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExecutedAction {
+    pub call_id: String,
+    pub action: ComputerAction,
+    /// The error the executor reported, if the action failed.
+    pub error: Option<String>,
+}
+
+/// The outcome of a [`ComputerUseDriver`] run.
+#[derive(Debug, Clone)]
+pub struct ComputerUseRun {
+    /// The last interaction of the run.
+    pub interaction: Interaction,
+    /// Every action run, in order.
+    pub actions: Vec<ExecutedAction>,
+    /// The number of turns in which the model requested actions.
+    pub turns: usize,
+    pub stop: StopReason,
+}
+
+/// Runs the computer-use loop: executes the actions the model requests with an
+/// [`ActionExecutor`] and sends back what the screen looks like afterwards.
+pub struct ComputerUseDriver<E> {
+    executor: E,
+    environment: ComputerUseEnvironment,
+    excluded: Vec<String>,
+    handlers: Vec<Arc<dyn FunctionHandler>>,
+    max_turns: usize,
+    initial_observation: bool,
+}
+
+impl<E: ActionExecutor> ComputerUseDriver<E> {
+    pub fn new(executor: E, environment: ComputerUseEnvironment) -> Self {
+        Self {
+            executor,
+            environment,
+            excluded: Vec::new(),
+            handlers: Vec::new(),
+            max_turns: 50,
+            initial_observation: true,
+        }
+    }
+
+    /// Stops the model from using a predefined function, e.g. `"drag_and_drop"`.
+    ///
+    /// Calls to it are refused unless a function handler answers them.
+    pub fn with_excluded_function(mut self, name: impl Into<String>) -> Self {
+        self.excluded.push(name.into());
+        self
+    }
+
+    /// Adds custom functions next to the predefined ones, e.g. to replace an excluded one.
+    pub fn with_function_handler(mut self, handler: impl FunctionHandler + 'static) -> Self {
+        self.handlers.push(Arc::new(handler));
+        self
+    }
+
+    /// Set how many turns of actions a run may take. Defaults to 50.
+    pub fn with_max_turns(mut self, turns: usize) -> Self {
+        self.max_turns = turns;
+        self
+    }
+
+    /// Whether to send a screenshot with the initial request. Defaults to true.
+    pub fn with_initial_observation(mut self, initial_observation: bool) -> Self {
+        self.initial_observation = initial_observation;
+        self
+    }
+
+    pub fn executor(&self) -> &E {
+        &self.executor
+    }
+
+    pub fn executor_mut(&mut self) -> &mut E {
+        &mut self.executor
+    }
+
+    pub fn into_executor(self) -> E {
+        self.executor
+    }
+
+    /// The computer-use tool added to requests.
+    pub fn tool(&self) -> InteractionTool {
+        InteractionTool::ComputerUse {
+            environment: Some(self.environment.clone()),
+            excluded_predefined_functions: self.excluded.clone(),
+            enable_prompt_injection_detection: None,
+            disabled_safety_policies: vec![],
+        }
+    }
+
+    /// Runs the task described by `builder` until the model stops requesting actions.
+    ///
+    /// The computer-use tool and the tools of the function handlers are added to the
+    /// request. Actions of a turn run one after the other, each followed by an
+    /// observation sent back as its function result.
+    #[instrument(skip_all, fields(environment = ?self.environment, turns))]
+    pub async fn run(&mut self, builder: InteractionBuilder) -> Result<ComputerUseRun, Error> {
+        let mut builder = builder.with_tool(self.tool());
+        for handler in &self.handlers {
+            builder = builder.with_tools(handler.tools());
+        }
+        if self.initial_observation {
+            let observation = self.executor.observe().await;
+            let observation = observation.map_err(|message| ObserveSnafu { message }.build())?;
+            builder = builder.with_appended_content(InteractionContent::image(
+                observation.screenshot,
+                observation.mime_type,
+            ));
+        }
+
+        let mut actions = Vec::new();
+        let mut turns = 0;
+        loop {
+            let interaction = builder.clone().execute().await.context(ClientSnafu)?;
+            let calls: Vec<(String, Value, String)> = functions::pending_calls(&interaction)
+                .into_iter()
+                .map(|(name, arguments, id)| (name.to_string(), arguments.clone(), id.to_string()))
+                .collect();
+            let stop = if calls.is_empty() {
+                Some(StopReason::Completed)
+            } else if turns == self.max_turns {
+                Some(StopReason::TurnLimit)
+            } else {
+                None
+            };
+            if let Some(stop) = stop {
+                tracing::Span::current().record("turns", turns);
+                return Ok(ComputerUseRun {
+                    interaction,
+                    actions,
+                    turns,
+                    stop,
+                });
+            }
+            turns += 1;
+
+            let mut results = Vec::with_capacity(calls.len());
+            for (name, arguments, call_id) in calls {
+                let step = match self.call(&name, &arguments, &call_id, &mut actions).await? {
+                    Ok(step) => step,
+                    Err(stop) => {
+                        return Ok(ComputerUseRun {
+                            interaction,
+                            actions,
+                            turns,
+                            stop,
+                        })
+                    }
+                };
+                results.push(step);
+            }
+            builder = builder.continued(&interaction, results);
+        }
+    }
+
+    /// Answers one call: with a function handler, by running a predefined action, or with
+    /// an error for the model. Returns the reason to stop when the user declines an action.
+    async fn call(
+        &mut self,
+        name: &str,
+        arguments: &Value,
+        call_id: &str,
+        actions: &mut Vec<ExecutedAction>,
+    ) -> Result<Result<Step, StopReason>, Error> {
+        let result =
+            |result: Result<StepResult, String>| functions::function_result(name, call_id, result);
+
+        if let Some(handler) = self.handlers.iter().find(|h| h.handles(name)) {
+            return Ok(Ok(result(handler.call(name, arguments.clone()).await)));
+        }
+        if self.excluded.iter().any(|excluded| excluded == name) {
+            return Ok(Ok(result(Err(format!("function {name} is not available")))));
+        }
+        let action = match ComputerAction::from_call(name, arguments) {
+            Ok(action) => action,
+            Err(e) => {
+                let message = match std::error::Error::source(&e) {
+                    Some(source) => format!("{e}: {source}"),
+                    None => e.to_string(),
+                };
+                return Ok(Ok(result(Err(message))));
+            }
+        };
+
+        let decision = SafetyDecision::from_arguments(arguments);
+        let acknowledged = match &decision {
+            Some(decision) if decision.requires_confirmation() => {
+                if !self.executor.confirm(&action, decision).await {
+                    return Ok(Err(StopReason::Declined {
+                        action,
+                        decision: decision.clone(),
+                    }));
+                }
+                true
+            }
+            _ => false,
+        };
+
+        tracing::debug!(action = ?action, call_id, "executing computer use action");
+        let error = self.executor.execute(&action).await.err();
+        let observation = self.executor.observe().await;
+        let observation = observation.map_err(|message| ObserveSnafu { message }.build())?;
+
+        let mut state = json!({});
+        if let Some(url) = &observation.url {
+            state["url"] = json!(url);
+        }
+        if acknowledged {
+            state["safety_acknowledgement"] = json!("true");
+        }
+        if let Some(error) = &error {
+            state["error"] = json!(error);
+        }
+        let content = vec![
+            StepResultContent::Text {
+                text: state.to_string(),
+            },
+            StepResultContent::Image {
+                data: Some(observation.screenshot),
+                uri: None,
+                mime_type: Some(observation.mime_type),
+            },
+        ];
+        let step = Step::FunctionResult {
+            name: Some(name.to_string()),
+            call_id: call_id.to_string(),
+            result: StepResult::ContentArray(content),
+            is_error: error.is_some().then_some(true),
+        };
+        actions.push(ExecutedAction {
+            call_id: call_id.to_string(),
+            action,
+            error,
+        });
+        Ok(Ok(step))
+    }
+}
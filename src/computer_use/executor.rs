@@ -0,0 +1,54 @@
+use async_trait::async_trait;
+
+use crate::computer_use::action::{ComputerAction, SafetyDecision};
+use crate::interactions::model::ImageMimeType;
+
+/// The state of the environment after an action, sent back to the model.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Observation {
+    /// Base64-encoded screenshot.
+    pub screenshot: String,
+    pub mime_type: ImageMimeType,
+    /// The URL of the current page, for browser environments.
+    pub url: Option<String>,
+}
+
+impl Observation {
+    /// A base64-encoded PNG screenshot.
+    pub fn png(screenshot: impl Into<String>) -> Self {
+        Self {
+            screenshot: screenshot.into(),
+            mime_type: ImageMimeType::Png,
+            url: None,
+        }
+    }
+
+    pub fn with_url(mut self, url: impl Into<String>) -> Self {
+        self.url = Some(url.into());
+        self
+    }
+}
+
+/// Carries out computer-use actions, e.g. in a headless browser.
+///
+/// Coordinates in actions are normalized; see [`Point::to_pixels`](crate::computer_use::Point::to_pixels).
+#[async_trait]
+pub trait ActionExecutor: Send {
+    /// Performs an action. An error is reported to the model together with the next
+    /// observation, so it can try something else.
+    async fn execute(&mut self, action: &ComputerAction) -> Result<(), String>;
+
+    /// Captures the current state of the environment.
+    ///
+    /// A failure ends the run with [`Error::Observe`](crate::computer_use::Error::Observe),
+    /// since the model cannot go on without seeing the screen.
+    async fn observe(&mut self) -> Result<Observation, String>;
+
+    /// Asks the user whether an action the service flagged may run.
+    ///
+    /// Denying the action ends the run. Defaults to denying every flagged action.
+    async fn confirm(&mut self, action: &ComputerAction, decision: &SafetyDecision) -> bool {
+        let _ = (action, decision);
+        false
+    }
+}
@@ -0,0 +1,76 @@
+//! Executing computer-use actions.
+//!
+//! With the [computer-use tool](crate::interactions::InteractionTool::computer_use) the model
+//! operates a UI by calling predefined functions such as `click_at` or `type_text_at`.
+//! [`ComputerAction`] is the typed form of those calls, an [`ActionExecutor`] carries them
+//! out, and a [`ComputerUseDriver`] runs the loop: it executes each requested action, sends
+//! back a screenshot as the function result, and asks the executor to confirm actions the
+//! service flags as needing the user's consent.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # use gemini_rust::prelude::*;
+//! # use gemini_rust::{ActionExecutor, ComputerAction, ComputerUseDriver, ComputerUseEnvironment, Observation};
+//! struct Browser;
+//!
+//! #[async_trait::async_trait]
+//! impl ActionExecutor for Browser {
+//!     async fn execute(&mut self, action: &ComputerAction) -> Result<(), String> {
+//!         println!("{action:?}");
+//!         Ok(())
+//!     }
+//!
+//!     async fn observe(&mut self) -> Result<Observation, String> {
+//!         Ok(Observation::png("iVBORw0KGgo=").with_url("https://example.com"))
+//!     }
+//! }
+//!
+//! # async fn example(gemini: &Gemini) -> Result<(), Box<dyn std::error::Error>> {
+//! let mut driver = ComputerUseDriver::new(Browser, ComputerUseEnvironment::Browser)
+//!     .with_excluded_function("drag_and_drop");
+//! let run = driver
+//!     .run(
+//!         gemini
+//!             .create_interaction()
+//!             .with_model("gemini-2.5-computer-use-preview-10-2025")
+//!             .with_text("Find the opening hours of the nearest library."),
+//!     )
+//!     .await?;
+//!
+//! println!("{} ({} actions)", run.interaction.output_text(), run.actions.len());
+//! # Ok(())
+//! # }
+//! ```
+
+use snafu::Snafu;
+
+pub mod action;
+pub mod driver;
+pub mod executor;
+
+pub use action::{
+    ComputerAction, Point, SafetyDecision, SafetyDecisionKind, ScrollDirection,
+    PREDEFINED_FUNCTIONS,
+};
+pub use driver::{ComputerUseDriver, ComputerUseRun, ExecutedAction, StopReason};
+pub use executor::{ActionExecutor, Observation};
+
+#[derive(Debug, Snafu)]
+#[snafu(visibility(pub(crate)))]
+pub enum Error {
+    #[snafu(display("'{name}' is not a predefined computer use function"))]
+    UnknownAction { name: String },
+
+    #[snafu(display("invalid arguments for computer use function '{name}'"))]
+    InvalidArguments {
+        source: serde_json::Error,
+        name: String,
+    },
+
+    #[snafu(display("failed to observe the environment: {message}"))]
+    Observe { message: String },
+
+    #[snafu(display("interaction request failed"))]
+    Client { source: crate::ClientError },
+}
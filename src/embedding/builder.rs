@@ -7,14 +7,93 @@ use super::model::{
 };
 use crate::{
     client::{Error as ClientError, GeminiClient},
-    Content, Message,
+    files::Error as FilesError,
+    Content, FileData, FileHandle, Message, Model, Part,
 };
 
+/// A single item to embed, with optional per-item settings.
+///
+/// Items without their own task type or title use the ones set on the [`EmbedBuilder`].
+#[derive(Debug, Clone)]
+pub struct EmbedInput {
+    /// The content to embed.
+    pub content: Content,
+    /// The task type for this item.
+    pub task_type: Option<TaskType>,
+    /// The document title for this item.
+    pub title: Option<String>,
+}
+
+impl EmbedInput {
+    /// Embeds arbitrary content.
+    pub fn content(content: Content) -> Self {
+        Self {
+            content,
+            task_type: None,
+            title: None,
+        }
+    }
+
+    /// Embeds a text.
+    pub fn text(text: impl Into<String>) -> Self {
+        Self::content(Message::embed(text).content)
+    }
+
+    /// Embeds base64-encoded inline data, such as an image.
+    pub fn inline_data(mime_type: impl Into<String>, data: impl Into<String>) -> Self {
+        Self::content(Content::inline_data(mime_type, data))
+    }
+
+    /// Embeds a base64-encoded PDF document.
+    pub fn pdf(data: impl Into<String>) -> Self {
+        Self::inline_data("application/pdf", data)
+    }
+
+    /// Embeds a previously uploaded file.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file metadata is incomplete (missing MIME type or URI).
+    pub fn file(file_handle: &FileHandle) -> Result<Self, FilesError> {
+        Ok(Self::content(Content {
+            parts: Some(vec![Part::FileData {
+                file_data: FileData::try_from(file_handle)?,
+            }]),
+            role: None,
+        }))
+    }
+
+    /// Sets the task type for this item.
+    pub fn with_task_type(mut self, task_type: TaskType) -> Self {
+        self.task_type = Some(task_type);
+        self
+    }
+
+    /// Sets the document title for this item.
+    pub fn with_title(mut self, title: impl Into<String>) -> Self {
+        self.title = Some(title.into());
+        self
+    }
+}
+
+impl From<String> for EmbedInput {
+    fn from(text: String) -> Self {
+        Self::text(text)
+    }
+}
+
+impl From<&str> for EmbedInput {
+    fn from(text: &str) -> Self {
+        Self::text(text)
+    }
+}
+
 /// Builder for embed generation requests
 #[derive(Clone)]
 pub struct EmbedBuilder {
     client: Arc<GeminiClient>,
-    contents: Vec<Content>,
+    model: Option<Model>,
+    inputs: Vec<EmbedInput>,
     task_type: Option<TaskType>,
     title: Option<String>,
     output_dimensionality: Option<i32>,
@@ -25,17 +104,23 @@ impl EmbedBuilder {
     pub(crate) fn new(client: Arc<GeminiClient>) -> Self {
         Self {
             client,
-            contents: Vec::new(),
+            model: None,
+            inputs: Vec::new(),
             task_type: None,
             title: None,
             output_dimensionality: None,
         }
     }
 
+    /// Use an embedding model other than the client's model
+    pub fn with_model<M: Into<Model>>(mut self, model: M) -> Self {
+        self.model = Some(model.into());
+        self
+    }
+
     /// Add a vec of text to embed to the request
     pub fn with_text(mut self, text: impl Into<String>) -> Self {
-        let message = Message::embed(text);
-        self.contents.push(message.content);
+        self.inputs.push(EmbedInput::text(text));
         self
     }
 
@@ -43,12 +128,42 @@ impl EmbedBuilder {
     pub fn with_chunks(mut self, chunks: Vec<impl Into<String>>) -> Self {
         //for each chunks
         for chunk in chunks {
-            let message = Message::embed(chunk);
-            self.contents.push(message.content);
+            self.inputs.push(EmbedInput::text(chunk));
         }
         self
     }
 
+    /// Add an item with its own task type or title
+    pub fn with_input(mut self, input: impl Into<EmbedInput>) -> Self {
+        self.inputs.push(input.into());
+        self
+    }
+
+    /// Add several items to batch embed
+    pub fn with_inputs<I: Into<EmbedInput>>(mut self, inputs: impl IntoIterator<Item = I>) -> Self {
+        self.inputs.extend(inputs.into_iter().map(Into::into));
+        self
+    }
+
+    /// Add a base64-encoded image to embed
+    pub fn with_image(self, mime_type: impl Into<String>, data: impl Into<String>) -> Self {
+        self.with_input(EmbedInput::inline_data(mime_type, data))
+    }
+
+    /// Add a base64-encoded PDF document to embed
+    pub fn with_pdf(self, data: impl Into<String>) -> Self {
+        self.with_input(EmbedInput::pdf(data))
+    }
+
+    /// Add a previously uploaded file to embed
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file metadata is incomplete (missing MIME type or URI).
+    pub fn with_file(self, file_handle: &FileHandle) -> Result<Self, FilesError> {
+        Ok(self.with_input(EmbedInput::file(file_handle)?))
+    }
+
     /// Specify embedding task type
     pub fn with_task_type(mut self, task_type: TaskType) -> Self {
         self.task_type = Some(task_type);
@@ -75,40 +190,52 @@ impl EmbedBuilder {
         output.dimensionality = self.output_dimensionality
     ))]
     pub async fn execute(self) -> Result<ContentEmbeddingResponse, ClientError> {
-        let request = EmbedContentRequest {
-            model: self.client.model.clone(),
-            content: self.contents.first().expect("No content set").clone(),
-            task_type: self.task_type,
-            title: self.title,
-            output_dimensionality: self.output_dimensionality,
-        };
+        let input = self.inputs.first().expect("No content set");
+        let request = self.request(input);
 
         self.client.embed_content(request).await
     }
 
     /// Execute the request
     #[instrument(skip_all, fields(
-        batch.size = self.contents.len(),
+        batch.size = self.inputs.len(),
         task.type = self.task_type.as_ref().map(AsRef::<str>::as_ref),
         title = self.title,
         output.dimensionality = self.output_dimensionality
     ))]
     pub async fn execute_batch(self) -> Result<BatchContentEmbeddingResponse, ClientError> {
-        let mut batch_request = BatchEmbedContentsRequest {
-            requests: Vec::new(),
-        };
-
-        for content in self.contents {
-            let request = EmbedContentRequest {
-                model: self.client.model.clone(),
-                content: content.clone(),
-                task_type: self.task_type.clone(),
-                title: self.title.clone(),
-                output_dimensionality: self.output_dimensionality,
-            };
-            batch_request.requests.push(request);
+        let batch_request = self.batch_request();
+        self.client
+            .embed_content_batch(&self.model(), batch_request)
+            .await
+    }
+
+    /// Builds one request per item.
+    pub(crate) fn batch_request(&self) -> BatchEmbedContentsRequest {
+        BatchEmbedContentsRequest {
+            requests: self
+                .inputs
+                .iter()
+                .map(|input| self.request(input))
+                .collect(),
         }
+    }
+
+    /// The model set with [`with_model`](Self::with_model), or the client's model.
+    fn model(&self) -> Model {
+        self.model
+            .clone()
+            .unwrap_or_else(|| self.client.model.clone())
+    }
 
-        self.client.embed_content_batch(batch_request).await
+    /// Builds the request for one item, falling back to the builder's settings.
+    fn request(&self, input: &EmbedInput) -> EmbedContentRequest {
+        EmbedContentRequest {
+            model: self.model(),
+            content: input.content.clone(),
+            task_type: input.task_type.clone().or_else(|| self.task_type.clone()),
+            title: input.title.clone().or_else(|| self.title.clone()),
+            output_dimensionality: self.output_dimensionality,
+        }
     }
 }
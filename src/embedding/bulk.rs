@@ -0,0 +1,456 @@
+//! Embedding large collections of documents.
+//!
+//! [`BulkEmbedder`] takes any number of documents, splits long texts with a [`Chunker`], which
+//! sizes chunks by character count rather than by tokenizing, groups the chunks into `batchEmbedContents` calls of at most
+//! [`MAX_BATCH_SIZE`] items, runs a bounded number of calls concurrently, retries calls that
+//! fail with transient errors, and yields one [`ChunkEmbedding`] per chunk in input order.
+//!
+//! ```no_run
+//! # use gemini_rust::prelude::*;
+//! # async fn example(client: &Gemini) -> Result<(), Box<dyn std::error::Error>> {
+//! use futures::StreamExt;
+//!
+//! let documents = vec!["first document", "second document"];
+//! let mut embeddings = client
+//!     .bulk_embed()
+//!     .with_concurrency(2)
+//!     .embed_stream(futures::stream::iter(documents));
+//! while let Some(chunk) = embeddings.next().await {
+//!     let chunk = chunk?;
+//!     println!("document {} bytes {}..{}", chunk.document_index, chunk.start, chunk.end);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use futures::{stream, Stream, StreamExt, TryStreamExt};
+use snafu::ResultExt;
+use std::{sync::Arc, time::Duration};
+use tokio::{sync::Mutex, time::Instant};
+use tracing::instrument;
+
+use super::model::{BatchEmbedContentsRequest, ContentEmbedding, EmbedContentRequest, TaskType};
+use super::{BatchSnafu, EmbeddingCountSnafu, Error};
+use crate::{client::GeminiClient, Message};
+
+/// The largest number of items accepted by one `batchEmbedContents` call.
+pub const MAX_BATCH_SIZE: usize = 100;
+
+/// Splits long texts into overlapping chunks of a bounded number of characters.
+///
+/// The text is not tokenized: sizes given in tokens are converted to characters with a fixed
+/// ratio (four characters per token by default, typical for English text). Text with fewer
+/// characters per token, such as code or other languages, yields chunks with more tokens
+/// than the limit, so limits should leave headroom. Chunks end at whitespace where possible,
+/// and each chunk starts `overlap_tokens` before the end of the previous one.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Chunker {
+    max_tokens: usize,
+    overlap_tokens: usize,
+    chars_per_token: f32,
+}
+
+impl Default for Chunker {
+    /// Chunks of up to 8,192 characters, about 2,048 tokens (the input limit of the Gemini
+    /// embedding models) of English text, without overlap.
+    fn default() -> Self {
+        Self {
+            max_tokens: 2048,
+            overlap_tokens: 0,
+            chars_per_token: 4.0,
+        }
+    }
+}
+
+/// A slice of a text produced by [`Chunker::split`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TextChunk<'a> {
+    /// The text of the chunk.
+    pub text: &'a str,
+    /// Byte offset of the chunk's start in the original text.
+    pub start: usize,
+    /// Byte offset just past the chunk's end in the original text.
+    pub end: usize,
+}
+
+impl Chunker {
+    /// Creates a chunker producing chunks of up to `max_tokens` estimated tokens.
+    pub fn new(max_tokens: usize) -> Self {
+        Self {
+            max_tokens,
+            ..Default::default()
+        }
+    }
+
+    /// Sets how many estimated tokens consecutive chunks share.
+    ///
+    /// The overlap is capped at half the chunk size.
+    pub fn with_overlap(mut self, overlap_tokens: usize) -> Self {
+        self.overlap_tokens = overlap_tokens;
+        self
+    }
+
+    /// Sets the number of characters assumed per token.
+    pub fn with_chars_per_token(mut self, chars_per_token: f32) -> Self {
+        self.chars_per_token = chars_per_token;
+        self
+    }
+
+    /// Returns the estimated number of tokens in `text`.
+    pub fn estimate_tokens(&self, text: &str) -> usize {
+        (text.chars().count() as f32 / self.chars_per_token).ceil() as usize
+    }
+
+    /// Splits `text` into chunks. Whitespace-only texts produce no chunks.
+    pub fn split<'a>(&self, text: &'a str) -> Vec<TextChunk<'a>> {
+        let max_chars = ((self.max_tokens as f32 * self.chars_per_token) as usize).max(1);
+        let overlap_chars =
+            ((self.overlap_tokens as f32 * self.chars_per_token) as usize).min(max_chars / 2);
+
+        let mut chunks = Vec::new();
+        let mut start = 0;
+        while start < text.len() {
+            let rest = &text[start..];
+            let hard_end = rest
+                .char_indices()
+                .nth(max_chars)
+                .map_or(text.len(), |(offset, _)| start + offset);
+            let end = if hard_end == text.len() {
+                hard_end
+            } else {
+                // Break after the last whitespace that leaves a non-empty chunk.
+                text[start..hard_end]
+                    .char_indices()
+                    .rev()
+                    .find(|(offset, c)| c.is_whitespace() && *offset > 0)
+                    .map_or(hard_end, |(offset, c)| start + offset + c.len_utf8())
+            };
+
+            let chunk = &text[start..end];
+            if !chunk.trim().is_empty() {
+                chunks.push(TextChunk {
+                    text: chunk,
+                    start,
+                    end,
+                });
+            }
+            if end == text.len() {
+                break;
+            }
+
+            let next = text[start..end]
+                .char_indices()
+                .rev()
+                .nth(overlap_chars.saturating_sub(1))
+                .filter(|_| overlap_chars > 0)
+                .map_or(end, |(offset, _)| start + offset);
+            // Always make progress, even when the overlap covers the whole chunk.
+            start = if next > start { next } else { end };
+        }
+        chunks
+    }
+}
+
+/// A document given to a [`BulkEmbedder`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BulkDocument {
+    /// An identifier copied to the document's [`ChunkEmbedding`]s.
+    pub id: Option<String>,
+    /// The text to embed.
+    pub text: String,
+    /// The document title, used with [`TaskType::RetrievalDocument`].
+    pub title: Option<String>,
+}
+
+impl BulkDocument {
+    /// Creates a document with an id.
+    pub fn new(id: impl Into<String>, text: impl Into<String>) -> Self {
+        Self {
+            id: Some(id.into()),
+            text: text.into(),
+            title: None,
+        }
+    }
+
+    /// Sets the document title.
+    pub fn with_title(mut self, title: impl Into<String>) -> Self {
+        self.title = Some(title.into());
+        self
+    }
+}
+
+impl From<String> for BulkDocument {
+    fn from(text: String) -> Self {
+        Self {
+            id: None,
+            text,
+            title: None,
+        }
+    }
+}
+
+impl From<&str> for BulkDocument {
+    fn from(text: &str) -> Self {
+        text.to_string().into()
+    }
+}
+
+/// The embedding of one chunk of a document.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChunkEmbedding {
+    /// Position of the document in the input.
+    pub document_index: usize,
+    /// The document's id, if it had one.
+    pub document_id: Option<String>,
+    /// Position of the chunk within the document.
+    pub chunk_index: usize,
+    /// Byte offset of the chunk's start in the document text.
+    pub start: usize,
+    /// Byte offset just past the chunk's end in the document text.
+    pub end: usize,
+    /// The chunk text.
+    pub text: String,
+    /// The embedding of the chunk.
+    pub embedding: ContentEmbedding,
+}
+
+/// A chunk waiting to be embedded.
+struct PendingChunk {
+    document_index: usize,
+    document_id: Option<String>,
+    title: Option<String>,
+    chunk_index: usize,
+    start: usize,
+    end: usize,
+    text: String,
+}
+
+/// Spaces requests out to stay under a requests-per-minute limit.
+#[derive(Debug)]
+struct Throttle {
+    interval: Duration,
+    next: Mutex<Option<Instant>>,
+}
+
+impl Throttle {
+    async fn wait(&self) {
+        let mut next = self.next.lock().await;
+        let now = Instant::now();
+        let slot = next.map_or(now, |next| next.max(now));
+        *next = Some(slot + self.interval);
+        drop(next);
+        tokio::time::sleep_until(slot).await;
+    }
+}
+
+/// Embeds many documents with chunking, batching, bounded concurrency and retries.
+#[derive(Clone)]
+pub struct BulkEmbedder {
+    client: Arc<GeminiClient>,
+    chunker: Chunker,
+    batch_size: usize,
+    concurrency: usize,
+    max_retries: u32,
+    initial_backoff: Duration,
+    throttle: Option<Arc<Throttle>>,
+    task_type: Option<TaskType>,
+    output_dimensionality: Option<i32>,
+}
+
+impl BulkEmbedder {
+    pub(crate) fn new(client: Arc<GeminiClient>) -> Self {
+        Self {
+            client,
+            chunker: Chunker::default(),
+            batch_size: MAX_BATCH_SIZE,
+            concurrency: 4,
+            max_retries: 3,
+            initial_backoff: Duration::from_secs(1),
+            throttle: None,
+            task_type: None,
+            output_dimensionality: None,
+        }
+    }
+
+    /// Sets how documents are split into chunks.
+    pub fn with_chunker(mut self, chunker: Chunker) -> Self {
+        self.chunker = chunker;
+        self
+    }
+
+    /// Sets the number of chunks per `batchEmbedContents` call, capped at [`MAX_BATCH_SIZE`].
+    pub fn with_batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.clamp(1, MAX_BATCH_SIZE);
+        self
+    }
+
+    /// Sets how many calls may be in flight at once.
+    pub fn with_concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    /// Sets how many times a call failing with a rate limit, server or network error is
+    /// retried, and the delay before the first retry. The delay doubles on every retry.
+    pub fn with_retries(mut self, max_retries: u32, initial_backoff: Duration) -> Self {
+        self.max_retries = max_retries;
+        self.initial_backoff = initial_backoff;
+        self
+    }
+
+    /// Limits the number of calls started per minute.
+    pub fn with_requests_per_minute(mut self, requests_per_minute: u32) -> Self {
+        self.throttle = Some(Arc::new(Throttle {
+            interval: Duration::from_secs(60) / requests_per_minute.max(1),
+            next: Mutex::new(None),
+        }));
+        self
+    }
+
+    /// Sets the embedding task type.
+    pub fn with_task_type(mut self, task_type: TaskType) -> Self {
+        self.task_type = Some(task_type);
+        self
+    }
+
+    /// Sets the number of dimensions of the returned embeddings.
+    ///
+    /// Truncated embeddings are not unit length; see
+    /// [`ContentEmbedding::normalize`].
+    pub fn with_output_dimensionality(mut self, output_dimensionality: i32) -> Self {
+        self.output_dimensionality = Some(output_dimensionality);
+        self
+    }
+
+    /// Embeds a stream of documents, yielding chunk embeddings in input order.
+    ///
+    /// Chunks are grouped into calls of up to the batch size as they become available; a
+    /// call is not held back waiting for the stream to fill it.
+    ///
+    /// A call that still fails after its retries, or that returns a different number of
+    /// embeddings than it was sent chunks, yields one error in place of its chunks; the
+    /// error lists the chunks through [`Error::chunks`]. The stream then carries on with the
+    /// next call.
+    pub fn embed_stream<S, D>(
+        &self,
+        documents: S,
+    ) -> impl Stream<Item = Result<ChunkEmbedding, Error>> + Send + 'static
+    where
+        S: Stream<Item = D> + Send + 'static,
+        D: Into<BulkDocument>,
+    {
+        let chunker = self.chunker.clone();
+        let this = self.clone();
+        documents
+            .enumerate()
+            .flat_map(move |(document_index, document)| {
+                let document: BulkDocument = document.into();
+                let chunks: Vec<PendingChunk> = chunker
+                    .split(&document.text)
+                    .into_iter()
+                    .enumerate()
+                    .map(|(chunk_index, chunk)| PendingChunk {
+                        document_index,
+                        document_id: document.id.clone(),
+                        title: document.title.clone(),
+                        chunk_index,
+                        start: chunk.start,
+                        end: chunk.end,
+                        text: chunk.text.to_string(),
+                    })
+                    .collect();
+                stream::iter(chunks)
+            })
+            .ready_chunks(self.batch_size)
+            .map(move |batch| {
+                let this = this.clone();
+                async move { this.embed_batch(batch).await }
+            })
+            .buffered(self.concurrency)
+            .flat_map(|result| {
+                let items: Vec<Result<ChunkEmbedding, Error>> = match result {
+                    Ok(items) => items.into_iter().map(Ok).collect(),
+                    Err(e) => vec![Err(e)],
+                };
+                stream::iter(items)
+            })
+    }
+
+    /// Embeds all `documents`, failing on the first call that cannot be completed.
+    pub async fn embed_all<D: Into<BulkDocument>>(
+        &self,
+        documents: impl IntoIterator<Item = D>,
+    ) -> Result<Vec<ChunkEmbedding>, Error> {
+        let documents: Vec<BulkDocument> = documents.into_iter().map(Into::into).collect();
+        self.embed_stream(stream::iter(documents))
+            .try_collect()
+            .await
+    }
+
+    #[instrument(skip_all, fields(batch.size = batch.len()))]
+    async fn embed_batch(&self, batch: Vec<PendingChunk>) -> Result<Vec<ChunkEmbedding>, Error> {
+        let chunks = || {
+            batch
+                .iter()
+                .map(|chunk| (chunk.document_index, chunk.chunk_index))
+                .collect::<Vec<_>>()
+        };
+        let request = BatchEmbedContentsRequest {
+            requests: batch
+                .iter()
+                .map(|chunk| EmbedContentRequest {
+                    model: self.client.model.clone(),
+                    content: Message::embed(chunk.text.clone()).content,
+                    task_type: self.task_type.clone(),
+                    title: chunk.title.clone(),
+                    output_dimensionality: self.output_dimensionality,
+                })
+                .collect(),
+        };
+
+        let mut backoff = self.initial_backoff;
+        let mut attempt = 0;
+        let response = loop {
+            if let Some(throttle) = &self.throttle {
+                throttle.wait().await;
+            }
+            match self
+                .client
+                .embed_content_batch(&self.client.model, request.clone())
+                .await
+            {
+                Ok(response) => break response,
+                Err(e) if attempt < self.max_retries && e.is_transient() => {
+                    attempt += 1;
+                    tracing::warn!(attempt, error = %e, "retrying embedding batch");
+                    tokio::time::sleep(backoff).await;
+                    backoff *= 2;
+                }
+                Err(e) => {
+                    return Err(Box::new(e)).context(BatchSnafu { chunks: chunks() });
+                }
+            }
+        };
+
+        if response.embeddings.len() != batch.len() {
+            return EmbeddingCountSnafu {
+                actual: response.embeddings.len(),
+                chunks: chunks(),
+            }
+            .fail();
+        }
+        Ok(batch
+            .into_iter()
+            .zip(response.embeddings)
+            .map(|(chunk, embedding)| ChunkEmbedding {
+                document_index: chunk.document_index,
+                document_id: chunk.document_id,
+                chunk_index: chunk.chunk_index,
+                start: chunk.start,
+                end: chunk.end,
+                text: chunk.text,
+                embedding,
+            })
+            .collect())
+    }
+}
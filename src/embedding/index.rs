@@ -0,0 +1,207 @@
+//! A small in-memory vector index with brute-force search.
+//!
+//! [`EmbeddingIndex`] keeps normalized embeddings together with an id and arbitrary metadata,
+//! and answers top-k queries by cosine similarity. Every query scans every entry, which is
+//! fast enough for tens of thousands of vectors; use a dedicated vector database beyond that.
+//!
+//! The index can be saved to and loaded from a JSON file.
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use snafu::{ensure, ResultExt};
+use std::{cmp::Ordering, path::Path};
+
+use super::model::ContentEmbedding;
+use super::*;
+
+/// An entry of an [`EmbeddingIndex`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IndexEntry<M> {
+    /// The id the embedding was inserted under.
+    pub id: String,
+    /// The normalized embedding.
+    pub embedding: ContentEmbedding,
+    /// The metadata stored with the embedding.
+    pub metadata: M,
+}
+
+/// A result of [`EmbeddingIndex::search`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchHit<'a, M> {
+    /// The id of the matching entry.
+    pub id: &'a str,
+    /// The cosine similarity to the query.
+    pub score: f32,
+    /// The metadata of the matching entry.
+    pub metadata: &'a M,
+}
+
+/// An in-memory collection of embeddings searchable by cosine similarity.
+///
+/// Deserialization fails if an entry's embedding does not have the index's dimensions.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "RawIndex<M>")]
+pub struct EmbeddingIndex<M = serde_json::Value> {
+    dimensions: Option<usize>,
+    entries: Vec<IndexEntry<M>>,
+}
+
+#[derive(Deserialize)]
+struct RawIndex<M> {
+    dimensions: Option<usize>,
+    entries: Vec<IndexEntry<M>>,
+}
+
+impl<M> TryFrom<RawIndex<M>> for EmbeddingIndex<M> {
+    type Error = Error;
+
+    fn try_from(raw: RawIndex<M>) -> Result<Self, Error> {
+        let mut index = Self {
+            dimensions: raw.dimensions,
+            entries: Vec::with_capacity(raw.entries.len()),
+        };
+        for entry in raw.entries {
+            index.check_dimensions(&entry.embedding)?;
+            index.dimensions = Some(entry.embedding.dimensions());
+            index.entries.push(entry);
+        }
+        Ok(index)
+    }
+}
+
+impl<M> Default for EmbeddingIndex<M> {
+    fn default() -> Self {
+        Self {
+            dimensions: None,
+            entries: Vec::new(),
+        }
+    }
+}
+
+impl<M> EmbeddingIndex<M> {
+    /// Creates an empty index. Its dimensions are set by the first insertion.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the number of entries.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether the index has no entries.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Returns the number of dimensions of the stored embeddings, once known.
+    pub fn dimensions(&self) -> Option<usize> {
+        self.dimensions
+    }
+
+    /// Returns the entries in insertion order.
+    pub fn entries(&self) -> &[IndexEntry<M>] {
+        &self.entries
+    }
+
+    /// Returns the entry with the given id.
+    pub fn get(&self, id: &str) -> Option<&IndexEntry<M>> {
+        self.entries.iter().find(|entry| entry.id == id)
+    }
+
+    /// Adds an embedding, replacing any entry with the same id.
+    ///
+    /// The embedding is normalized before being stored.
+    pub fn insert(
+        &mut self,
+        id: impl Into<String>,
+        embedding: &ContentEmbedding,
+        metadata: M,
+    ) -> Result<(), Error> {
+        self.check_dimensions(embedding)?;
+        self.dimensions = Some(embedding.dimensions());
+
+        let entry = IndexEntry {
+            id: id.into(),
+            embedding: embedding.normalized(),
+            metadata,
+        };
+        match self.entries.iter_mut().find(|e| e.id == entry.id) {
+            Some(existing) => *existing = entry,
+            None => self.entries.push(entry),
+        }
+        Ok(())
+    }
+
+    /// Removes and returns the entry with the given id.
+    pub fn remove(&mut self, id: &str) -> Option<IndexEntry<M>> {
+        let position = self.entries.iter().position(|entry| entry.id == id)?;
+        Some(self.entries.remove(position))
+    }
+
+    /// Returns the `k` entries most similar to `query`, best first.
+    pub fn search(
+        &self,
+        query: &ContentEmbedding,
+        k: usize,
+    ) -> Result<Vec<SearchHit<'_, M>>, Error> {
+        self.search_filtered(query, k, |_| true)
+    }
+
+    /// Like [`search`](Self::search), but only considers entries whose metadata matches
+    /// `filter`.
+    pub fn search_filtered(
+        &self,
+        query: &ContentEmbedding,
+        k: usize,
+        filter: impl Fn(&M) -> bool,
+    ) -> Result<Vec<SearchHit<'_, M>>, Error> {
+        self.check_dimensions(query)?;
+        let query = query.normalized();
+        let mut hits: Vec<SearchHit<'_, M>> = self
+            .entries
+            .iter()
+            .filter(|entry| filter(&entry.metadata))
+            .map(|entry| SearchHit {
+                id: &entry.id,
+                score: entry.embedding.dot(&query),
+                metadata: &entry.metadata,
+            })
+            .collect();
+        hits.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(Ordering::Equal));
+        hits.truncate(k);
+        Ok(hits)
+    }
+
+    fn check_dimensions(&self, embedding: &ContentEmbedding) -> Result<(), Error> {
+        if let Some(expected) = self.dimensions {
+            ensure!(
+                embedding.dimensions() == expected,
+                DimensionMismatchSnafu {
+                    expected,
+                    actual: embedding.dimensions(),
+                }
+            );
+        }
+        Ok(())
+    }
+}
+
+impl<M: Serialize + DeserializeOwned> EmbeddingIndex<M> {
+    /// Writes the index to a JSON file, replacing it if it exists.
+    pub async fn save(&self, path: impl AsRef<Path>) -> Result<(), Error> {
+        let path = path.as_ref();
+        let json = serde_json::to_vec(self).context(SerializeSnafu)?;
+        tokio::fs::write(path, json)
+            .await
+            .context(IndexIoSnafu { path })
+    }
+
+    /// Reads an index written by [`save`](Self::save).
+    ///
+    /// Fails if the file holds embeddings of different dimensions.
+    pub async fn load(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let path = path.as_ref();
+        let json = tokio::fs::read(path).await.context(IndexIoSnafu { path })?;
+        serde_json::from_slice(&json).context(DeserializeSnafu)
+    }
+}
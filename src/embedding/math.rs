@@ -0,0 +1,263 @@
+//! Vector operations on embeddings: normalization, similarity and quantization.
+//!
+//! Embeddings returned with their full dimensionality are already unit length. Embeddings
+//! truncated with `output_dimensionality` are not, and should be
+//! [normalized](ContentEmbedding::normalize) before comparing them with a dot product.
+
+use serde::{Deserialize, Serialize};
+use snafu::ensure;
+
+use super::model::ContentEmbedding;
+use super::{BinaryLengthSnafu, Error};
+
+impl ContentEmbedding {
+    /// Creates an embedding from its values.
+    pub fn new(values: Vec<f32>) -> Self {
+        Self { values }
+    }
+
+    /// Returns the number of dimensions.
+    pub fn dimensions(&self) -> usize {
+        self.values.len()
+    }
+
+    /// Returns the L2 norm (length) of the vector.
+    pub fn norm(&self) -> f32 {
+        self.values.iter().map(|v| v * v).sum::<f32>().sqrt()
+    }
+
+    /// Scales the vector to unit length. A zero vector is left unchanged.
+    pub fn normalize(&mut self) {
+        let norm = self.norm();
+        if norm > 0.0 {
+            self.values.iter_mut().for_each(|v| *v /= norm);
+        }
+    }
+
+    /// Returns a unit-length copy of the vector.
+    pub fn normalized(&self) -> Self {
+        let mut embedding = self.clone();
+        embedding.normalize();
+        embedding
+    }
+
+    /// Returns the dot product with `other`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the embeddings have different dimensions.
+    pub fn dot(&self, other: &ContentEmbedding) -> f32 {
+        assert_same_dimensions(self.dimensions(), other.dimensions());
+        self.values
+            .iter()
+            .zip(&other.values)
+            .map(|(a, b)| a * b)
+            .sum()
+    }
+
+    /// Returns the cosine similarity with `other`, between -1 and 1.
+    ///
+    /// Zero vectors have a similarity of 0 with everything.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the embeddings have different dimensions.
+    pub fn cosine_similarity(&self, other: &ContentEmbedding) -> f32 {
+        let norms = self.norm() * other.norm();
+        if norms == 0.0 {
+            return 0.0;
+        }
+        self.dot(other) / norms
+    }
+
+    /// Returns the Euclidean distance to `other`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the embeddings have different dimensions.
+    pub fn euclidean_distance(&self, other: &ContentEmbedding) -> f32 {
+        assert_same_dimensions(self.dimensions(), other.dimensions());
+        self.values
+            .iter()
+            .zip(&other.values)
+            .map(|(a, b)| (a - b) * (a - b))
+            .sum::<f32>()
+            .sqrt()
+    }
+
+    /// Quantizes each value to a signed byte, scaled by the largest absolute value.
+    ///
+    /// This takes a quarter of the memory and keeps similarities within about 1% of the
+    /// original.
+    pub fn quantize_int8(&self) -> Int8Embedding {
+        let max = self.values.iter().fold(0.0f32, |max, v| max.max(v.abs()));
+        let scale = if max > 0.0 { max / 127.0 } else { 1.0 };
+        Int8Embedding {
+            values: self
+                .values
+                .iter()
+                .map(|v| (v / scale).round().clamp(-127.0, 127.0) as i8)
+                .collect(),
+            scale,
+        }
+    }
+
+    /// Quantizes each value to one bit, its sign.
+    ///
+    /// This takes 1/32 of the memory. Similarity is approximated with the Hamming distance,
+    /// which is good for a fast first pass before re-ranking with full vectors.
+    pub fn quantize_binary(&self) -> BinaryEmbedding {
+        let mut bits = vec![0u8; self.values.len().div_ceil(8)];
+        for (i, value) in self.values.iter().enumerate() {
+            if *value > 0.0 {
+                bits[i / 8] |= 1 << (i % 8);
+            }
+        }
+        BinaryEmbedding {
+            bits,
+            dimensions: self.values.len(),
+        }
+    }
+}
+
+/// An embedding quantized to signed bytes by [`ContentEmbedding::quantize_int8`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Int8Embedding {
+    /// The quantized values.
+    pub values: Vec<i8>,
+    /// The value one quantization step stands for.
+    pub scale: f32,
+}
+
+impl Int8Embedding {
+    /// Returns the number of dimensions.
+    pub fn dimensions(&self) -> usize {
+        self.values.len()
+    }
+
+    /// Restores approximate floating point values.
+    pub fn dequantize(&self) -> ContentEmbedding {
+        ContentEmbedding::new(
+            self.values
+                .iter()
+                .map(|v| f32::from(*v) * self.scale)
+                .collect(),
+        )
+    }
+
+    /// Returns the approximate dot product with `other`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the embeddings have different dimensions.
+    pub fn dot(&self, other: &Int8Embedding) -> f32 {
+        assert_same_dimensions(self.dimensions(), other.dimensions());
+        let sum: i32 = self
+            .values
+            .iter()
+            .zip(&other.values)
+            .map(|(a, b)| i32::from(*a) * i32::from(*b))
+            .sum();
+        sum as f32 * self.scale * other.scale
+    }
+}
+
+/// An embedding quantized to sign bits by [`ContentEmbedding::quantize_binary`].
+///
+/// Deserialization fails if `bits` does not hold exactly `dimensions` bits.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "RawBinaryEmbedding")]
+pub struct BinaryEmbedding {
+    /// The sign bits, eight dimensions per byte, least significant bit first.
+    pub bits: Vec<u8>,
+    /// The number of dimensions.
+    pub dimensions: usize,
+}
+
+#[derive(Deserialize)]
+struct RawBinaryEmbedding {
+    bits: Vec<u8>,
+    dimensions: usize,
+}
+
+impl TryFrom<RawBinaryEmbedding> for BinaryEmbedding {
+    type Error = Error;
+
+    fn try_from(raw: RawBinaryEmbedding) -> Result<Self, Error> {
+        Self::new(raw.bits, raw.dimensions)
+    }
+}
+
+impl BinaryEmbedding {
+    /// Creates an embedding from its sign bits, which must take `dimensions.div_ceil(8)` bytes.
+    pub fn new(bits: Vec<u8>, dimensions: usize) -> Result<Self, Error> {
+        let embedding = Self { bits, dimensions };
+        embedding.check()?;
+        Ok(embedding)
+    }
+
+    /// Checks that `bits` holds exactly `dimensions` bits.
+    pub fn check(&self) -> Result<(), Error> {
+        ensure!(
+            self.bits.len() == self.dimensions.div_ceil(8),
+            BinaryLengthSnafu {
+                dimensions: self.dimensions,
+                bytes: self.bits.len(),
+            }
+        );
+        Ok(())
+    }
+
+    /// Returns the number of dimensions whose signs differ from `other`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the embeddings have different dimensions.
+    pub fn hamming_distance(&self, other: &BinaryEmbedding) -> u32 {
+        assert_same_dimensions(self.dimensions, other.dimensions);
+        self.bits
+            .iter()
+            .zip(&other.bits)
+            .map(|(a, b)| (a ^ b).count_ones())
+            .sum()
+    }
+
+    /// Returns a similarity between -1 (all signs differ) and 1 (all signs match).
+    ///
+    /// # Panics
+    ///
+    /// Panics if the embeddings have different dimensions.
+    pub fn similarity(&self, other: &BinaryEmbedding) -> f32 {
+        let distance = self.hamming_distance(other);
+        if self.dimensions == 0 {
+            return 0.0;
+        }
+        1.0 - 2.0 * distance as f32 / self.dimensions as f32
+    }
+
+    /// Restores a unit-length vector of `±1/sqrt(dimensions)` values.
+    ///
+    /// Fails if the public fields were changed so that `bits` no longer matches `dimensions`.
+    pub fn dequantize(&self) -> Result<ContentEmbedding, Error> {
+        self.check()?;
+        let magnitude = 1.0 / (self.dimensions.max(1) as f32).sqrt();
+        Ok(ContentEmbedding::new(
+            (0..self.dimensions)
+                .map(|i| {
+                    if self.bits[i / 8] & (1 << (i % 8)) != 0 {
+                        magnitude
+                    } else {
+                        -magnitude
+                    }
+                })
+                .collect(),
+        ))
+    }
+}
+
+fn assert_same_dimensions(left: usize, right: usize) {
+    assert_eq!(
+        left, right,
+        "embeddings have different dimensions ({left} and {right})"
+    );
+}
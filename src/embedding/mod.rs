@@ -4,11 +4,69 @@
 //! It includes support for both single and batch embedding operations with various task types
 //! for optimization.
 
+use snafu::Snafu;
+use std::path::PathBuf;
+
 pub mod builder;
+pub mod bulk;
+pub mod index;
+pub mod math;
 pub mod model;
 
-pub use builder::EmbedBuilder;
+pub use builder::{EmbedBuilder, EmbedInput};
+pub use bulk::{BulkDocument, BulkEmbedder, ChunkEmbedding, Chunker, TextChunk};
+pub use index::{EmbeddingIndex, IndexEntry, SearchHit};
+pub use math::{BinaryEmbedding, Int8Embedding};
 pub use model::{
     BatchContentEmbeddingResponse, BatchEmbedContentsRequest, ContentEmbedding,
     ContentEmbeddingResponse, EmbedContentRequest, TaskType,
 };
+
+#[derive(Debug, Snafu)]
+#[snafu(visibility(pub(crate)))]
+pub enum Error {
+    #[snafu(display("embedding has {actual} dimensions, expected {expected}"))]
+    DimensionMismatch { expected: usize, actual: usize },
+
+    #[snafu(display(
+        "binary embedding of {dimensions} dimensions cannot be stored in {bytes} bytes"
+    ))]
+    BinaryLength { dimensions: usize, bytes: usize },
+
+    #[snafu(display("failed to access embedding index file '{}'", path.display()))]
+    IndexIo {
+        source: std::io::Error,
+        path: PathBuf,
+    },
+
+    #[snafu(display("failed to serialize embedding index"))]
+    Serialize { source: serde_json::Error },
+
+    #[snafu(display("failed to deserialize embedding index"))]
+    Deserialize { source: serde_json::Error },
+
+    #[snafu(display("failed to embed a batch of {} chunks", chunks.len()))]
+    Batch {
+        source: Box<crate::client::Error>,
+        /// `(document_index, chunk_index)` of every chunk in the batch.
+        chunks: Vec<(usize, usize)>,
+    },
+
+    #[snafu(display("embedding batch returned {actual} embeddings for {} chunks", chunks.len()))]
+    EmbeddingCount {
+        actual: usize,
+        /// `(document_index, chunk_index)` of every chunk in the batch.
+        chunks: Vec<(usize, usize)>,
+    },
+}
+
+impl Error {
+    /// The `(document_index, chunk_index)` of every chunk a failed bulk embedding batch
+    /// contained, or an empty slice for other errors.
+    pub fn chunks(&self) -> &[(usize, usize)] {
+        match self {
+            Error::Batch { chunks, .. } | Error::EmbeddingCount { chunks, .. } => chunks,
+            _ => &[],
+        }
+    }
+}
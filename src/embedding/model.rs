@@ -7,7 +7,7 @@ use crate::{Content, Model};
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct ContentEmbedding {
     /// The values generated
-    pub values: Vec<f32>, //Maybe Quantize this
+    pub values: Vec<f32>,
 }
 
 /// Response for single embedding request
@@ -2,7 +2,11 @@ use std::sync::Arc;
 use tracing::instrument;
 
 use crate::client::{Error, GeminiClient};
-use crate::file_search::model::{extract_document_id, extract_store_name, Document, DocumentState};
+use crate::common::poll::PollPolicy;
+use crate::file_search::model::{
+    extract_document_id, extract_store_name, CustomMetadata, Document, DocumentState,
+};
+use crate::file_search::OperationHandle;
 
 /// A handle for managing a document within a file search store.
 ///
@@ -42,6 +46,60 @@ impl DocumentHandle {
         Ok(())
     }
 
+    /// Applies `patch` to the document's custom metadata and saves it.
+    ///
+    /// The whole list of entries is sent, so changes made elsewhere since the last
+    /// [`refresh`](Self::refresh) are overwritten.
+    #[instrument(skip_all, fields(document.name = %self.document.name))]
+    pub async fn update_metadata(&mut self, patch: MetadataPatch) -> Result<(), Error> {
+        let mut metadata = self.document.custom_metadata.clone().unwrap_or_default();
+        patch.apply(&mut metadata);
+        self.document = self
+            .client
+            .update_document_metadata(&self.document.name, &metadata)
+            .await?;
+        Ok(())
+    }
+
+    /// Uploads `data` again in place of this document, typically one whose processing
+    /// failed, and deletes this document once the new one is processed.
+    ///
+    /// The new document gets the same display name, MIME type and custom metadata. Its
+    /// upload is awaited with `policy`; this document is only deleted when that succeeds.
+    /// Returns the finished upload operation of the new document.
+    ///
+    /// On failure, the error comes with the upload operation if one was started, so a new
+    /// document whose processing failed, or whose predecessor could not be deleted, is not
+    /// lost.
+    #[instrument(skip_all, fields(document.name = %self.document.name, file.size = data.len()))]
+    pub async fn retry(
+        self,
+        data: Vec<u8>,
+        policy: &PollPolicy,
+    ) -> Result<OperationHandle, (Option<OperationHandle>, Error)> {
+        let store_name = extract_store_name(&self.document.name).map_err(|e| (None, e))?;
+        let operation = self
+            .client
+            .upload_to_file_search_store(
+                &store_name,
+                data,
+                self.document.display_name.clone(),
+                Some(self.document.mime_type.clone()),
+                self.document.custom_metadata.clone(),
+                None,
+            )
+            .await
+            .map_err(|e| (None, e))?;
+        let mut operation = OperationHandle::new(self.client.clone(), operation);
+        if let Err(e) = operation.wait(policy).await {
+            return Err((Some(operation), e));
+        }
+        match self.delete(true).await {
+            Ok(()) => Ok(operation),
+            Err(e) => Err((Some(operation), e)),
+        }
+    }
+
     #[instrument(skip_all, fields(document.name = %self.document.name, force))]
     pub async fn delete(self, force: bool) -> Result<(), Error> {
         let store_name = extract_store_name(&self.document.name)?;
@@ -51,3 +109,38 @@ impl DocumentHandle {
             .await
     }
 }
+
+/// Changes to a document's custom metadata, for [`DocumentHandle::update_metadata`].
+///
+/// Entries not mentioned by the patch are kept.
+#[derive(Debug, Clone, Default)]
+pub struct MetadataPatch {
+    set: Vec<CustomMetadata>,
+    remove: Vec<String>,
+}
+
+impl MetadataPatch {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds an entry, replacing any entry with the same key.
+    pub fn set(mut self, entry: CustomMetadata) -> Self {
+        self.set.push(entry);
+        self
+    }
+
+    /// Removes the entry with the given key.
+    pub fn remove(mut self, key: impl Into<String>) -> Self {
+        self.remove.push(key.into());
+        self
+    }
+
+    /// Applies the patch to a list of metadata entries.
+    pub fn apply(&self, metadata: &mut Vec<CustomMetadata>) {
+        metadata.retain(|entry| {
+            !self.remove.contains(&entry.key) && self.set.iter().all(|set| set.key != entry.key)
+        });
+        metadata.extend(self.set.iter().cloned());
+    }
+}
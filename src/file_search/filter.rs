@@ -0,0 +1,529 @@
+//! Typed metadata filters for File Search.
+//!
+//! File Search narrows retrieval to documents whose custom metadata matches a filter written
+//! in [AIP-160](https://google.aip.dev/160) syntax. A filter with a misspelled key or a value
+//! of the wrong type is not rejected by the API, it just matches nothing. [`MetadataFilter`]
+//! builds filters from typed parts, renders them with correct quoting and can be checked
+//! against a [`MetadataSchema`] describing the metadata the documents carry.
+
+use snafu::{ensure, OptionExt, Snafu};
+use std::collections::BTreeMap;
+use std::fmt;
+
+use crate::file_search::model::{CustomMetadata, CustomMetadataValue};
+
+#[derive(Debug, Snafu)]
+#[snafu(visibility(pub(crate)))]
+pub enum FilterError {
+    #[snafu(display("'{key}' is not a valid metadata key"))]
+    InvalidKey { key: String },
+
+    #[snafu(display("metadata key '{key}' is not in the schema"))]
+    UnknownKey { key: String },
+
+    #[snafu(display(
+        "metadata key '{key}' holds {actual} values, but '{operator}' needs {expected}"
+    ))]
+    KindMismatch {
+        key: String,
+        operator: &'static str,
+        expected: MetadataKind,
+        actual: MetadataKind,
+    },
+
+    #[snafu(display("metadata key '{key}' is compared with a non-finite number"))]
+    NonFiniteNumber { key: String },
+
+    #[snafu(display("a metadata filter group has no conditions"))]
+    EmptyGroup,
+}
+
+/// The kind of value stored under a metadata key, mirroring [`CustomMetadataValue`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MetadataKind {
+    String,
+    StringList,
+    Numeric,
+}
+
+impl MetadataKind {
+    /// Returns the kind of a metadata value.
+    pub fn of(value: &CustomMetadataValue) -> Self {
+        match value {
+            CustomMetadataValue::StringValue { .. } => Self::String,
+            CustomMetadataValue::StringListValue { .. } => Self::StringList,
+            CustomMetadataValue::NumericValue { .. } => Self::Numeric,
+        }
+    }
+}
+
+impl fmt::Display for MetadataKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::String => "string",
+            Self::StringList => "string list",
+            Self::Numeric => "numeric",
+        })
+    }
+}
+
+/// The metadata keys documents carry and the kind of value under each.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MetadataSchema {
+    keys: BTreeMap<String, MetadataKind>,
+}
+
+impl MetadataSchema {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_key(mut self, key: impl Into<String>, kind: MetadataKind) -> Self {
+        self.keys.insert(key.into(), kind);
+        self
+    }
+
+    /// Infers a schema from the metadata of a sample document.
+    pub fn from_metadata(metadata: &[CustomMetadata]) -> Self {
+        Self {
+            keys: metadata
+                .iter()
+                .map(|entry| (entry.key.clone(), MetadataKind::of(&entry.value)))
+                .collect(),
+        }
+    }
+
+    /// Returns the kind of value stored under `key`.
+    pub fn kind(&self, key: &str) -> Option<MetadataKind> {
+        self.keys.get(key).copied()
+    }
+}
+
+/// A value a metadata key is compared with.
+#[derive(Debug, Clone, PartialEq)]
+pub enum FilterValue {
+    String(String),
+    Numeric(f64),
+}
+
+impl FilterValue {
+    fn kind(&self) -> MetadataKind {
+        match self {
+            Self::String(_) => MetadataKind::String,
+            Self::Numeric(_) => MetadataKind::Numeric,
+        }
+    }
+}
+
+impl fmt::Display for FilterValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::String(value) => write_quoted(f, value),
+            Self::Numeric(value) => write!(f, "{value}"),
+        }
+    }
+}
+
+impl From<String> for FilterValue {
+    fn from(value: String) -> Self {
+        Self::String(value)
+    }
+}
+
+impl From<&str> for FilterValue {
+    fn from(value: &str) -> Self {
+        Self::String(value.to_string())
+    }
+}
+
+macro_rules! numeric_filter_value {
+    ($($ty:ty),*) => {
+        $(impl From<$ty> for FilterValue {
+            fn from(value: $ty) -> Self {
+                Self::Numeric(value as f64)
+            }
+        })*
+    };
+}
+
+numeric_filter_value!(f64, f32, i64, i32, u32, u64, usize);
+
+/// A comparison operator.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Comparison {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl Comparison {
+    fn operator(self) -> &'static str {
+        match self {
+            Self::Eq => "=",
+            Self::Ne => "!=",
+            Self::Lt => "<",
+            Self::Le => "<=",
+            Self::Gt => ">",
+            Self::Ge => ">=",
+        }
+    }
+}
+
+/// A filter on document metadata, rendered to AIP-160 syntax by its
+/// [`Display`](fmt::Display) implementation.
+///
+/// # Example
+///
+/// ```
+/// use gemini_rust::{MetadataFilter, MetadataKind, MetadataSchema};
+///
+/// let filter = MetadataFilter::eq("author", "Robert \"R.\" Graves")
+///     .and(MetadataFilter::ge("year", 1930))
+///     .and(MetadataFilter::has("tags", "poetry").or(MetadataFilter::is_in(
+///         "genre",
+///         ["myth", "epic"],
+///     )));
+///
+/// assert_eq!(
+///     filter.to_string(),
+///     r#"author = "Robert \"R.\" Graves" AND year >= 1930 AND (tags:"poetry" OR (genre = "myth" OR genre = "epic"))"#
+/// );
+///
+/// let schema = MetadataSchema::new()
+///     .with_key("author", MetadataKind::String)
+///     .with_key("year", MetadataKind::Numeric)
+///     .with_key("tags", MetadataKind::StringList);
+/// assert!(filter.validate(&schema).is_err()); // "genre" is not in the schema
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub enum MetadataFilter {
+    /// Compares the value under `key` with `value`.
+    Compare {
+        key: String,
+        comparison: Comparison,
+        value: FilterValue,
+    },
+    /// Matches when the value under `key` equals any of `values`.
+    In {
+        key: String,
+        values: Vec<FilterValue>,
+    },
+    /// Matches when the string list under `key` contains `value`.
+    Has { key: String, value: String },
+    /// Matches when all filters match.
+    And(Vec<MetadataFilter>),
+    /// Matches when any filter matches.
+    Or(Vec<MetadataFilter>),
+    /// Matches when the filter does not.
+    Not(Box<MetadataFilter>),
+}
+
+impl MetadataFilter {
+    fn compare(key: impl Into<String>, comparison: Comparison, value: FilterValue) -> Self {
+        Self::Compare {
+            key: key.into(),
+            comparison,
+            value,
+        }
+    }
+
+    /// `key = value`, for string or numeric values.
+    pub fn eq(key: impl Into<String>, value: impl Into<FilterValue>) -> Self {
+        Self::compare(key, Comparison::Eq, value.into())
+    }
+
+    /// `key != value`, for string or numeric values.
+    pub fn ne(key: impl Into<String>, value: impl Into<FilterValue>) -> Self {
+        Self::compare(key, Comparison::Ne, value.into())
+    }
+
+    /// `key < value`, for numeric values.
+    pub fn lt(key: impl Into<String>, value: impl Into<f64>) -> Self {
+        Self::compare(key, Comparison::Lt, FilterValue::Numeric(value.into()))
+    }
+
+    /// `key <= value`, for numeric values.
+    pub fn le(key: impl Into<String>, value: impl Into<f64>) -> Self {
+        Self::compare(key, Comparison::Le, FilterValue::Numeric(value.into()))
+    }
+
+    /// `key > value`, for numeric values.
+    pub fn gt(key: impl Into<String>, value: impl Into<f64>) -> Self {
+        Self::compare(key, Comparison::Gt, FilterValue::Numeric(value.into()))
+    }
+
+    /// `key >= value`, for numeric values.
+    pub fn ge(key: impl Into<String>, value: impl Into<f64>) -> Self {
+        Self::compare(key, Comparison::Ge, FilterValue::Numeric(value.into()))
+    }
+
+    /// Matches when the value under `key` equals any of `values`.
+    pub fn is_in<V: Into<FilterValue>>(
+        key: impl Into<String>,
+        values: impl IntoIterator<Item = V>,
+    ) -> Self {
+        Self::In {
+            key: key.into(),
+            values: values.into_iter().map(Into::into).collect(),
+        }
+    }
+
+    /// Matches when the string list under `key` contains `value`.
+    pub fn has(key: impl Into<String>, value: impl Into<String>) -> Self {
+        Self::Has {
+            key: key.into(),
+            value: value.into(),
+        }
+    }
+
+    /// Matches when every filter matches.
+    pub fn all(filters: impl IntoIterator<Item = MetadataFilter>) -> Self {
+        Self::And(filters.into_iter().collect())
+    }
+
+    /// Matches when any filter matches.
+    pub fn any(filters: impl IntoIterator<Item = MetadataFilter>) -> Self {
+        Self::Or(filters.into_iter().collect())
+    }
+
+    /// Matches when both this filter and `other` match.
+    pub fn and(self, other: MetadataFilter) -> Self {
+        match self {
+            Self::And(mut filters) => {
+                filters.push(other);
+                Self::And(filters)
+            }
+            filter => Self::And(vec![filter, other]),
+        }
+    }
+
+    /// Matches when this filter or `other` matches.
+    pub fn or(self, other: MetadataFilter) -> Self {
+        match self {
+            Self::Or(mut filters) => {
+                filters.push(other);
+                Self::Or(filters)
+            }
+            filter => Self::Or(vec![filter, other]),
+        }
+    }
+
+    /// Matches when this filter does not.
+    #[allow(clippy::should_implement_trait)]
+    pub fn not(self) -> Self {
+        Self::Not(Box::new(self))
+    }
+
+    /// Checks that keys are well-formed, numbers are finite and groups are not empty.
+    pub fn check(&self) -> Result<(), FilterError> {
+        self.visit(&mut |_, _, _| Ok(()))
+    }
+
+    /// Like [`check`](Self::check), and also checks that every key is in `schema` and holds
+    /// values of the kind its operator needs.
+    pub fn validate(&self, schema: &MetadataSchema) -> Result<(), FilterError> {
+        self.visit(&mut |key, operator, expected| {
+            let actual = schema.kind(key).context(UnknownKeySnafu { key })?;
+            ensure!(
+                actual == expected,
+                KindMismatchSnafu {
+                    key,
+                    operator,
+                    expected,
+                    actual,
+                }
+            );
+            Ok(())
+        })
+    }
+
+    /// Evaluates the filter against a document's metadata.
+    ///
+    /// A condition on a key the document lacks, or whose value is of another kind, does not
+    /// match; this includes `!=`.
+    pub fn matches(&self, metadata: &[CustomMetadata]) -> bool {
+        let value = |key: &str| {
+            metadata
+                .iter()
+                .find(|entry| entry.key == key)
+                .map(|entry| &entry.value)
+        };
+        let equals = |key: &str, expected: &FilterValue| match (value(key), expected) {
+            (Some(CustomMetadataValue::StringValue { string_value }), FilterValue::String(s)) => {
+                Some(string_value == s)
+            }
+            (
+                Some(CustomMetadataValue::NumericValue { numeric_value }),
+                FilterValue::Numeric(n),
+            ) => Some(numeric_value == n),
+            _ => None,
+        };
+        match self {
+            Self::Compare {
+                key,
+                comparison,
+                value: expected,
+            } => match comparison {
+                Comparison::Eq => equals(key, expected) == Some(true),
+                Comparison::Ne => equals(key, expected) == Some(false),
+                ordering => {
+                    let (
+                        Some(CustomMetadataValue::NumericValue { numeric_value }),
+                        FilterValue::Numeric(n),
+                    ) = (value(key), expected)
+                    else {
+                        return false;
+                    };
+                    match ordering {
+                        Comparison::Lt => numeric_value < n,
+                        Comparison::Le => numeric_value <= n,
+                        Comparison::Gt => numeric_value > n,
+                        _ => numeric_value >= n,
+                    }
+                }
+            },
+            Self::In { key, values } => values.iter().any(|v| equals(key, v) == Some(true)),
+            Self::Has { key, value: item } => matches!(
+                value(key),
+                Some(CustomMetadataValue::StringListValue { string_list_value })
+                    if string_list_value.values.contains(item)
+            ),
+            Self::And(filters) => filters.iter().all(|filter| filter.matches(metadata)),
+            Self::Or(filters) => filters.iter().any(|filter| filter.matches(metadata)),
+            Self::Not(filter) => !filter.matches(metadata),
+        }
+    }
+
+    /// Calls `check_key` with the key, operator and expected value kind of every condition.
+    fn visit(
+        &self,
+        check_key: &mut impl FnMut(&str, &'static str, MetadataKind) -> Result<(), FilterError>,
+    ) -> Result<(), FilterError> {
+        let mut check_value = |key: &str, operator, value: &FilterValue| {
+            ensure!(is_valid_key(key), InvalidKeySnafu { key });
+            if let FilterValue::Numeric(number) = value {
+                ensure!(number.is_finite(), NonFiniteNumberSnafu { key });
+            }
+            check_key(key, operator, value.kind())
+        };
+        match self {
+            Self::Compare {
+                key,
+                comparison,
+                value,
+            } => check_value(key, comparison.operator(), value),
+            Self::In { key, values } => {
+                ensure!(!values.is_empty(), EmptyGroupSnafu);
+                values
+                    .iter()
+                    .try_for_each(|value| check_value(key, "=", value))
+            }
+            Self::Has { key, .. } => {
+                ensure!(is_valid_key(key), InvalidKeySnafu { key });
+                check_key(key, ":", MetadataKind::StringList)
+            }
+            Self::And(filters) | Self::Or(filters) => {
+                ensure!(!filters.is_empty(), EmptyGroupSnafu);
+                filters
+                    .iter()
+                    .try_for_each(|filter| filter.visit(&mut *check_key))
+            }
+            Self::Not(filter) => filter.visit(check_key),
+        }
+    }
+
+    /// Writes the filter, in parentheses if it is made of several conditions.
+    fn fmt_operand(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let compound = match self {
+            Self::And(filters) | Self::Or(filters) => filters.len() > 1,
+            Self::In { values, .. } => values.len() > 1,
+            _ => false,
+        };
+        if compound {
+            write!(f, "({self})")
+        } else {
+            write!(f, "{self}")
+        }
+    }
+}
+
+impl fmt::Display for MetadataFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Compare {
+                key,
+                comparison,
+                value,
+            } => write!(f, "{key} {} {value}", comparison.operator()),
+            Self::In { key, values } => {
+                for (i, value) in values.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(" OR ")?;
+                    }
+                    write!(f, "{key} = {value}")?;
+                }
+                Ok(())
+            }
+            Self::Has { key, value } => {
+                write!(f, "{key}:")?;
+                write_quoted(f, value)
+            }
+            Self::And(filters) | Self::Or(filters) => {
+                let separator = if matches!(self, Self::And(_)) {
+                    " AND "
+                } else {
+                    " OR "
+                };
+                for (i, filter) in filters.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(separator)?;
+                    }
+                    filter.fmt_operand(f)?;
+                }
+                Ok(())
+            }
+            Self::Not(filter) => {
+                f.write_str("NOT ")?;
+                match **filter {
+                    Self::Not(_) => write!(f, "({filter})"),
+                    _ => filter.fmt_operand(f),
+                }
+            }
+        }
+    }
+}
+
+impl From<MetadataFilter> for String {
+    fn from(filter: MetadataFilter) -> Self {
+        filter.to_string()
+    }
+}
+
+impl From<&MetadataFilter> for String {
+    fn from(filter: &MetadataFilter) -> Self {
+        filter.to_string()
+    }
+}
+
+fn is_valid_key(key: &str) -> bool {
+    let mut chars = key.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.')
+}
+
+fn write_quoted(f: &mut fmt::Formatter<'_>, value: &str) -> fmt::Result {
+    f.write_str("\"")?;
+    for c in value.chars() {
+        match c {
+            '"' | '\\' => write!(f, "\\{c}")?,
+            '\n' => f.write_str("\\n")?,
+            c => write!(f, "{c}")?,
+        }
+    }
+    f.write_str("\"")
+}
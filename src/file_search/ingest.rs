@@ -0,0 +1,433 @@
+use futures::{stream, StreamExt};
+use glob::{MatchOptions, Pattern};
+use mime::Mime;
+use snafu::ResultExt;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tracing::instrument;
+
+use crate::client::{Error, GeminiClient, InvalidGlobSnafu, IoSnafu};
+use crate::common::poll::PollPolicy;
+use crate::file_search::model::{ChunkingConfig, CustomMetadata};
+use crate::file_search::OperationHandle;
+
+type MetadataFn = dyn Fn(&IngestFile) -> Vec<CustomMetadata> + Send + Sync;
+
+/// A file about to be uploaded by an [`IngestBuilder`], passed to the metadata callback.
+#[derive(Debug, Clone)]
+pub struct IngestFile {
+    /// The path of the file.
+    pub path: PathBuf,
+    /// The path relative to the ingested directory, used as the document's display name.
+    pub relative_path: PathBuf,
+    /// The MIME type inferred from the file extension.
+    pub mime_type: Mime,
+    /// The file contents.
+    pub data: Vec<u8>,
+}
+
+impl IngestFile {
+    /// Returns the contents as text, if they are valid UTF-8.
+    pub fn text(&self) -> Option<&str> {
+        std::str::from_utf8(&self.data).ok()
+    }
+
+    /// Returns the `key: value` pairs of a front matter block delimited by `---` lines at the
+    /// start of the file, as used by Markdown static site generators.
+    ///
+    /// Only flat scalar entries are returned; quotes around values are removed.
+    pub fn frontmatter(&self) -> Vec<(String, String)> {
+        let Some(text) = self.text() else {
+            return vec![];
+        };
+        let mut lines = text.lines();
+        if lines.next().map(str::trim_end) != Some("---") {
+            return vec![];
+        }
+        lines
+            .take_while(|line| line.trim_end() != "---")
+            .filter(|line| !line.starts_with([' ', '\t', '-', '#']))
+            .filter_map(|line| line.split_once(':'))
+            .map(|(key, value)| {
+                let value = value.trim();
+                let value = value
+                    .strip_prefix('"')
+                    .and_then(|v| v.strip_suffix('"'))
+                    .or_else(|| value.strip_prefix('\'').and_then(|v| v.strip_suffix('\'')))
+                    .unwrap_or(value);
+                (key.trim().to_string(), value.to_string())
+            })
+            .filter(|(_, value)| !value.is_empty())
+            .collect()
+    }
+}
+
+/// Why a file was not uploaded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SkipReason {
+    /// The file matched none of the include patterns.
+    NotIncluded,
+    /// The file matched an exclude pattern.
+    Excluded,
+    /// No MIME type is known for the file extension.
+    UnknownMimeType,
+    /// The file is larger than the configured limit.
+    TooLarge { size: u64, limit: u64 },
+}
+
+/// A file that was uploaded and indexed.
+#[derive(Debug, Clone)]
+pub struct IngestedDocument {
+    /// The path relative to the ingested directory.
+    pub path: PathBuf,
+    /// The resource name of the created document, when the operation reports it.
+    pub document_name: Option<String>,
+    /// The completed upload operation.
+    pub operation: OperationHandle,
+}
+
+/// A file whose upload or processing failed.
+#[derive(Debug)]
+pub struct FailedIngest {
+    /// The path relative to the ingested directory.
+    pub path: PathBuf,
+    /// What went wrong.
+    pub error: Error,
+}
+
+/// A file that was not uploaded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SkippedFile {
+    /// The path relative to the ingested directory.
+    pub path: PathBuf,
+    /// Why the file was skipped.
+    pub reason: SkipReason,
+}
+
+/// The outcome of [`IngestBuilder::execute`]. Each list is sorted by path.
+#[derive(Debug, Default)]
+pub struct IngestReport {
+    pub succeeded: Vec<IngestedDocument>,
+    pub failed: Vec<FailedIngest>,
+    pub skipped: Vec<SkippedFile>,
+}
+
+/// Builder for uploading every file of a directory tree to a file search store.
+///
+/// Files are matched against include and exclude glob patterns. A pattern containing `/`
+/// is matched against the path relative to the directory (`**` matches any number of
+/// directories); any other pattern is matched against the file name alone. Without include
+/// patterns, every file is included.
+///
+/// # Example
+///
+/// ```no_run
+/// use gemini_rust::prelude::*;
+/// # async fn example(store: FileSearchStoreHandle) -> Result<(), Box<dyn std::error::Error>> {
+/// let report = store
+///     .ingest_directory("./docs")
+///     .with_include("*.md")?
+///     .with_exclude("drafts/**")?
+///     .with_concurrency(8)
+///     .execute()
+///     .await?;
+///
+/// println!(
+///     "{} uploaded, {} failed, {} skipped",
+///     report.succeeded.len(),
+///     report.failed.len(),
+///     report.skipped.len()
+/// );
+/// # Ok(())
+/// # }
+/// ```
+pub struct IngestBuilder {
+    pub(crate) client: Arc<GeminiClient>,
+    pub(crate) store_name: String,
+    pub(crate) root: PathBuf,
+    pub(crate) selector: FileSelector,
+    pub(crate) metadata: Option<Arc<MetadataFn>>,
+    pub(crate) chunking_config: Option<ChunkingConfig>,
+    pub(crate) concurrency: usize,
+    pub(crate) poll_policy: PollPolicy,
+}
+
+impl IngestBuilder {
+    pub(crate) fn new(client: Arc<GeminiClient>, store_name: String, root: PathBuf) -> Self {
+        Self {
+            client,
+            store_name,
+            root,
+            selector: FileSelector::default(),
+            metadata: None,
+            chunking_config: None,
+            concurrency: 4,
+            poll_policy: PollPolicy::default(),
+        }
+    }
+
+    /// Only uploads files matching `pattern`, or any other include pattern: the path relative
+    /// to the directory if the pattern contains `/`, the file name otherwise.
+    pub fn with_include(mut self, pattern: &str) -> Result<Self, Error> {
+        self.selector.include.push(parse_pattern(pattern)?);
+        Ok(self)
+    }
+
+    /// Skips files matching `pattern`, matched like include patterns; exclusion wins over
+    /// inclusion.
+    pub fn with_exclude(mut self, pattern: &str) -> Result<Self, Error> {
+        self.selector.exclude.push(parse_pattern(pattern)?);
+        Ok(self)
+    }
+
+    /// Derives each document's custom metadata from the file, for example from its path or
+    /// [front matter](IngestFile::frontmatter).
+    pub fn with_metadata(
+        mut self,
+        metadata: impl Fn(&IngestFile) -> Vec<CustomMetadata> + Send + Sync + 'static,
+    ) -> Self {
+        self.metadata = Some(Arc::new(metadata));
+        self
+    }
+
+    /// Sets how every uploaded file is split into chunks; the store's default otherwise.
+    pub fn with_chunking_config(mut self, config: ChunkingConfig) -> Self {
+        self.chunking_config = Some(config);
+        self
+    }
+
+    /// Sets how many files are uploaded and processed at once.
+    pub fn with_concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    /// Skips files larger than `bytes`.
+    pub fn with_max_file_size(mut self, bytes: u64) -> Self {
+        self.selector.max_file_size = Some(bytes);
+        self
+    }
+
+    /// Sets how the upload operations are polled until the documents are indexed.
+    pub fn with_poll_policy(mut self, policy: PollPolicy) -> Self {
+        self.poll_policy = policy;
+        self
+    }
+
+    /// Walks the directory, uploads the selected files and waits for them to be indexed.
+    ///
+    /// Fails only if the directory itself cannot be read; problems with individual files
+    /// are listed in the report.
+    #[instrument(skip_all, fields(
+        store.name = %self.store_name,
+        root = %self.root.display(),
+        files.total,
+    ))]
+    pub async fn execute(self) -> Result<IngestReport, Error> {
+        let mut report = IngestReport::default();
+        let selected = self.select(&mut report).await?;
+
+        let this = &self;
+        let mut results = stream::iter(selected)
+            .map(|relative_path| async move {
+                let result = this.ingest_file(&relative_path, |_| Vec::new()).await;
+                (relative_path, result)
+            })
+            .buffer_unordered(self.concurrency);
+        while let Some((path, result)) = results.next().await {
+            match result {
+                Ok(operation) => report.succeeded.push(IngestedDocument {
+                    document_name: operation.document_name().map(str::to_string),
+                    path,
+                    operation,
+                }),
+                Err(error) => report.failed.push(FailedIngest { path, error }),
+            }
+        }
+        drop(results);
+
+        report.succeeded.sort_by(|a, b| a.path.cmp(&b.path));
+        report.failed.sort_by(|a, b| a.path.cmp(&b.path));
+        report.skipped.sort_by(|a, b| a.path.cmp(&b.path));
+        Ok(report)
+    }
+
+    /// Walks the directory and returns the files to upload, recording the others in `report`.
+    pub(crate) async fn select(&self, report: &mut IngestReport) -> Result<Vec<PathBuf>, Error> {
+        let (files, unreadable) = walk(&self.root).await?;
+        report.failed.extend(unreadable);
+        tracing::Span::current().record("files.total", files.len());
+
+        let mut selected = Vec::new();
+        for (relative_path, size) in files {
+            match self.selector.skip_reason(&relative_path, size) {
+                Some(reason) => report.skipped.push(SkippedFile {
+                    path: relative_path,
+                    reason,
+                }),
+                None => selected.push(relative_path),
+            }
+        }
+        Ok(selected)
+    }
+
+    /// Uploads a file and waits for it to be indexed. The entries returned by
+    /// `extra_metadata` are added to those of the metadata callback, replacing those with the
+    /// same keys.
+    pub(crate) async fn ingest_file(
+        &self,
+        relative_path: &Path,
+        extra_metadata: impl FnOnce(&IngestFile) -> Vec<CustomMetadata>,
+    ) -> Result<OperationHandle, Error> {
+        let path = self.root.join(relative_path);
+        let data = tokio::fs::read(&path).await.context(IoSnafu)?;
+        let file = IngestFile {
+            mime_type: mime_guess::from_path(relative_path).first_or_octet_stream(),
+            relative_path: relative_path.to_path_buf(),
+            path,
+            data,
+        };
+        let mut metadata = self
+            .metadata
+            .as_ref()
+            .map(|metadata| metadata(&file))
+            .unwrap_or_default();
+        let extra_metadata = extra_metadata(&file);
+        metadata.retain(|entry| extra_metadata.iter().all(|extra| extra.key != entry.key));
+        metadata.extend(extra_metadata);
+        let metadata = Some(metadata).filter(|metadata| !metadata.is_empty());
+
+        let operation = self
+            .client
+            .upload_to_file_search_store(
+                &self.store_name,
+                file.data,
+                Some(display_path(relative_path)),
+                Some(file.mime_type),
+                metadata,
+                self.chunking_config.clone(),
+            )
+            .await?;
+        let mut operation = OperationHandle::new(self.client.clone(), operation);
+        operation.wait(&self.poll_policy).await?;
+        Ok(operation)
+    }
+}
+
+/// The include and exclude rules of an [`IngestBuilder`].
+#[derive(Debug, Clone, Default)]
+pub(crate) struct FileSelector {
+    pub(crate) include: Vec<Pattern>,
+    pub(crate) exclude: Vec<Pattern>,
+    pub(crate) max_file_size: Option<u64>,
+}
+
+impl FileSelector {
+    pub(crate) fn skip_reason(&self, relative_path: &Path, size: u64) -> Option<SkipReason> {
+        if !self.include.is_empty() && !self.include.iter().any(|p| matches(p, relative_path)) {
+            return Some(SkipReason::NotIncluded);
+        }
+        if self.exclude.iter().any(|p| matches(p, relative_path)) {
+            return Some(SkipReason::Excluded);
+        }
+        if mime_guess::from_path(relative_path).first().is_none() {
+            return Some(SkipReason::UnknownMimeType);
+        }
+        match self.max_file_size {
+            Some(limit) if size > limit => Some(SkipReason::TooLarge { size, limit }),
+            _ => None,
+        }
+    }
+}
+
+fn parse_pattern(pattern: &str) -> Result<Pattern, Error> {
+    Pattern::new(pattern).context(InvalidGlobSnafu { pattern })
+}
+
+fn matches(pattern: &Pattern, relative_path: &Path) -> bool {
+    let options = MatchOptions {
+        require_literal_separator: true,
+        ..Default::default()
+    };
+    if pattern.as_str().contains('/') {
+        pattern.matches_with(&display_path(relative_path), options)
+    } else {
+        relative_path
+            .file_name()
+            .is_some_and(|name| pattern.matches_with(&name.to_string_lossy(), options))
+    }
+}
+
+/// Formats a relative path with `/` separators on every platform.
+pub(crate) fn display_path(relative_path: &Path) -> String {
+    relative_path
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Lists the regular files below `root` with their sizes, as paths relative to `root`.
+///
+/// Symbolic links to files are followed; links to directories are not, to avoid cycles.
+/// Subdirectories and entries that cannot be read are returned as failures, and the walk
+/// carries on with the rest; only an unreadable `root` is an error.
+pub(crate) async fn walk(root: &Path) -> Result<(Vec<(PathBuf, u64)>, Vec<FailedIngest>), Error> {
+    let mut files = Vec::new();
+    let mut failed = Vec::new();
+    let mut pending = vec![PathBuf::new()];
+
+    while let Some(relative_dir) = pending.pop() {
+        let mut entries = match tokio::fs::read_dir(root.join(&relative_dir)).await {
+            Ok(entries) => entries,
+            Err(e) if relative_dir.as_os_str().is_empty() => return Err(e).context(IoSnafu),
+            Err(e) => {
+                failed.push(FailedIngest {
+                    path: relative_dir,
+                    error: Error::Io { source: e },
+                });
+                continue;
+            }
+        };
+        loop {
+            let entry = match entries.next_entry().await {
+                Ok(Some(entry)) => entry,
+                Ok(None) => break,
+                Err(e) => {
+                    // The rest of the directory cannot be listed.
+                    failed.push(FailedIngest {
+                        path: relative_dir.clone(),
+                        error: Error::Io { source: e },
+                    });
+                    break;
+                }
+            };
+            let relative_path = relative_dir.join(entry.file_name());
+            let file_type = match entry.file_type().await {
+                Ok(file_type) => file_type,
+                Err(e) => {
+                    failed.push(FailedIngest {
+                        path: relative_path,
+                        error: Error::Io { source: e },
+                    });
+                    continue;
+                }
+            };
+            if file_type.is_dir() {
+                pending.push(relative_path);
+                continue;
+            }
+            match tokio::fs::metadata(entry.path()).await {
+                Ok(metadata) if metadata.is_file() => files.push((relative_path, metadata.len())),
+                Ok(_) => {}
+                Err(e) => failed.push(FailedIngest {
+                    path: relative_path,
+                    error: Error::Io { source: e },
+                }),
+            }
+        }
+    }
+
+    files.sort();
+    Ok((files, failed))
+}
@@ -1,17 +1,37 @@
 pub mod document_builder;
 pub mod document_handle;
+pub mod filter;
 pub mod import_builder;
+pub mod ingest;
 pub mod model;
 pub mod operation_handle;
+pub mod operation_set;
+pub mod query_builder;
+pub mod stats;
 pub mod store_builder;
 pub mod store_handle;
+pub mod sync;
 pub mod upload_builder;
 
 pub use document_builder::DocumentBuilder;
-pub use document_handle::DocumentHandle;
+pub use document_handle::{DocumentHandle, MetadataPatch};
+pub use filter::{
+    Comparison, FilterError, FilterValue, MetadataFilter, MetadataKind, MetadataSchema,
+};
 pub use import_builder::ImportBuilder;
+pub use ingest::{
+    FailedIngest, IngestBuilder, IngestFile, IngestReport, IngestedDocument, SkipReason,
+    SkippedFile,
+};
 pub use model::*;
 pub use operation_handle::OperationHandle;
+pub use operation_set::{OperationCounts, OperationOutcome, OperationProgress, OperationSet};
+pub use query_builder::{QueryBuilder, RetrievedChunk};
+pub use stats::StoreStats;
 pub use store_builder::FileSearchStoreBuilder;
 pub use store_handle::FileSearchStoreHandle;
+pub use sync::{
+    SyncAction, SyncBuilder, SyncChange, SyncPlan, SyncReport, CONTENT_HASH_KEY, SOURCE_PATH_KEY,
+    SYNC_ROOT_KEY,
+};
 pub use upload_builder::UploadBuilder;
@@ -139,12 +139,47 @@ pub struct CustomMetadata {
     pub value: CustomMetadataValue,
 }
 
+/// The value of a [`CustomMetadata`] entry.
+///
+/// On the wire it is flattened into the entry as `stringValue`, `stringListValue` or
+/// `numericValue`.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(untagged)]
 pub enum CustomMetadataValue {
-    StringValue { string_value: String },
-    StringListValue { string_list_value: StringList },
-    NumericValue { numeric_value: f64 },
+    StringValue {
+        #[serde(rename = "stringValue")]
+        string_value: String,
+    },
+    StringListValue {
+        #[serde(rename = "stringListValue")]
+        string_list_value: StringList,
+    },
+    NumericValue {
+        #[serde(rename = "numericValue")]
+        numeric_value: f64,
+    },
+}
+
+impl CustomMetadata {
+    /// Creates a string-valued entry.
+    pub fn string(key: impl Into<String>, value: impl Into<String>) -> Self {
+        Self {
+            key: key.into(),
+            value: CustomMetadataValue::StringValue {
+                string_value: value.into(),
+            },
+        }
+    }
+}
+
+impl CustomMetadataValue {
+    /// Returns the value if it is a string.
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Self::StringValue { string_value } => Some(string_value),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -184,8 +219,12 @@ pub struct Operation {
     pub name: String,
 
     /// Service-specific metadata
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub metadata: Option<serde_json::Value>,
+    #[serde(
+        default,
+        deserialize_with = "deserialize_lenient_metadata",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub metadata: Option<OperationMetadata>,
 
     /// Whether operation is complete
     #[serde(default, skip_serializing_if = "Option::is_none")]
@@ -200,7 +239,159 @@ pub struct Operation {
 #[serde(untagged)]
 pub enum OperationResult {
     Error { error: Status },
-    Response { response: serde_json::Value },
+    Response { response: OperationResponse },
+}
+
+/// Progress information attached to a running operation.
+///
+/// Every field is optional, as the service only fills in some of them.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OperationMetadata {
+    /// Type URL of the metadata message
+    #[serde(rename = "@type", skip_serializing_if = "Option::is_none")]
+    pub type_url: Option<String>,
+
+    /// When the operation started
+    #[serde(
+        default,
+        with = "time::serde::rfc3339::option",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub create_time: Option<OffsetDateTime>,
+
+    /// When the operation last changed
+    #[serde(
+        default,
+        with = "time::serde::rfc3339::option",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub update_time: Option<OffsetDateTime>,
+
+    /// Estimated completion, from 0 to 100
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub progress_percent: Option<f32>,
+
+    /// Fields not covered above
+    #[serde(flatten)]
+    pub other: serde_json::Map<String, serde_json::Value>,
+}
+
+/// Reads operation metadata, keeping every field in `other` when the known fields do not
+/// have the expected types, so unexpected metadata never fails the whole operation.
+fn deserialize_lenient_metadata<'de, D: serde::Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<OperationMetadata>, D::Error> {
+    let Some(value) = Option::<serde_json::Value>::deserialize(deserializer)? else {
+        return Ok(None);
+    };
+    Ok(Some(serde_json::from_value(value.clone()).unwrap_or_else(
+        |_| {
+            let other = match value {
+                serde_json::Value::Object(map) => map,
+                value => serde_json::Map::from_iter([("value".to_string(), value)]),
+            };
+            OperationMetadata {
+                other,
+                ..Default::default()
+            }
+        },
+    )))
+}
+
+/// The result of a finished upload or import operation.
+///
+/// Which variant applies is decided by the response's `@type`, falling back to its fields.
+/// A response that does not match its typed view is kept as [`Other`](Self::Other).
+#[derive(Debug, Clone, Serialize)]
+#[serde(untagged)]
+pub enum OperationResponse {
+    Upload(UploadToFileSearchStoreResponse),
+    Import(ImportFileResponse),
+    /// A response of an unknown type
+    Other(serde_json::Value),
+}
+
+impl OperationResponse {
+    /// Returns the resource name of the created document.
+    pub fn document_name(&self) -> Option<&str> {
+        match self {
+            Self::Upload(response) => Some(&response.document_name),
+            Self::Import(response) => Some(&response.document_name),
+            Self::Other(value) => value.get("documentName").and_then(|name| name.as_str()),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for OperationResponse {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = serde_json::Value::deserialize(deserializer)?;
+        let type_url = value
+            .get("@type")
+            .and_then(|t| t.as_str())
+            .unwrap_or_default();
+        let is_upload = type_url.ends_with(".UploadToFileSearchStoreResponse")
+            || (type_url.is_empty() && value.get("sizeBytes").is_some());
+        let is_import = type_url.ends_with(".ImportFileResponse")
+            || (type_url.is_empty() && value.get("documentName").is_some());
+
+        let typed = if is_upload {
+            serde_json::from_value(value.clone()).map(Self::Upload).ok()
+        } else if is_import {
+            serde_json::from_value(value.clone()).map(Self::Import).ok()
+        } else {
+            None
+        };
+        Ok(typed.unwrap_or(Self::Other(value)))
+    }
+}
+
+/// Response of a finished upload to a file search store.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UploadToFileSearchStoreResponse {
+    /// Type URL of the response message
+    #[serde(rename = "@type", skip_serializing_if = "Option::is_none")]
+    pub type_url: Option<String>,
+
+    /// Resource name of the store (e.g., "fileSearchStores/my-store-123")
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parent: Option<String>,
+
+    /// Resource name of the created document
+    pub document_name: String,
+
+    /// MIME type of the uploaded file
+    #[serde(
+        default,
+        with = "mime_as_string::optional",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub mime_type: Option<mime::Mime>,
+
+    /// Size of the uploaded file in bytes
+    #[serde(
+        default,
+        deserialize_with = "deserialize_optional_string_to_i64",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub size_bytes: Option<i64>,
+}
+
+/// Response of a finished import of a file into a file search store.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportFileResponse {
+    /// Type URL of the response message
+    #[serde(rename = "@type", skip_serializing_if = "Option::is_none")]
+    pub type_url: Option<String>,
+
+    /// Resource name of the store (e.g., "fileSearchStores/my-store-123")
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parent: Option<String>,
+
+    /// Resource name of the created document
+    pub document_name: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
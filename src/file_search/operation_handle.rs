@@ -1,9 +1,14 @@
 use std::sync::Arc;
-use std::time::{Duration, Instant};
+use std::time::Duration;
 use tracing::instrument;
 
 use crate::client::{Error, GeminiClient};
-use crate::file_search::model::{Operation, OperationResult};
+use crate::common::poll::{PollPolicy, PollStop};
+use crate::file_search::model::{
+    extract_document_id, extract_store_name, Document, Operation, OperationMetadata,
+    OperationResponse, OperationResult,
+};
+use crate::file_search::DocumentHandle;
 
 /// A handle for monitoring long-running file upload/import operations.
 ///
@@ -29,16 +34,76 @@ impl OperationHandle {
         self.operation.done.unwrap_or(false)
     }
 
+    /// Returns the operation as last fetched.
+    pub fn operation(&self) -> &Operation {
+        &self.operation
+    }
+
     pub fn result(&self) -> Option<&OperationResult> {
         self.operation.result.as_ref()
     }
 
+    /// Returns the progress information of the operation, if the service reported any.
+    pub fn metadata(&self) -> Option<&OperationMetadata> {
+        self.operation.metadata.as_ref()
+    }
+
+    /// Returns the response of the operation, once it finished successfully.
+    pub fn response(&self) -> Option<&OperationResponse> {
+        match &self.operation.result {
+            Some(OperationResult::Response { response }) => Some(response),
+            _ => None,
+        }
+    }
+
+    /// Returns the resource name of the created document, once the operation finished
+    /// successfully.
+    pub fn document_name(&self) -> Option<&str> {
+        self.response()?.document_name()
+    }
+
     #[instrument(skip_all, fields(operation.name = %self.operation.name))]
     pub async fn refresh(&mut self) -> Result<(), Error> {
         self.operation = self.client.get_operation(&self.operation.name).await?;
         Ok(())
     }
 
+    /// Polls the operation according to `policy` until it is done.
+    ///
+    /// An operation that is already done returns immediately, without a request.
+    #[instrument(skip_all, fields(operation.name = %self.operation.name))]
+    pub async fn wait(&mut self, policy: &PollPolicy) -> Result<(), Error> {
+        let mut poller = policy.start();
+        let name = self.operation.name.clone();
+        let stopped = |stop| match stop {
+            PollStop::TimedOut => Error::OperationTimeout { name: name.clone() },
+            PollStop::Cancelled => Error::OperationCancelled { name: name.clone() },
+        };
+        poller.check_cancelled().map_err(stopped)?;
+
+        while !self.is_done() {
+            self.refresh().await?;
+            if !self.is_done() {
+                poller.wait().await.map_err(stopped)?;
+            }
+        }
+        self.check_result()
+    }
+
+    fn check_result(&self) -> Result<(), Error> {
+        if let Some(OperationResult::Error { error }) = &self.operation.result {
+            return Err(Error::OperationFailed {
+                name: self.operation.name.clone(),
+                code: error.code,
+                message: error.message.clone(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Polls the operation every `interval` until it is done or `timeout` elapses.
+    ///
+    /// Equivalent to [`wait`](Self::wait) with [`PollPolicy::fixed`].
     #[instrument(skip_all, fields(
         operation.name = %self.operation.name,
         poll.interval.secs = interval.as_secs(),
@@ -49,29 +114,37 @@ impl OperationHandle {
         interval: Duration,
         timeout: Option<Duration>,
     ) -> Result<(), Error> {
-        let start = Instant::now();
-
-        while !self.operation.done.unwrap_or(false) {
-            if let Some(timeout) = timeout {
-                if start.elapsed() >= timeout {
-                    return Err(Error::OperationTimeout {
-                        name: self.operation.name.clone(),
-                    });
-                }
-            }
+        let mut policy = PollPolicy::fixed(interval);
+        policy.timeout = timeout;
+        self.wait(&policy).await
+    }
 
-            tokio::time::sleep(interval).await;
-            self.refresh().await?;
-        }
+    /// Returns the handle of the document created by the finished operation.
+    ///
+    /// Fails if the operation is still running, failed, or did not report a document.
+    #[instrument(skip_all, fields(operation.name = %self.operation.name))]
+    pub async fn document(&self) -> Result<DocumentHandle, Error> {
+        let document = self.fetch_document().await?;
+        Ok(DocumentHandle::new(self.client.clone(), document))
+    }
 
-        if let Some(OperationResult::Error { error }) = &self.operation.result {
-            return Err(Error::OperationFailed {
+    pub(crate) async fn fetch_document(&self) -> Result<Document, Error> {
+        if !self.is_done() {
+            return Err(Error::OperationPending {
                 name: self.operation.name.clone(),
-                code: error.code,
-                message: error.message.clone(),
             });
         }
-
-        Ok(())
+        self.check_result()?;
+        let document_name =
+            self.document_name()
+                .ok_or_else(|| Error::OperationMissingDocument {
+                    name: self.operation.name.clone(),
+                })?;
+        self.client
+            .get_document(
+                &extract_store_name(document_name)?,
+                &extract_document_id(document_name)?,
+            )
+            .await
     }
 }
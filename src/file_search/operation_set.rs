@@ -0,0 +1,251 @@
+use async_stream::stream;
+use futures::{stream, Stream, StreamExt};
+use tracing::instrument;
+
+use crate::client::Error;
+use crate::common::poll::{PollPolicy, PollStop};
+use crate::file_search::model::Document;
+use crate::file_search::OperationHandle;
+
+/// Counts of the operations in an [`OperationSet`] by state.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct OperationCounts {
+    /// Operations that finished and produced a document.
+    pub succeeded: usize,
+    /// Operations that failed, or whose waiting was cut short.
+    pub failed: usize,
+    /// Operations still running.
+    pub pending: usize,
+}
+
+impl OperationCounts {
+    pub fn total(&self) -> usize {
+        self.succeeded + self.failed + self.pending
+    }
+
+    /// Whether every operation has finished.
+    pub fn is_finished(&self) -> bool {
+        self.pending == 0
+    }
+}
+
+/// The result of one operation of an [`OperationSet`].
+#[derive(Debug)]
+pub struct OperationOutcome {
+    /// The position of the operation in the set.
+    pub index: usize,
+    /// The operation, in its last known state.
+    pub operation: OperationHandle,
+    /// The document the operation created, or why there is none.
+    pub result: Result<Document, Error>,
+}
+
+/// An update from [`OperationSet::watch`], emitted after every polling round.
+#[derive(Debug)]
+pub struct OperationProgress {
+    /// The counts after this round.
+    pub counts: OperationCounts,
+    /// The operations that completed during this round.
+    pub completed: Vec<OperationOutcome>,
+}
+
+/// Waits for many upload or import operations at once.
+///
+/// Every polling round refreshes all pending operations concurrently. Rounds follow the
+/// set's [`PollPolicy`], except that the delay falls back to the initial interval whenever
+/// an operation completes, as the others are then likely to finish soon too.
+///
+/// # Example
+///
+/// ```no_run
+/// use futures::StreamExt;
+/// use gemini_rust::prelude::*;
+/// use gemini_rust::OperationSet;
+/// # async fn example(store: FileSearchStoreHandle, files: Vec<Vec<u8>>) -> Result<(), Box<dyn std::error::Error>> {
+/// let mut operations = OperationSet::new();
+/// for data in files {
+///     operations.push(store.upload(data).execute().await?);
+/// }
+///
+/// let mut progress = Box::pin(operations.watch());
+/// while let Some(update) = progress.next().await {
+///     let counts = update.counts;
+///     println!("{}/{} done, {} failed", counts.succeeded, counts.total(), counts.failed);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct OperationSet {
+    operations: Vec<OperationHandle>,
+    policy: PollPolicy,
+    concurrency: usize,
+}
+
+impl Default for OperationSet {
+    fn default() -> Self {
+        Self {
+            operations: Vec::new(),
+            policy: PollPolicy::default(),
+            concurrency: 8,
+        }
+    }
+}
+
+impl OperationSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_poll_policy(mut self, policy: PollPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Sets how many operations are refreshed at once.
+    pub fn with_concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    pub fn push(&mut self, operation: OperationHandle) {
+        self.operations.push(operation);
+    }
+
+    pub fn len(&self) -> usize {
+        self.operations.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.operations.is_empty()
+    }
+
+    /// Polls the operations until all have finished, yielding progress after every round.
+    ///
+    /// The last item has no pending operations. If the policy's timeout elapses or its
+    /// cancellation token fires, the operations still pending complete with
+    /// [`Error::OperationTimeout`] or [`Error::OperationCancelled`]. Errors fetching an
+    /// operation are retried in the next round when they are transient.
+    pub fn watch(self) -> impl Stream<Item = OperationProgress> + Send {
+        stream! {
+            let Self { operations, policy, concurrency } = self;
+            let mut pending: Vec<_> = operations.into_iter().enumerate().collect();
+            let mut counts = OperationCounts {
+                pending: pending.len(),
+                ..Default::default()
+            };
+            let mut poller = policy.start();
+            let mut stopped = poller.check_cancelled().err();
+
+            loop {
+                let completed = match stopped {
+                    Some(stop) => std::mem::take(&mut pending)
+                        .into_iter()
+                        .map(|(index, operation)| stopped_outcome(index, operation, stop))
+                        .collect(),
+                    None => poll_round(&mut pending, concurrency).await,
+                };
+                for outcome in &completed {
+                    counts.pending -= 1;
+                    match outcome.result {
+                        Ok(_) => counts.succeeded += 1,
+                        Err(_) => counts.failed += 1,
+                    }
+                }
+                if !completed.is_empty() {
+                    poller.reset_interval();
+                }
+                yield OperationProgress { counts, completed };
+
+                if pending.is_empty() {
+                    break;
+                }
+                if let Err(stop) = poller.wait().await {
+                    stopped = Some(stop);
+                }
+            }
+        }
+    }
+
+    /// Polls the operations until all have finished and returns their outcomes in the order
+    /// they were added.
+    #[instrument(skip_all, fields(operations = self.operations.len()))]
+    pub async fn wait(self) -> Vec<OperationOutcome> {
+        let mut outcomes: Vec<_> = self
+            .watch()
+            .flat_map(|progress| stream::iter(progress.completed))
+            .collect()
+            .await;
+        outcomes.sort_by_key(|outcome| outcome.index);
+        outcomes
+    }
+}
+
+impl FromIterator<OperationHandle> for OperationSet {
+    fn from_iter<T: IntoIterator<Item = OperationHandle>>(iter: T) -> Self {
+        let mut set = Self::new();
+        set.extend(iter);
+        set
+    }
+}
+
+impl Extend<OperationHandle> for OperationSet {
+    fn extend<T: IntoIterator<Item = OperationHandle>>(&mut self, iter: T) {
+        self.operations.extend(iter);
+    }
+}
+
+/// Refreshes the pending operations, removes the finished ones and returns their outcomes.
+async fn poll_round(
+    pending: &mut Vec<(usize, OperationHandle)>,
+    concurrency: usize,
+) -> Vec<OperationOutcome> {
+    let results: Vec<_> = stream::iter(std::mem::take(pending))
+        .map(|(index, mut operation)| async move {
+            let refreshed = if operation.is_done() {
+                Ok(())
+            } else {
+                operation.refresh().await
+            };
+            let result = match refreshed {
+                Ok(()) if operation.is_done() => Some(operation.fetch_document().await),
+                Ok(()) => None,
+                Err(e) if e.is_transient() => {
+                    tracing::warn!(operation.name = operation.name(), error = %e, "failed to refresh operation");
+                    None
+                }
+                Err(e) => Some(Err(e)),
+            };
+            (index, operation, result)
+        })
+        .buffer_unordered(concurrency)
+        .collect()
+        .await;
+
+    let mut completed = Vec::new();
+    for (index, operation, result) in results {
+        match result {
+            Some(result) => completed.push(OperationOutcome {
+                index,
+                operation,
+                result,
+            }),
+            None => pending.push((index, operation)),
+        }
+    }
+    pending.sort_by_key(|(index, _)| *index);
+    completed.sort_by_key(|outcome| outcome.index);
+    completed
+}
+
+fn stopped_outcome(index: usize, operation: OperationHandle, stop: PollStop) -> OperationOutcome {
+    let name = operation.name().to_string();
+    OperationOutcome {
+        index,
+        operation,
+        result: Err(match stop {
+            PollStop::TimedOut => Error::OperationTimeout { name },
+            PollStop::Cancelled => Error::OperationCancelled { name },
+        }),
+    }
+}
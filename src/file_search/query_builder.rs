@@ -0,0 +1,146 @@
+use snafu::ResultExt;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tracing::instrument;
+
+use crate::client::{Error, GeminiClient, InvalidMetadataFilterSnafu};
+use crate::file_search::model::{extract_document_id, extract_store_name, CustomMetadata};
+use crate::file_search::MetadataFilter;
+use crate::tools::Tool;
+use crate::{Message, RetrievedContext};
+
+/// A chunk of a document retrieved by a [`QueryBuilder`].
+#[derive(Debug, Clone)]
+pub struct RetrievedChunk {
+    /// Position of the chunk in the service's retrieval order, best first.
+    pub rank: usize,
+    /// The retrieved text.
+    pub text: Option<String>,
+    /// The title of the document.
+    pub title: Option<String>,
+    /// The resource name of the document.
+    pub document_name: Option<String>,
+    /// The custom metadata of the document.
+    pub custom_metadata: Vec<CustomMetadata>,
+    /// The number of segments of the answer grounded in this chunk.
+    pub supports: usize,
+}
+
+/// Builder for a retrieval query against a file search store.
+///
+/// The File Search API only retrieves chunks while generating, so the query is sent as a
+/// `generateContent` call with the file search tool as its only tool, and the chunks are
+/// read from the grounding metadata of the response. The answer itself is discarded, so
+/// keep queries short to limit the generated tokens. The metadata of the retrieved
+/// documents is fetched with one request per document.
+///
+/// ```no_run
+/// # use gemini_rust::{prelude::*, MetadataFilter};
+/// # async fn example(store: FileSearchStoreHandle) -> Result<(), Box<dyn std::error::Error>> {
+/// let chunks = store
+///     .query("How are chunks split?")
+///     .with_metadata_filter(&MetadataFilter::eq("lang", "en"))?
+///     .execute()
+///     .await?;
+/// for chunk in chunks {
+///     println!("{} {:?}", chunk.rank, chunk.document_name);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub struct QueryBuilder {
+    pub(crate) client: Arc<GeminiClient>,
+    pub(crate) store_name: String,
+    pub(crate) query: String,
+    pub(crate) metadata_filter: Option<String>,
+}
+
+impl QueryBuilder {
+    /// Only retrieves chunks of documents whose custom metadata matches `filter`.
+    ///
+    /// Fails if the filter has an invalid key or an empty group.
+    pub fn with_metadata_filter(mut self, filter: &MetadataFilter) -> Result<Self, Error> {
+        filter.check().context(InvalidMetadataFilterSnafu)?;
+        self.metadata_filter = Some(filter.to_string());
+        Ok(self)
+    }
+
+    /// Returns the retrieved chunks in the service's order, best first.
+    #[allow(deprecated)]
+    #[instrument(skip_all, fields(
+        store.name = %self.store_name,
+        filter.present = self.metadata_filter.is_some(),
+        chunks.returned,
+    ))]
+    pub async fn execute(self) -> Result<Vec<RetrievedChunk>, Error> {
+        let request = crate::GenerateContentRequest {
+            contents: vec![Message::user(self.query).content],
+            generation_config: None,
+            safety_settings: None,
+            tools: Some(vec![Tool::file_search(
+                vec![self.store_name],
+                self.metadata_filter,
+            )]),
+            tool_config: None,
+            system_instruction: None,
+            cached_content: None,
+        };
+        let response = self.client.generate_content_raw(request).await?;
+        let Some(grounding) = response
+            .candidates
+            .into_iter()
+            .next()
+            .and_then(|candidate| candidate.grounding_metadata)
+        else {
+            return Ok(Vec::new());
+        };
+
+        let mut supports: HashMap<u32, usize> = HashMap::new();
+        for support in grounding.grounding_supports.unwrap_or_default() {
+            for index in support.grounding_chunk_indices {
+                *supports.entry(index).or_default() += 1;
+            }
+        }
+        let contexts: Vec<(usize, RetrievedContext)> = grounding
+            .grounding_chunks
+            .unwrap_or_default()
+            .into_iter()
+            .enumerate()
+            .filter_map(|(index, chunk)| Some((index, chunk.retrieved_context?)))
+            .collect();
+
+        let mut metadata: HashMap<String, Vec<CustomMetadata>> = HashMap::new();
+        for (_, context) in &contexts {
+            let Some(name) = &context.document_name else {
+                continue;
+            };
+            if metadata.contains_key(name) {
+                continue;
+            }
+            let document = self
+                .client
+                .get_document(&extract_store_name(name)?, &extract_document_id(name)?)
+                .await?;
+            metadata.insert(name.clone(), document.custom_metadata.unwrap_or_default());
+        }
+
+        let chunks: Vec<RetrievedChunk> = contexts
+            .into_iter()
+            .enumerate()
+            .map(|(rank, (index, context))| RetrievedChunk {
+                rank,
+                custom_metadata: context
+                    .document_name
+                    .as_ref()
+                    .and_then(|name| metadata.get(name).cloned())
+                    .unwrap_or_default(),
+                supports: supports.get(&(index as u32)).copied().unwrap_or_default(),
+                text: context.text,
+                title: context.title,
+                document_name: context.document_name,
+            })
+            .collect();
+        tracing::Span::current().record("chunks.returned", chunks.len());
+        Ok(chunks)
+    }
+}
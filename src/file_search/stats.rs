@@ -0,0 +1,46 @@
+use std::collections::BTreeMap;
+
+use crate::file_search::model::{Document, DocumentState};
+
+/// A summary of the documents in a file search store, computed by
+/// [`FileSearchStoreHandle::stats`](crate::file_search::FileSearchStoreHandle::stats).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StoreStats {
+    /// Number of documents.
+    pub documents: usize,
+    /// Documents ready for retrieval.
+    pub active: usize,
+    /// Documents still being processed.
+    pub pending: usize,
+    /// Documents whose processing failed.
+    pub failed: usize,
+    /// Total size of all documents in bytes.
+    pub total_bytes: i64,
+    /// Number of documents by MIME type (without parameters such as `charset`).
+    pub by_mime_type: BTreeMap<String, usize>,
+}
+
+impl StoreStats {
+    pub fn from_documents<'a>(documents: impl IntoIterator<Item = &'a Document>) -> Self {
+        let mut stats = Self::default();
+        for document in documents {
+            stats.add(document);
+        }
+        stats
+    }
+
+    pub(crate) fn add(&mut self, document: &Document) {
+        self.documents += 1;
+        match document.state {
+            DocumentState::StateActive => self.active += 1,
+            DocumentState::StatePending => self.pending += 1,
+            DocumentState::StateFailed => self.failed += 1,
+            DocumentState::StateUnspecified => {}
+        }
+        self.total_bytes += document.size_bytes;
+        *self
+            .by_mime_type
+            .entry(document.mime_type.essence_str().to_string())
+            .or_default() += 1;
+    }
+}
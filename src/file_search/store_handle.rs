@@ -1,9 +1,15 @@
+use futures::{future, Stream, TryStreamExt};
+use snafu::ResultExt;
+use std::path::PathBuf;
 use std::sync::Arc;
 use tracing::instrument;
 
-use crate::client::{Error, GeminiClient};
-use crate::file_search::model::FileSearchStore;
-use crate::file_search::{DocumentBuilder, ImportBuilder, UploadBuilder};
+use crate::client::{Error, GeminiClient, InvalidMetadataFilterSnafu};
+use crate::file_search::model::{DocumentState, FileSearchStore};
+use crate::file_search::{
+    DocumentBuilder, DocumentHandle, ImportBuilder, IngestBuilder, MetadataFilter, QueryBuilder,
+    StoreStats, SyncBuilder, UploadBuilder,
+};
 
 /// A handle for managing a file search store.
 ///
@@ -79,6 +85,78 @@ impl FileSearchStoreHandle {
         Ok(())
     }
 
+    #[instrument(skip_all, fields(store.name = %self.store.name))]
+    pub async fn update_display_name(
+        &mut self,
+        display_name: impl Into<String>,
+    ) -> Result<(), Error> {
+        self.store = self
+            .client
+            .update_file_search_store_display_name(&self.store.name, &display_name.into())
+            .await?;
+        Ok(())
+    }
+
+    /// Summarizes the store's documents by state and MIME type.
+    ///
+    /// Lists every document, so this takes one request per 20 documents.
+    #[instrument(skip_all, fields(store.name = %self.store.name))]
+    pub async fn stats(&self) -> Result<StoreStats, Error> {
+        let mut stats = StoreStats::default();
+        let documents = self.documents().list(Some(20));
+        futures::pin_mut!(documents);
+        while let Some(document) = documents.try_next().await? {
+            stats.add(document.document());
+        }
+        Ok(stats)
+    }
+
+    /// Lists the documents whose processing failed. Each can be uploaded again with
+    /// [`DocumentHandle::retry`](crate::file_search::DocumentHandle::retry).
+    pub fn failed_documents(&self) -> impl Stream<Item = Result<DocumentHandle, Error>> {
+        self.documents()
+            .list(Some(20))
+            .try_filter(|document| future::ready(document.state() == DocumentState::StateFailed))
+    }
+
+    /// Deletes every document whose custom metadata matches `filter` and returns their names.
+    ///
+    /// The filter is evaluated locally, as described for [`MetadataFilter::matches`], on
+    /// the listed documents. Stops at the first failed deletion, returning the names of the
+    /// documents deleted so far together with the error.
+    #[instrument(skip_all, fields(store.name = %self.store.name, filter = %filter, deleted))]
+    pub async fn delete_documents_where(
+        &self,
+        filter: &MetadataFilter,
+    ) -> Result<Vec<String>, (Vec<String>, Error)> {
+        filter
+            .check()
+            .context(InvalidMetadataFilterSnafu)
+            .map_err(|e| (Vec::new(), e))?;
+        let matching: Vec<_> = self
+            .documents()
+            .list(Some(20))
+            .try_filter(|document| {
+                let metadata = document.document().custom_metadata.as_deref();
+                future::ready(filter.matches(metadata.unwrap_or_default()))
+            })
+            .try_collect()
+            .await
+            .map_err(|e| (Vec::new(), e))?;
+
+        let mut deleted = Vec::with_capacity(matching.len());
+        for document in matching {
+            let name = document.name().to_string();
+            if let Err(e) = document.delete(true).await {
+                tracing::Span::current().record("deleted", deleted.len());
+                return Err((deleted, e));
+            }
+            deleted.push(name);
+        }
+        tracing::Span::current().record("deleted", deleted.len());
+        Ok(deleted)
+    }
+
     #[instrument(skip_all, fields(store.name = %self.store.name, force))]
     pub async fn delete(self, force: bool) -> Result<(), Error> {
         self.client
@@ -108,6 +186,26 @@ impl FileSearchStoreHandle {
         }
     }
 
+    /// Retrieves the chunks of the store most relevant to `query`; see [`QueryBuilder`].
+    pub fn query(&self, query: impl Into<String>) -> QueryBuilder {
+        QueryBuilder {
+            client: self.client.clone(),
+            store_name: self.store.name.clone(),
+            query: query.into(),
+            metadata_filter: None,
+        }
+    }
+
+    /// Uploads the files of a directory tree; see [`IngestBuilder`].
+    pub fn ingest_directory(&self, path: impl Into<PathBuf>) -> IngestBuilder {
+        IngestBuilder::new(self.client.clone(), self.store.name.clone(), path.into())
+    }
+
+    /// Makes the store mirror a directory tree; see [`SyncBuilder`].
+    pub fn sync_directory(&self, path: impl Into<PathBuf>) -> SyncBuilder {
+        SyncBuilder::new(self.client.clone(), self.store.name.clone(), path.into())
+    }
+
     pub fn documents(&self) -> DocumentBuilder {
         DocumentBuilder {
             client: self.client.clone(),
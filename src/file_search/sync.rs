@@ -0,0 +1,500 @@
+use futures::{stream, StreamExt, TryStreamExt};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tracing::instrument;
+
+use crate::client::{Error, GeminiClient};
+use crate::common::poll::PollPolicy;
+use crate::file_search::ingest::{display_path, IngestReport};
+use crate::file_search::model::{
+    extract_document_id, ChunkingConfig, CustomMetadata, Document, DocumentState,
+};
+use crate::file_search::{
+    DocumentBuilder, FailedIngest, IngestBuilder, IngestFile, IngestedDocument, SkippedFile,
+};
+
+/// The metadata key under which a synced document's path, relative to the synced
+/// directory, is stored.
+pub const SOURCE_PATH_KEY: &str = "source_path";
+
+/// The metadata key under which the hex-encoded SHA-256 hash of a synced document's
+/// contents is stored.
+pub const CONTENT_HASH_KEY: &str = "content_sha256";
+
+/// The metadata key under which the namespace of the sync that uploaded a document is
+/// stored; see [`SyncBuilder::with_namespace`].
+pub const SYNC_ROOT_KEY: &str = "sync_root";
+
+/// What a sync does with one path.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SyncAction {
+    /// The file has no document yet and is uploaded.
+    Create,
+    /// The file changed; it is uploaded again and `document` is then deleted.
+    Update { document: String },
+    /// The file was removed, or the document is a duplicate; `document` is deleted.
+    Delete { document: String },
+    /// `document` is up to date.
+    Unchanged { document: String },
+}
+
+/// One entry of a [`SyncPlan`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SyncChange {
+    /// The path relative to the synced directory.
+    pub path: PathBuf,
+    pub action: SyncAction,
+}
+
+/// The changes a sync would make, as computed by [`SyncBuilder::plan`].
+///
+/// Its [`Display`](fmt::Display) output lists uploads with `+`, replacements with `~` and
+/// deletions with `-`, followed by a summary line.
+#[derive(Debug, Default)]
+pub struct SyncPlan {
+    /// The changes, sorted by path.
+    pub changes: Vec<SyncChange>,
+    /// Files excluded by the builder's rules.
+    pub skipped: Vec<SkippedFile>,
+    /// Files or directories that could not be read.
+    pub failed: Vec<FailedIngest>,
+}
+
+impl SyncPlan {
+    /// Whether applying the plan would modify the store.
+    pub fn has_changes(&self) -> bool {
+        self.changes
+            .iter()
+            .any(|change| !matches!(change.action, SyncAction::Unchanged { .. }))
+    }
+
+    /// Returns the paths that would be uploaded, new or changed.
+    pub fn uploads(&self) -> impl Iterator<Item = &Path> {
+        self.changes
+            .iter()
+            .filter(|c| matches!(c.action, SyncAction::Create | SyncAction::Update { .. }))
+            .map(|c| c.path.as_path())
+    }
+
+    /// Returns the names of the documents that would be deleted, including those replaced by
+    /// updates.
+    pub fn deletions(&self) -> impl Iterator<Item = &str> {
+        self.changes.iter().filter_map(|c| match &c.action {
+            SyncAction::Update { document } | SyncAction::Delete { document } => {
+                Some(document.as_str())
+            }
+            _ => None,
+        })
+    }
+}
+
+impl fmt::Display for SyncPlan {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (mut created, mut updated, mut deleted, mut unchanged) = (0, 0, 0, 0);
+        for change in &self.changes {
+            let marker = match change.action {
+                SyncAction::Create => {
+                    created += 1;
+                    '+'
+                }
+                SyncAction::Update { .. } => {
+                    updated += 1;
+                    '~'
+                }
+                SyncAction::Delete { .. } => {
+                    deleted += 1;
+                    '-'
+                }
+                SyncAction::Unchanged { .. } => {
+                    unchanged += 1;
+                    continue;
+                }
+            };
+            writeln!(f, "{marker} {}", display_path(&change.path))?;
+        }
+        write!(
+            f,
+            "{created} to create, {updated} to update, {deleted} to delete, {unchanged} unchanged"
+        )?;
+        if !self.failed.is_empty() {
+            write!(f, ", {} unreadable", self.failed.len())?;
+        }
+        Ok(())
+    }
+}
+
+/// The outcome of applying a [`SyncPlan`].
+#[derive(Debug, Default)]
+pub struct SyncReport {
+    /// Files that were uploaded, new or changed.
+    pub uploaded: Vec<IngestedDocument>,
+    /// Names of the documents that were deleted.
+    pub deleted: Vec<String>,
+    /// Paths whose documents were already up to date.
+    pub unchanged: Vec<PathBuf>,
+    /// Files excluded by the builder's rules.
+    pub skipped: Vec<SkippedFile>,
+    /// Paths whose upload or deletion failed. A changed file whose upload failed keeps its
+    /// previous document.
+    pub failed: Vec<FailedIngest>,
+}
+
+/// Builder for synchronizing a file search store with a local directory.
+///
+/// Every uploaded document records the sync's namespace, its path and a hash of its contents
+/// in its custom metadata (see [`SYNC_ROOT_KEY`], [`SOURCE_PATH_KEY`] and
+/// [`CONTENT_HASH_KEY`]). A sync uploads files that have no document, whose contents changed
+/// or whose document failed processing, deletes documents whose file is gone or no longer
+/// selected, and leaves the rest alone. Only documents of the same namespace are considered,
+/// so several directories can be synced into one store; documents of other namespaces, or
+/// without one, such as those uploaded by other means, are never touched.
+///
+/// File selection and upload options work as on [`IngestBuilder`].
+///
+/// # Example
+///
+/// ```no_run
+/// use gemini_rust::prelude::*;
+/// # async fn example(store: FileSearchStoreHandle) -> Result<(), Box<dyn std::error::Error>> {
+/// let sync = store.sync_directory("./site/docs").with_include("*.md")?;
+///
+/// let plan = sync.plan().await?;
+/// println!("{plan}");
+///
+/// if plan.has_changes() {
+///     let report = sync.apply(plan).await;
+///     println!("{} failures", report.failed.len());
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub struct SyncBuilder {
+    ingest: IngestBuilder,
+    namespace: String,
+}
+
+impl SyncBuilder {
+    pub(crate) fn new(client: Arc<GeminiClient>, store_name: String, root: PathBuf) -> Self {
+        Self {
+            namespace: default_namespace(&root),
+            ingest: IngestBuilder::new(client, store_name, root),
+        }
+    }
+
+    /// Sets the namespace recorded on uploaded documents. Defaults to the canonical path of
+    /// the directory, so syncs of `./docs` and of its absolute path share documents.
+    ///
+    /// Set a fixed namespace when the same directory is synced from different locations,
+    /// such as several checkouts of a repository.
+    pub fn with_namespace(mut self, namespace: impl Into<String>) -> Self {
+        self.namespace = namespace.into();
+        self
+    }
+
+    /// Returns the namespace recorded on uploaded documents.
+    pub fn namespace(&self) -> &str {
+        &self.namespace
+    }
+
+    /// Only syncs files matching `pattern`; see [`IngestBuilder::with_include`].
+    pub fn with_include(mut self, pattern: &str) -> Result<Self, Error> {
+        self.ingest = self.ingest.with_include(pattern)?;
+        Ok(self)
+    }
+
+    /// Leaves out files matching `pattern`, deleting their documents; see
+    /// [`IngestBuilder::with_exclude`].
+    pub fn with_exclude(mut self, pattern: &str) -> Result<Self, Error> {
+        self.ingest = self.ingest.with_exclude(pattern)?;
+        Ok(self)
+    }
+
+    /// Derives each uploaded document's custom metadata from the file. The sync keys are
+    /// added to the returned entries.
+    pub fn with_metadata(
+        mut self,
+        metadata: impl Fn(&IngestFile) -> Vec<CustomMetadata> + Send + Sync + 'static,
+    ) -> Self {
+        self.ingest = self.ingest.with_metadata(metadata);
+        self
+    }
+
+    /// Sets how uploaded files are split into chunks; documents left unchanged keep theirs.
+    pub fn with_chunking_config(mut self, config: ChunkingConfig) -> Self {
+        self.ingest = self.ingest.with_chunking_config(config);
+        self
+    }
+
+    /// Sets how many files are hashed, uploaded or deleted at once.
+    pub fn with_concurrency(mut self, concurrency: usize) -> Self {
+        self.ingest = self.ingest.with_concurrency(concurrency);
+        self
+    }
+
+    /// Skips files larger than `bytes`. Documents of files that grow past the limit are
+    /// deleted.
+    pub fn with_max_file_size(mut self, bytes: u64) -> Self {
+        self.ingest = self.ingest.with_max_file_size(bytes);
+        self
+    }
+
+    /// Sets how the upload operations are polled until the documents are indexed.
+    pub fn with_poll_policy(mut self, policy: PollPolicy) -> Self {
+        self.ingest = self.ingest.with_poll_policy(policy);
+        self
+    }
+
+    /// Compares the directory with the store without modifying anything.
+    #[instrument(skip_all, fields(
+        store.name = %self.ingest.store_name,
+        root = %self.ingest.root.display(),
+        files.total,
+    ))]
+    pub async fn plan(&self) -> Result<SyncPlan, Error> {
+        let mut selection = IngestReport::default();
+        let selected = self.ingest.select(&mut selection).await?;
+
+        let root = &self.ingest.root;
+        let hashes: Vec<_> = stream::iter(selected)
+            .map(|relative_path| async move {
+                let data = tokio::fs::read(root.join(&relative_path)).await;
+                (relative_path, data.map(|data| content_hash(&data)))
+            })
+            .buffered(self.ingest.concurrency)
+            .collect()
+            .await;
+        let mut local = Vec::new();
+        for (path, hash) in hashes {
+            match hash {
+                Ok(hash) => local.push((path, hash)),
+                Err(source) => selection.failed.push(FailedIngest {
+                    path,
+                    error: Error::Io { source },
+                }),
+            }
+        }
+
+        let documents = DocumentBuilder {
+            client: self.ingest.client.clone(),
+            store_name: self.ingest.store_name.clone(),
+        }
+        .list(Some(20))
+        .map_ok(|handle| handle.document().clone())
+        .try_collect::<Vec<_>>()
+        .await?;
+
+        // Documents of files or directories that could not be read are kept.
+        let mut changes = plan_changes(&self.namespace, &local, &documents);
+        changes.retain(|change| {
+            !matches!(change.action, SyncAction::Delete { .. })
+                || !selection
+                    .failed
+                    .iter()
+                    .any(|failed| change.path.starts_with(&failed.path))
+        });
+
+        Ok(SyncPlan {
+            changes,
+            skipped: selection.skipped,
+            failed: selection.failed,
+        })
+    }
+
+    /// Carries out a plan computed by [`plan`](Self::plan).
+    ///
+    /// Changed files are uploaded before their previous document is deleted, so the store
+    /// never lacks a version of them.
+    #[instrument(skip_all, fields(
+        store.name = %self.ingest.store_name,
+        changes = plan.changes.len(),
+    ))]
+    pub async fn apply(&self, plan: SyncPlan) -> SyncReport {
+        let mut report = SyncReport {
+            skipped: plan.skipped,
+            failed: plan.failed,
+            ..Default::default()
+        };
+
+        let results = stream::iter(plan.changes)
+            .map(|change| self.apply_change(change))
+            .buffer_unordered(self.ingest.concurrency)
+            .collect::<Vec<_>>()
+            .await;
+        for result in results {
+            match result {
+                ChangeOutcome::Uploaded { document, replaced } => {
+                    report.uploaded.push(*document);
+                    report.deleted.extend(replaced);
+                }
+                ChangeOutcome::Deleted(name) => report.deleted.push(name),
+                ChangeOutcome::Unchanged(path) => report.unchanged.push(path),
+                ChangeOutcome::Failed(failure) => report.failed.push(failure),
+            }
+        }
+
+        report.uploaded.sort_by(|a, b| a.path.cmp(&b.path));
+        report.deleted.sort();
+        report.unchanged.sort();
+        report.failed.sort_by(|a, b| a.path.cmp(&b.path));
+        report
+    }
+
+    /// Plans and applies a sync.
+    pub async fn execute(self) -> Result<SyncReport, Error> {
+        let plan = self.plan().await?;
+        Ok(self.apply(plan).await)
+    }
+
+    async fn apply_change(&self, change: SyncChange) -> ChangeOutcome {
+        let SyncChange { path, action } = change;
+        let replaced = match action {
+            SyncAction::Unchanged { .. } => return ChangeOutcome::Unchanged(path),
+            SyncAction::Delete { document } => {
+                return match self.delete_document(&document).await {
+                    Ok(()) => ChangeOutcome::Deleted(document),
+                    Err(error) => ChangeOutcome::Failed(FailedIngest { path, error }),
+                };
+            }
+            SyncAction::Create => None,
+            SyncAction::Update { document } => Some(document),
+        };
+
+        let source_path = display_path(&path);
+        let operation = self
+            .ingest
+            .ingest_file(&path, |file| {
+                vec![
+                    CustomMetadata::string(SYNC_ROOT_KEY, self.namespace.clone()),
+                    CustomMetadata::string(SOURCE_PATH_KEY, source_path),
+                    CustomMetadata::string(CONTENT_HASH_KEY, content_hash(&file.data)),
+                ]
+            })
+            .await;
+        let operation = match operation {
+            Ok(operation) => operation,
+            Err(error) => return ChangeOutcome::Failed(FailedIngest { path, error }),
+        };
+        if let Some(document) = &replaced {
+            if let Err(error) = self.delete_document(document).await {
+                return ChangeOutcome::Failed(FailedIngest { path, error });
+            }
+        }
+        ChangeOutcome::Uploaded {
+            document: Box::new(IngestedDocument {
+                document_name: operation.document_name().map(str::to_string),
+                path,
+                operation,
+            }),
+            replaced,
+        }
+    }
+
+    async fn delete_document(&self, name: &str) -> Result<(), Error> {
+        let document_id = extract_document_id(name)?;
+        self.ingest
+            .client
+            .delete_document(&self.ingest.store_name, &document_id, true)
+            .await
+    }
+}
+
+enum ChangeOutcome {
+    Uploaded {
+        document: Box<IngestedDocument>,
+        replaced: Option<String>,
+    },
+    Deleted(String),
+    Unchanged(PathBuf),
+    Failed(FailedIngest),
+}
+
+pub(crate) fn content_hash(data: &[u8]) -> String {
+    Sha256::digest(data)
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
+/// Matches local files, given as relative paths with content hashes, against the store's
+/// documents synced under `namespace`.
+///
+/// Of several documents with the same source path, one with a matching hash is kept and
+/// the others are deleted.
+pub(crate) fn plan_changes(
+    namespace: &str,
+    local: &[(PathBuf, String)],
+    documents: &[Document],
+) -> Vec<SyncChange> {
+    let mut remote: BTreeMap<String, Vec<(&str, Option<&str>)>> = BTreeMap::new();
+    for document in documents {
+        let metadata = document.custom_metadata.as_deref().unwrap_or_default();
+        let value = |key: &str| {
+            metadata
+                .iter()
+                .find(|entry| entry.key == key)
+                .and_then(|entry| entry.value.as_str())
+        };
+        if value(SYNC_ROOT_KEY) != Some(namespace) {
+            continue;
+        }
+        if let Some(source_path) = value(SOURCE_PATH_KEY) {
+            // A document whose processing failed counts as outdated, so it is uploaded again.
+            let hash =
+                value(CONTENT_HASH_KEY).filter(|_| document.state != DocumentState::StateFailed);
+            remote
+                .entry(source_path.to_string())
+                .or_default()
+                .push((&document.name, hash));
+        }
+    }
+
+    let mut changes = Vec::new();
+    for (path, hash) in local {
+        let mut existing = remote.remove(&display_path(path)).unwrap_or_default();
+        let current = existing
+            .iter()
+            .position(|(_, remote_hash)| *remote_hash == Some(hash.as_str()));
+        let action = match current {
+            Some(index) => SyncAction::Unchanged {
+                document: existing.remove(index).0.to_string(),
+            },
+            None if existing.is_empty() => SyncAction::Create,
+            None => SyncAction::Update {
+                document: existing.remove(0).0.to_string(),
+            },
+        };
+        changes.push(SyncChange {
+            path: path.clone(),
+            action,
+        });
+        changes.extend(existing.into_iter().map(|(document, _)| SyncChange {
+            path: path.clone(),
+            action: SyncAction::Delete {
+                document: document.to_string(),
+            },
+        }));
+    }
+    for (source_path, existing) in remote {
+        changes.extend(existing.into_iter().map(|(document, _)| SyncChange {
+            path: PathBuf::from(&source_path),
+            action: SyncAction::Delete {
+                document: document.to_string(),
+            },
+        }));
+    }
+
+    changes.sort_by(|a, b| a.path.cmp(&b.path));
+    changes
+}
+
+/// The namespace of a sync of `root`: its canonical path, or the path as given if it cannot be
+/// resolved, in which case the sync fails when reading the directory.
+pub(crate) fn default_namespace(root: &Path) -> String {
+    match std::fs::canonicalize(root) {
+        Ok(path) => path.to_string_lossy().into_owned(),
+        Err(_) => display_path(root),
+    }
+}
@@ -1,17 +1,22 @@
+use snafu::ResultExt;
 use std::sync::Arc;
 use tracing::instrument;
 
 use crate::{
     cache::CachedContentHandle,
-    client::{Error as ClientError, GeminiClient, GenerationStream},
+    client::{Error as ClientError, GeminiClient, GenerationStream, InvalidSafetySettingsSnafu},
     files::Error as FilesError,
     generation::{
         GenerateContentRequest, ImageConfig, MediaResolutionLevel, SpeakerVoiceConfig,
         SpeechConfig, ThinkingConfig, ThinkingLevel,
     },
+    safety::{
+        filter::{FilterChain, FilterTag},
+        validate_settings, ContentFilter,
+    },
     tools::{FunctionCallingConfig, ToolConfig},
     Content, FileHandle, FunctionCallingMode, FunctionDeclaration, GenerationConfig,
-    GenerationResponse, Message, Role, SafetySetting, Tool,
+    GenerationResponse, Message, Role, SafetyPolicy, SafetySetting, Tool,
 };
 
 /// Builder for content generation requests
@@ -29,6 +34,9 @@ pub struct ContentBuilder {
     tool_config: Option<ToolConfig>,
     system_instruction: Option<Content>,
     cached_content: Option<String>,
+    error_on_block: bool,
+    content_filters: FilterChain,
+    output_filters: FilterChain,
 }
 
 impl ContentBuilder {
@@ -43,15 +51,53 @@ impl ContentBuilder {
             tool_config: None,
             system_instruction: None,
             cached_content: None,
+            error_on_block: false,
+            content_filters: FilterChain::default(),
+            output_filters: FilterChain::default(),
         }
     }
 
     /// Sets the safety settings for the request.
+    ///
+    /// Settings using categories Gemini models do not accept make [`execute`](Self::execute)
+    /// fail with [`ClientError::InvalidSafetySettings`] before the request is sent.
     pub fn with_safety_settings(mut self, safety_settings: Vec<SafetySetting>) -> Self {
         self.safety_settings = Some(safety_settings);
         self
     }
 
+    /// Sets the safety settings for the request from a [`SafetyPolicy`].
+    pub fn with_safety_policy(mut self, policy: SafetyPolicy) -> Self {
+        self.safety_settings = Some(policy.into_settings());
+        self
+    }
+
+    /// Makes [`execute`](Self::execute) fail with [`ClientError::Blocked`] when the prompt
+    /// or the output is blocked, instead of returning the response.
+    ///
+    /// Partially returned output is not treated as blocked. See
+    /// [`GenerationResponse::safety_outcome`] to inspect blocks yourself.
+    pub fn with_error_on_block(mut self, error_on_block: bool) -> Self {
+        self.error_on_block = error_on_block;
+        self
+    }
+
+    /// Adds a filter run over every text and inline data part before the request is sent.
+    ///
+    /// See [`safety::filter`](crate::safety::filter) for what filters can do.
+    pub fn with_content_filter(mut self, filter: impl ContentFilter + 'static) -> Self {
+        self.content_filters.push(Arc::new(filter));
+        self
+    }
+
+    /// Adds a filter run over the text parts of the response.
+    ///
+    /// Output filters are not applied by [`execute_stream`](Self::execute_stream).
+    pub fn with_output_filter(mut self, filter: impl ContentFilter + 'static) -> Self {
+        self.output_filters.push(Arc::new(filter));
+        self
+    }
+
     /// Sets the system prompt for the request.
     ///
     /// This is an alias for [`with_system_instruction()`](Self::with_system_instruction).
@@ -176,8 +222,16 @@ impl ContentBuilder {
     ///
     /// This allows reusing previously cached system instructions and conversation history,
     /// which can reduce latency and cost.
+    ///
+    /// If the cache was created with a safety policy and no safety settings have been set
+    /// on this builder, the cache's policy is used.
     pub fn with_cached_content(mut self, cached_content: &CachedContentHandle) -> Self {
         self.cached_content = Some(cached_content.name().to_string());
+        if self.safety_settings.is_none() {
+            self.safety_settings = cached_content
+                .safety_policy()
+                .map(|policy| policy.settings().to_vec());
+        }
         self
     }
 
@@ -444,6 +498,10 @@ impl ContentBuilder {
     }
 
     /// Builds the `GenerateContentRequest`.
+    ///
+    /// The safety settings are not validated and the content filters are not run; use
+    /// [`build_checked`](Self::build_checked) for a request that is sent some other way, such
+    /// as in a batch.
     pub fn build(self) -> GenerateContentRequest {
         GenerateContentRequest {
             contents: self.contents,
@@ -465,8 +523,21 @@ impl ContentBuilder {
     ))]
     pub async fn execute(self) -> Result<GenerationResponse, ClientError> {
         let client = self.client.clone();
-        let request = self.build();
-        client.generate_content_raw(request).await
+        let error_on_block = self.error_on_block;
+        let output_filters = self.output_filters.clone();
+        let (request, tags) = self.filtered_request()?;
+        let mut response = client.generate_content_raw(request).await?;
+        if error_on_block {
+            let outcome = response.safety_outcome();
+            if outcome.is_blocked() {
+                return Err(ClientError::Blocked { outcome });
+            }
+        }
+        response.filter_tags = tags;
+        if !output_filters.is_empty() {
+            output_filters.filter_response(&mut response)?;
+        }
+        Ok(response)
     }
 
     /// Executes the content generation request as a stream.
@@ -478,7 +549,7 @@ impl ContentBuilder {
     ))]
     pub async fn execute_stream(self) -> Result<GenerationStream, ClientError> {
         let client = self.client.clone();
-        let request = self.build();
+        let request = self.build_checked()?;
         client.generate_content_stream(request).await
     }
 
@@ -488,7 +559,29 @@ impl ContentBuilder {
     ))]
     pub async fn count_tokens(self) -> Result<super::model::CountTokensResponse, ClientError> {
         let client = self.client.clone();
-        let request = self.build();
+        let request = self.build_checked()?;
         client.count_tokens(request).await
     }
+
+    /// Builds the request, validating its safety settings and running the content filters.
+    ///
+    /// Fails with [`ClientError::InvalidSafetySettings`] or
+    /// [`ClientError::ContentRejected`]. Tags raised by the filters are only logged.
+    pub fn build_checked(self) -> Result<GenerateContentRequest, ClientError> {
+        self.filtered_request().map(|(request, _)| request)
+    }
+
+    /// Like [`build_checked`](Self::build_checked), also returning the tags raised.
+    fn filtered_request(self) -> Result<(GenerateContentRequest, Vec<FilterTag>), ClientError> {
+        let content_filters = self.content_filters.clone();
+        let mut request = self.build();
+        let settings = request.safety_settings.as_deref().unwrap_or_default();
+        validate_settings(settings).context(InvalidSafetySettingsSnafu)?;
+        let tags = if content_filters.is_empty() {
+            Vec::new()
+        } else {
+            content_filters.filter_request(&mut request)?
+        };
+        Ok((request, tags))
+    }
 }
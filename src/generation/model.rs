@@ -3,7 +3,7 @@ use serde::{Deserialize, Serialize};
 use time::OffsetDateTime;
 
 use crate::{
-    safety::{SafetyRating, SafetySetting},
+    safety::{filter::FilterTag, SafetyRating, SafetySetting},
     Content, Modality, Part,
 };
 
@@ -157,6 +157,30 @@ pub struct GroundingChunk {
     /// Web-specific grounding information
     #[serde(skip_serializing_if = "Option::is_none")]
     pub web: Option<WebGroundingChunk>,
+    /// Retrieved document information, e.g. from file search
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub retrieved_context: Option<RetrievedContext>,
+}
+
+/// Grounding chunk information from retrieved documents
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct RetrievedContext {
+    /// The URI of the document
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub uri: Option<String>,
+    /// The title of the document
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    /// The retrieved text
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+    /// The file search store the document belongs to
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub file_search_store: Option<String>,
+    /// The resource name of the document
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub document_name: Option<String>,
 }
 
 /// Maps-specific grounding chunk information
@@ -196,6 +220,9 @@ pub struct GroundingSupport {
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct GroundingSegment {
+    /// Index of the part the segment belongs to
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub part_index: Option<u32>,
     /// Start index of the segment in the response text
     #[serde(skip_serializing_if = "Option::is_none")]
     pub start_index: Option<u32>,
@@ -230,6 +257,9 @@ pub struct GenerationResponse {
     /// Response ID
     #[serde(skip_serializing_if = "Option::is_none")]
     pub response_id: Option<String>,
+    /// Tags raised by the builder's content and output filters; not part of the API response
+    #[serde(skip)]
+    pub filter_tags: Vec<FilterTag>,
 }
 
 /// Reason why content was blocked
@@ -263,6 +293,16 @@ pub struct PromptFeedback {
 }
 
 impl GenerationResponse {
+    /// Classify the safety feedback of the prompt and the first candidate
+    pub fn safety_outcome(&self) -> crate::safety::SafetyOutcome {
+        crate::safety::SafetyOutcome::of_response(self)
+    }
+
+    /// Whether the prompt or the first candidate was blocked without producing output
+    pub fn is_blocked(&self) -> bool {
+        self.safety_outcome().is_blocked()
+    }
+
     /// Get the text of the first candidate
     pub fn text(&self) -> String {
         self.candidates
@@ -282,6 +322,36 @@ impl GenerationResponse {
             .unwrap_or_default()
     }
 
+    /// Get the text of the first candidate with its grounding citations resolved
+    pub fn annotated_text(&self) -> crate::citation::AnnotatedText {
+        let Some(candidate) = self.candidates.first() else {
+            return Default::default();
+        };
+        let parts: Vec<&str> = candidate
+            .content
+            .parts
+            .iter()
+            .flatten()
+            .map(|p| match p {
+                Part::Text {
+                    text,
+                    thought: None | Some(false),
+                    ..
+                } => text.as_str(),
+                _ => "",
+            })
+            .collect();
+        match &candidate.grounding_metadata {
+            Some(metadata) => {
+                crate::citation::CitationResolver::new().resolve_grounding(&parts, metadata)
+            }
+            None => crate::citation::AnnotatedText {
+                text: parts.concat(),
+                ..Default::default()
+            },
+        }
+    }
+
     /// Get function calls from the response
     pub fn function_calls(&self) -> Vec<&crate::tools::FunctionCall> {
         self.candidates
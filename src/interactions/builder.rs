@@ -1,9 +1,15 @@
 use std::sync::Arc;
 use tracing::{instrument, Span};
 
+use crate::cache::CachedContentHandle;
 use crate::client::{Error as ClientError, GeminiClient};
+use crate::interactions::functions::{self, FunctionHandler};
 use crate::interactions::model::*;
 use crate::interactions::stream::InteractionStream;
+use crate::safety::{
+    filter::{FilterChain, FilterTag},
+    ContentFilter, SafetyPolicy, SafetySetting,
+};
 
 /// Fluent builder for constructing and executing interaction requests.
 #[derive(Clone)]
@@ -22,9 +28,17 @@ pub struct InteractionBuilder {
     previous_interaction_id: Option<String>,
     environment: Option<EnvironmentConfigOrString>,
     cached_content: Option<String>,
+    /// Model of the cache set with `with_cache`, checked against the request model.
+    cache_model: Option<String>,
     response_modalities: Vec<ResponseModality>,
     service_tier: Option<ServiceTier>,
     webhook_config: Option<WebhookConfig>,
+    error_on_block: bool,
+    safety_settings: Vec<SafetySetting>,
+    content_filters: FilterChain,
+    output_filters: FilterChain,
+    function_handlers: Vec<Arc<dyn FunctionHandler>>,
+    max_function_rounds: usize,
 }
 
 impl InteractionBuilder {
@@ -44,9 +58,16 @@ impl InteractionBuilder {
             previous_interaction_id: None,
             environment: None,
             cached_content: None,
+            cache_model: None,
             response_modalities: Vec::new(),
             service_tier: None,
             webhook_config: None,
+            error_on_block: false,
+            safety_settings: Vec::new(),
+            content_filters: FilterChain::default(),
+            output_filters: FilterChain::default(),
+            function_handlers: Vec::new(),
+            max_function_rounds: 10,
         }
     }
 
@@ -191,6 +212,23 @@ impl InteractionBuilder {
         self
     }
 
+    /// Add the tools of a [`FunctionHandler`] and route calls to them to it.
+    ///
+    /// Calls are only run by [`execute_with_functions`](Self::execute_with_functions).
+    pub fn with_function_handler(mut self, handler: impl FunctionHandler + 'static) -> Self {
+        self.tools.extend(handler.tools());
+        self.function_handlers.push(Arc::new(handler));
+        self
+    }
+
+    /// Set how many rounds of function results
+    /// [`execute_with_functions`](Self::execute_with_functions) sends before giving up.
+    /// Defaults to 10.
+    pub fn with_max_function_rounds(mut self, rounds: usize) -> Self {
+        self.max_function_rounds = rounds;
+        self
+    }
+
     /// Enable Google Search.
     pub fn with_google_search(mut self) -> Self {
         self.tools.push(InteractionTool::google_search());
@@ -389,6 +427,26 @@ impl InteractionBuilder {
     /// Set cached content.
     pub fn with_cached_content(mut self, cached_content: impl Into<String>) -> Self {
         self.cached_content = Some(cached_content.into());
+        self.cache_model = None;
+        self
+    }
+
+    /// Use a cache created with [`CacheBuilder`](crate::cache::CacheBuilder).
+    ///
+    /// When the handle knows its model (see [`CachedContentHandle::model`]), [`build`](Self::build)
+    /// fails with [`ClientError::CacheModelMismatch`] if the interaction targets a different
+    /// model, instead of the request being rejected by the API. A safety policy attached to
+    /// the cache is carried over unless one was already set with
+    /// [`with_safety_policy`](Self::with_safety_policy), and is subject to the same
+    /// restriction.
+    pub fn with_cache(mut self, cache: &CachedContentHandle) -> Self {
+        self.cached_content = Some(cache.name().to_string());
+        self.cache_model = cache.model().map(str::to_string);
+        if self.safety_settings.is_empty() {
+            if let Some(policy) = cache.safety_policy() {
+                self.safety_settings = policy.settings().to_vec();
+            }
+        }
         self
     }
 
@@ -410,9 +468,48 @@ impl InteractionBuilder {
         self
     }
 
+    /// Set the safety settings from a [`SafetyPolicy`].
+    ///
+    /// The Interactions API does not accept safety settings, so [`execute`](Self::execute)
+    /// and [`execute_stream`](Self::execute_stream) fail with
+    /// [`ClientError::SafetySettingsUnsupported`] when a policy is set. It applies when the
+    /// request runs through `generateContent`, as in
+    /// [`InteractionBatchBuilder`](crate::batch::InteractionBatchBuilder).
+    pub fn with_safety_policy(mut self, policy: SafetyPolicy) -> Self {
+        self.safety_settings = policy.into_settings();
+        self
+    }
+
+    /// Add a filter run over every text and inline data input before the request is sent.
+    ///
+    /// See [`safety::filter`](crate::safety::filter) for what filters can do. Filters are
+    /// applied by `execute()` and `execute_stream()`, not by [`build`](Self::build).
+    pub fn with_content_filter(mut self, filter: impl ContentFilter + 'static) -> Self {
+        self.content_filters.push(Arc::new(filter));
+        self
+    }
+
+    /// Add a filter run over the model output returned by `execute()`.
+    pub fn with_output_filter(mut self, filter: impl ContentFilter + 'static) -> Self {
+        self.output_filters.push(Arc::new(filter));
+        self
+    }
+
+    /// Make `execute()` fail with [`ClientError::Blocked`] when the interaction is blocked.
+    ///
+    /// See [`Interaction::safety_outcome`] for how blocks are detected.
+    pub fn with_error_on_block(mut self, error_on_block: bool) -> Self {
+        self.error_on_block = error_on_block;
+        self
+    }
+
     // ===== Build & Execute =====
 
     /// Build the request.
+    ///
+    /// The content filters are not run; they apply when the builder is executed or added to
+    /// a batch with
+    /// [`InteractionBatchBuilder::with_interaction`](crate::batch::InteractionBatchBuilder::with_interaction).
     pub fn build(self) -> Result<CreateInteractionRequest, ClientError> {
         let input = self.input.ok_or_else(|| ClientError::InvalidResourceName {
             name: "input is required for interaction".to_string(),
@@ -430,6 +527,18 @@ impl InteractionBuilder {
             self.model
         };
 
+        if let (Some(cache), Some(cache_model), Some(model)) =
+            (&self.cached_content, &self.cache_model, &model)
+        {
+            if cache_model.trim_start_matches("models/") != model.trim_start_matches("models/") {
+                return Err(ClientError::CacheModelMismatch {
+                    cache: cache.clone(),
+                    cache_model: cache_model.clone(),
+                    model: model.clone(),
+                });
+            }
+        }
+
         Ok(CreateInteractionRequest {
             model,
             agent: self.agent,
@@ -448,6 +557,7 @@ impl InteractionBuilder {
             response_modalities: self.response_modalities,
             service_tier: self.service_tier,
             webhook_config: self.webhook_config,
+            safety_settings: self.safety_settings,
         })
     }
 
@@ -464,8 +574,12 @@ impl InteractionBuilder {
     ))]
     pub async fn execute(self) -> Result<Interaction, ClientError> {
         let client = self.client.clone();
-        let request = self.build()?;
-        let response = client.create_interaction(request).await?;
+        let error_on_block = self.error_on_block;
+        let output_filters = self.output_filters.clone();
+        let (request, tags) = self.tagged_request()?;
+        ensure_no_safety_settings(&request)?;
+        let mut response = client.create_interaction(request).await?;
+        response.filter_tags = tags;
 
         Span::current().record("status.code", response.status.as_ref());
 
@@ -473,6 +587,16 @@ impl InteractionBuilder {
             Span::current().record("usage.total_tokens", usage.total_tokens);
         }
 
+        if error_on_block {
+            let outcome = response.safety_outcome();
+            if outcome.is_blocked() {
+                return Err(ClientError::Blocked { outcome });
+            }
+        }
+        if !output_filters.is_empty() {
+            output_filters.filter_interaction(&mut response)?;
+        }
+
         Ok(response)
     }
 
@@ -484,7 +608,129 @@ impl InteractionBuilder {
     ))]
     pub async fn execute_stream(self) -> Result<InteractionStream, ClientError> {
         let client = self.client.clone();
-        let request = self.build()?;
+        let request = self.filtered_request()?;
+        ensure_no_safety_settings(&request)?;
         client.create_interaction_stream(request).await
     }
+
+    /// Execute the interaction, running the model's function calls with the registered
+    /// [`FunctionHandler`]s until it answers without calling a function.
+    ///
+    /// Results are sent back with `previous_interaction_id` when the interaction is
+    /// stored, and with the whole conversation as step input otherwise. Calls of one turn
+    /// run concurrently. When the model calls a function no handler answers, the
+    /// interaction is returned as is so the caller can handle the calls. Fails with
+    /// [`ClientError::FunctionRoundsExceeded`] when the model is still calling functions
+    /// after the configured number of rounds.
+    #[instrument(skip_all, fields(
+        model = self.model.as_deref().unwrap_or(""),
+        handlers.count = self.function_handlers.len(),
+        rounds,
+    ))]
+    pub async fn execute_with_functions(self) -> Result<Interaction, ClientError> {
+        let handlers = self.function_handlers.clone();
+        let max_rounds = self.max_function_rounds;
+        let mut builder = self;
+        let mut rounds = 0;
+        loop {
+            let interaction = builder.clone().execute().await?;
+            let calls: Option<Vec<_>> = functions::pending_calls(&interaction)
+                .into_iter()
+                .map(|(name, arguments, id)| {
+                    let handler = handlers.iter().find(|h| h.handles(name))?.clone();
+                    Some((handler, name.to_string(), arguments.clone(), id.to_string()))
+                })
+                .collect();
+            let calls = match calls {
+                Some(calls) if !calls.is_empty() => calls,
+                _ => {
+                    Span::current().record("rounds", rounds);
+                    return Ok(interaction);
+                }
+            };
+            if rounds == max_rounds {
+                return Err(ClientError::FunctionRoundsExceeded { rounds });
+            }
+            rounds += 1;
+
+            let routed = calls
+                .into_iter()
+                .map(|(handler, name, arguments, call_id)| async move {
+                    tracing::debug!(function = name, call_id, "running function call");
+                    let result = handler.call(&name, arguments).await;
+                    functions::function_result(name, call_id, result)
+                });
+
+            let results = futures::future::join_all(routed).await;
+            builder = builder.continued(&interaction, results);
+        }
+    }
+
+    /// The builder for the request sending `results` after `interaction`, which this
+    /// builder produced.
+    ///
+    /// Uses `previous_interaction_id` when the interaction is stored, and the whole
+    /// conversation as step input otherwise.
+    pub(crate) fn continued(mut self, interaction: &Interaction, results: Vec<Step>) -> Self {
+        match interaction.id() {
+            Some(id) if self.store != Some(false) => {
+                self.with_previous_interaction(id).with_step_input(results)
+            }
+            _ => {
+                let mut steps = functions::history(self.input.take(), interaction);
+                steps.extend(results);
+                self.with_step_input(steps)
+            }
+        }
+    }
+
+    /// Adds a content item to the input, keeping text input as the first item.
+    pub(crate) fn with_appended_content(mut self, content: InteractionContent) -> Self {
+        self.input = Some(match self.input {
+            Some(InteractionInput::Text(text)) => {
+                InteractionInput::ContentArray(vec![InteractionContent::text(text), content])
+            }
+            Some(InteractionInput::Content(first)) => {
+                InteractionInput::ContentArray(vec![first, content])
+            }
+            Some(InteractionInput::ContentArray(mut items)) => {
+                items.push(content);
+                InteractionInput::ContentArray(items)
+            }
+            Some(InteractionInput::StepArray(mut steps)) => {
+                steps.push(Step::UserInput {
+                    content: vec![content],
+                });
+                InteractionInput::StepArray(steps)
+            }
+            None => InteractionInput::ContentArray(vec![content]),
+        });
+        self
+    }
+
+    /// Build the request and run the content filters over it.
+    pub(crate) fn filtered_request(self) -> Result<CreateInteractionRequest, ClientError> {
+        self.tagged_request().map(|(request, _)| request)
+    }
+
+    /// Like [`filtered_request`](Self::filtered_request), also returning the tags raised.
+    fn tagged_request(self) -> Result<(CreateInteractionRequest, Vec<FilterTag>), ClientError> {
+        let content_filters = self.content_filters.clone();
+        let mut request = self.build()?;
+        let tags = if content_filters.is_empty() {
+            Vec::new()
+        } else {
+            content_filters.filter_interaction_request(&mut request)?
+        };
+        Ok((request, tags))
+    }
+}
+
+/// Fails if the request carries safety settings, which the Interactions API would drop.
+fn ensure_no_safety_settings(request: &CreateInteractionRequest) -> Result<(), ClientError> {
+    if request.safety_settings.is_empty() {
+        Ok(())
+    } else {
+        Err(ClientError::SafetySettingsUnsupported)
+    }
 }
@@ -0,0 +1,772 @@
+//! Conversions between Interactions API types and the `generateContent` wire format.
+//!
+//! Some Gemini endpoints — batch mode and context caching in particular — only accept the
+//! `generateContent` request shape. These conversions let code written against
+//! [`CreateInteractionRequest`] reach them, and turn the resulting
+//! [`GenerationResponse`] back into an [`Interaction`].
+//!
+//! Only the parts of an interaction that have a `generateContent` equivalent can be
+//! converted. Agents, server-side conversation state (`previous_interaction_id`),
+//! environments and tools that run outside the model (MCP servers, computer use, retrieval)
+//! are rejected with a [`ConversionError`] rather than silently dropped. Transport options
+//! such as `stream`, `store`, `background`, `service_tier` and `webhook_config` do not
+//! affect the generated content and are ignored.
+//!
+//! Steps describing server-side tool invocations (Google Search, URL context, Maps, File
+//! Search and MCP calls) are skipped when converting history, since the model re-runs those
+//! tools itself.
+
+#![allow(deprecated)]
+
+use serde::Serialize;
+use serde_json::json;
+use snafu::{OptionExt, Snafu};
+
+use super::model::*;
+use crate::{
+    generation::{
+        Candidate, FinishReason, GenerateContentRequest, GenerationConfig, GenerationResponse,
+        ThinkingConfig, ThinkingLevel,
+    },
+    tools::{
+        CodeExecutionOutcome, CodeExecutionResult, ExecutableCode, FunctionCall,
+        FunctionDeclaration, FunctionResponse, LatLng, RetrievalConfig, Tool, ToolConfig,
+    },
+    Blob, Content, FileData, Part, Role,
+};
+
+#[derive(Debug, Snafu)]
+pub enum ConversionError {
+    #[snafu(display("'{field}' has no generateContent equivalent"))]
+    UnsupportedField { field: &'static str },
+
+    #[snafu(display("{tool} tools have no generateContent equivalent"))]
+    UnsupportedTool { tool: &'static str },
+
+    #[snafu(display("{kind} content has neither inline data nor a URI"))]
+    MissingContentData { kind: &'static str },
+
+    #[snafu(display("unable to determine the MIME type of {kind} content"))]
+    MissingMimeType { kind: &'static str },
+
+    #[snafu(display("function tool is missing a name"))]
+    MissingFunctionName,
+
+    #[snafu(display("image function results have no generateContent equivalent"))]
+    ImageFunctionResult,
+
+    #[snafu(display("'{field}' value {value} does not fit in a 32-bit integer"))]
+    OutOfRange { field: &'static str, value: i64 },
+}
+
+/// Serializes a MIME type enum to the string it represents on the wire.
+fn mime_string<T: Serialize>(mime_type: &Option<T>) -> Option<String> {
+    mime_type
+        .as_ref()
+        .and_then(|m| serde_json::to_value(m).ok())
+        .and_then(|v| v.as_str().map(str::to_string))
+}
+
+fn media_part(
+    kind: &'static str,
+    data: &Option<String>,
+    uri: &Option<String>,
+    mime_type: Option<String>,
+) -> Result<Part, ConversionError> {
+    match (data, uri) {
+        (Some(data), _) => Ok(Part::InlineData {
+            inline_data: Blob::new(mime_type.context(MissingMimeTypeSnafu { kind })?, data),
+            media_resolution: None,
+        }),
+        (None, Some(uri)) => {
+            let mime_type = mime_type
+                .or_else(|| {
+                    mime_guess::from_path(uri)
+                        .first()
+                        .map(|mime| mime.to_string())
+                })
+                .context(MissingMimeTypeSnafu { kind })?;
+            Ok(Part::FileData {
+                file_data: FileData {
+                    mime_type,
+                    file_uri: uri.clone(),
+                },
+            })
+        }
+        (None, None) => MissingContentDataSnafu { kind }.fail(),
+    }
+}
+
+/// Converts a single content item into a `generateContent` part.
+pub(crate) fn content_to_part(content: &InteractionContent) -> Result<Part, ConversionError> {
+    match content {
+        InteractionContent::Text { text, .. } => Ok(Part::Text {
+            text: text.clone(),
+            thought: None,
+            thought_signature: None,
+        }),
+        InteractionContent::Image {
+            data,
+            uri,
+            mime_type,
+            ..
+        } => media_part("image", data, uri, mime_string(mime_type)),
+        InteractionContent::Audio {
+            data,
+            uri,
+            mime_type,
+            ..
+        } => media_part("audio", data, uri, mime_string(mime_type)),
+        InteractionContent::Document {
+            data,
+            uri,
+            mime_type,
+        } => media_part("document", data, uri, mime_string(mime_type)),
+        InteractionContent::Video {
+            data,
+            uri,
+            mime_type,
+            ..
+        } => media_part("video", data, uri, mime_string(mime_type)),
+    }
+}
+
+/// Converts content items into a single user turn.
+pub(crate) fn contents_to_content(
+    contents: &[InteractionContent],
+) -> Result<Content, ConversionError> {
+    let parts = contents
+        .iter()
+        .map(content_to_part)
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(Content {
+        parts: Some(parts),
+        role: Some(Role::User),
+    })
+}
+
+/// Appends a part to the last turn when the role matches, otherwise starts a new turn.
+fn push_part(contents: &mut Vec<Content>, role: Role, part: Part) {
+    if let Some(last) = contents.last_mut() {
+        if last.role.as_ref() == Some(&role) {
+            last.parts.get_or_insert_with(Vec::new).push(part);
+            return;
+        }
+    }
+    contents.push(Content {
+        parts: Some(vec![part]),
+        role: Some(role),
+    });
+}
+
+fn function_result_value(
+    result: &StepResult,
+    is_error: Option<bool>,
+) -> Result<serde_json::Value, ConversionError> {
+    let value = match result {
+        StepResult::Object(value @ serde_json::Value::Object(_)) => value.clone(),
+        StepResult::Object(value) => json!({ "result": value }),
+        StepResult::String(text) => json!({ "result": text }),
+        StepResult::ContentArray(items) => {
+            let mut texts = Vec::with_capacity(items.len());
+            for item in items {
+                match item {
+                    StepResultContent::Text { text } => texts.push(text.as_str()),
+                    StepResultContent::Image { .. } => return ImageFunctionResultSnafu.fail(),
+                }
+            }
+            json!({ "result": texts.join("\n") })
+        }
+    };
+
+    Ok(if is_error == Some(true) {
+        json!({ "error": value })
+    } else {
+        value
+    })
+}
+
+/// Converts a step history into alternating `generateContent` turns.
+pub(crate) fn steps_to_contents(steps: &[Step]) -> Result<Vec<Content>, ConversionError> {
+    let mut contents = Vec::new();
+    // Thought signatures must travel with the function call that follows them.
+    let mut pending_signature: Option<String> = None;
+
+    for step in steps {
+        match step {
+            Step::UserInput { content } => {
+                for item in content {
+                    push_part(&mut contents, Role::User, content_to_part(item)?);
+                }
+            }
+            Step::ModelOutput { content, .. } => {
+                for item in content {
+                    push_part(&mut contents, Role::Model, content_to_part(item)?);
+                }
+            }
+            Step::Thought { signature, summary } => {
+                let text = summary
+                    .iter()
+                    .map(|ThoughtSummaryContent::Text { text }| text.as_str())
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                if !text.is_empty() {
+                    push_part(
+                        &mut contents,
+                        Role::Model,
+                        Part::Text {
+                            text,
+                            thought: Some(true),
+                            thought_signature: None,
+                        },
+                    );
+                }
+                pending_signature = signature.clone();
+            }
+            Step::FunctionCall {
+                name, arguments, ..
+            } => push_part(
+                &mut contents,
+                Role::Model,
+                Part::FunctionCall {
+                    function_call: FunctionCall::new(name.clone(), arguments.clone()),
+                    thought_signature: pending_signature.take(),
+                },
+            ),
+            Step::FunctionResult {
+                name,
+                call_id,
+                result,
+                is_error,
+            } => {
+                let name = name.clone().unwrap_or_else(|| {
+                    steps
+                        .iter()
+                        .find_map(|s| match s {
+                            Step::FunctionCall { name, id, .. } if id == call_id => {
+                                Some(name.clone())
+                            }
+                            _ => None,
+                        })
+                        .unwrap_or_else(|| call_id.clone())
+                });
+                push_part(
+                    &mut contents,
+                    Role::User,
+                    Part::FunctionResponse {
+                        function_response: FunctionResponse::new(
+                            name,
+                            function_result_value(result, *is_error)?,
+                        ),
+                    },
+                );
+            }
+            Step::CodeExecutionCall { arguments, .. } => push_part(
+                &mut contents,
+                Role::Model,
+                Part::ExecutableCode {
+                    executable_code: ExecutableCode {
+                        language: crate::tools::CodeLanguage::Python,
+                        code: arguments.code.clone().unwrap_or_default(),
+                    },
+                },
+            ),
+            Step::CodeExecutionResult {
+                result, is_error, ..
+            } => push_part(
+                &mut contents,
+                Role::Model,
+                Part::CodeExecutionResult {
+                    code_execution_result: CodeExecutionResult {
+                        outcome: if *is_error == Some(true) {
+                            CodeExecutionOutcome::OutcomeFailed
+                        } else {
+                            CodeExecutionOutcome::OutcomeOk
+                        },
+                        output: result.clone(),
+                    },
+                },
+            ),
+            // Server-side tool traces are re-created by the model.
+            Step::UrlContextCall { .. }
+            | Step::UrlContextResult { .. }
+            | Step::GoogleSearchCall { .. }
+            | Step::GoogleSearchResult { .. }
+            | Step::GoogleMapsCall { .. }
+            | Step::GoogleMapsResult { .. }
+            | Step::FileSearchCall { .. }
+            | Step::FileSearchResult { .. }
+            | Step::McpServerToolCall { .. }
+            | Step::McpServerToolResult { .. } => {}
+        }
+    }
+
+    Ok(contents)
+}
+
+/// Converts interaction input into `generateContent` turns.
+pub(crate) fn input_to_contents(input: &InteractionInput) -> Result<Vec<Content>, ConversionError> {
+    match input {
+        InteractionInput::Text(text) => Ok(vec![Content::text(text.clone()).with_role(Role::User)]),
+        InteractionInput::Content(content) => {
+            Ok(vec![contents_to_content(std::slice::from_ref(content))?])
+        }
+        InteractionInput::ContentArray(contents) => Ok(vec![contents_to_content(contents)?]),
+        InteractionInput::StepArray(steps) => steps_to_contents(steps),
+    }
+}
+
+/// Converts interaction tools into `generateContent` tools and an optional tool config.
+///
+/// Function tools are merged into a single function-declaration tool.
+pub(crate) fn tools_to_tools(
+    tools: &[InteractionTool],
+) -> Result<(Vec<Tool>, Option<ToolConfig>), ConversionError> {
+    let mut converted = Vec::new();
+    let mut declarations = Vec::new();
+    let mut tool_config = None;
+
+    for tool in tools {
+        match tool {
+            InteractionTool::Function {
+                name,
+                description,
+                parameters,
+            } => {
+                let name = name.clone().context(MissingFunctionNameSnafu)?;
+                let mut declaration =
+                    FunctionDeclaration::new(name, description.clone().unwrap_or_default(), None);
+                declaration.parameters_json_schema = parameters.clone();
+                declarations.push(declaration);
+            }
+            InteractionTool::CodeExecution => converted.push(Tool::code_execution()),
+            InteractionTool::UrlContext => converted.push(Tool::url_context()),
+            InteractionTool::GoogleSearch { .. } => converted.push(Tool::google_search()),
+            InteractionTool::GoogleMaps {
+                enable_widget,
+                latitude,
+                longitude,
+            } => {
+                converted.push(Tool::google_maps(*enable_widget));
+                if let (Some(latitude), Some(longitude)) = (latitude, longitude) {
+                    tool_config = Some(ToolConfig {
+                        function_calling_config: None,
+                        include_server_side_tool_invocations: None,
+                        retrieval_config: Some(RetrievalConfig {
+                            lat_lng: Some(LatLng::new(*latitude, *longitude)),
+                        }),
+                    });
+                }
+            }
+            InteractionTool::FileSearch {
+                file_search_store_names,
+                metadata_filter,
+                ..
+            } => converted.push(Tool::file_search(
+                file_search_store_names.clone(),
+                metadata_filter.clone(),
+            )),
+            InteractionTool::ComputerUse { .. } => {
+                return UnsupportedToolSnafu {
+                    tool: "computer use",
+                }
+                .fail()
+            }
+            InteractionTool::McpServer { .. } => {
+                return UnsupportedToolSnafu { tool: "MCP server" }.fail()
+            }
+            InteractionTool::Retrieval { .. } => {
+                return UnsupportedToolSnafu { tool: "retrieval" }.fail()
+            }
+        }
+    }
+
+    if !declarations.is_empty() {
+        converted.insert(0, Tool::with_functions(declarations));
+    }
+    Ok((converted, tool_config))
+}
+
+fn to_i32(field: &'static str, value: i64) -> Result<i32, ConversionError> {
+    i32::try_from(value)
+        .ok()
+        .context(OutOfRangeSnafu { field, value })
+}
+
+fn generation_config(
+    config: Option<&InteractionGenerationConfig>,
+    response_format: Option<&ResponseFormat>,
+    response_modalities: &[ResponseModality],
+) -> Result<Option<GenerationConfig>, ConversionError> {
+    if config.is_none() && response_format.is_none() && response_modalities.is_empty() {
+        return Ok(None);
+    }
+
+    let mut converted = GenerationConfig::default();
+
+    if let Some(config) = config {
+        if config.presence_penalty.is_some() {
+            return UnsupportedFieldSnafu {
+                field: "presence_penalty",
+            }
+            .fail();
+        }
+        if config.frequency_penalty.is_some() {
+            return UnsupportedFieldSnafu {
+                field: "frequency_penalty",
+            }
+            .fail();
+        }
+        if config.tool_choice.is_some() {
+            return UnsupportedFieldSnafu {
+                field: "tool_choice",
+            }
+            .fail();
+        }
+        if config.video_config.is_some() {
+            return UnsupportedFieldSnafu {
+                field: "video_config",
+            }
+            .fail();
+        }
+        if !config.speech_config.is_empty() {
+            return UnsupportedFieldSnafu {
+                field: "speech_config",
+            }
+            .fail();
+        }
+
+        converted.temperature = config.temperature.map(|t| t as f32);
+        converted.top_p = config.top_p.map(|p| p as f32);
+        converted.seed = config.seed.map(|s| to_i32("seed", s)).transpose()?;
+        converted.max_output_tokens = config
+            .max_output_tokens
+            .map(|m| to_i32("max_output_tokens", m))
+            .transpose()?;
+        if !config.stop_sequences.is_empty() {
+            converted.stop_sequences = Some(config.stop_sequences.clone());
+        }
+        if config.thinking_level.is_some() || config.thinking_summaries.is_some() {
+            converted.thinking_config = Some(ThinkingConfig {
+                thinking_budget: None,
+                include_thoughts: config
+                    .thinking_summaries
+                    .as_ref()
+                    .map(|s| matches!(s, ThinkingSummaries::Auto)),
+                thinking_level: config.thinking_level.as_ref().map(|level| match level {
+                    InteractionThinkingLevel::Minimal => ThinkingLevel::Minimal,
+                    InteractionThinkingLevel::Low => ThinkingLevel::Low,
+                    InteractionThinkingLevel::Medium => ThinkingLevel::Medium,
+                    InteractionThinkingLevel::High => ThinkingLevel::High,
+                }),
+            });
+        }
+    }
+
+    match response_format {
+        None => {}
+        Some(ResponseFormat::Text { mime_type, schema }) => {
+            converted.response_mime_type = mime_string(mime_type);
+            converted.response_json_schema = schema.clone();
+        }
+        Some(_) => {
+            return UnsupportedFieldSnafu {
+                field: "response_format",
+            }
+            .fail()
+        }
+    }
+
+    if !response_modalities.is_empty() {
+        let modalities = response_modalities
+            .iter()
+            .map(|modality| match modality {
+                ResponseModality::Text => Ok("TEXT".to_string()),
+                ResponseModality::Image => Ok("IMAGE".to_string()),
+                ResponseModality::Audio => Ok("AUDIO".to_string()),
+                ResponseModality::Video | ResponseModality::Document => UnsupportedFieldSnafu {
+                    field: "response_modalities",
+                }
+                .fail(),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        converted.response_modalities = Some(modalities);
+    }
+
+    Ok(Some(converted))
+}
+
+impl TryFrom<&CreateInteractionRequest> for GenerateContentRequest {
+    type Error = ConversionError;
+
+    /// Converts an interaction request into the `generateContent` wire format.
+    ///
+    /// The request's `model` is not part of the converted body; callers submit the result
+    /// against the model of their choice.
+    fn try_from(request: &CreateInteractionRequest) -> Result<Self, Self::Error> {
+        if request.agent.is_some() {
+            return UnsupportedFieldSnafu { field: "agent" }.fail();
+        }
+        if request.agent_config.is_some() {
+            return UnsupportedFieldSnafu {
+                field: "agent_config",
+            }
+            .fail();
+        }
+        if request.previous_interaction_id.is_some() {
+            return UnsupportedFieldSnafu {
+                field: "previous_interaction_id",
+            }
+            .fail();
+        }
+        if request.environment.is_some() {
+            return UnsupportedFieldSnafu {
+                field: "environment",
+            }
+            .fail();
+        }
+
+        let (tools, tool_config) = tools_to_tools(&request.tools)?;
+
+        Ok(GenerateContentRequest {
+            contents: input_to_contents(&request.input)?,
+            generation_config: generation_config(
+                request.generation_config.as_ref(),
+                request.response_format.as_ref(),
+                &request.response_modalities,
+            )?,
+            safety_settings: (!request.safety_settings.is_empty())
+                .then(|| request.safety_settings.clone()),
+            tools: (!tools.is_empty()).then_some(tools),
+            tool_config,
+            system_instruction: request.system_instruction.clone().map(Content::text),
+            cached_content: request.cached_content.clone(),
+        })
+    }
+}
+
+impl TryFrom<CreateInteractionRequest> for GenerateContentRequest {
+    type Error = ConversionError;
+
+    fn try_from(request: CreateInteractionRequest) -> Result<Self, Self::Error> {
+        GenerateContentRequest::try_from(&request)
+    }
+}
+
+/// Converts an inline or file-backed part into interaction output content.
+fn part_to_content(part: &Part) -> Option<InteractionContent> {
+    fn parse_mime<T: serde::de::DeserializeOwned>(mime_type: &str) -> Option<T> {
+        serde_json::from_value(json!(mime_type)).ok()
+    }
+
+    let (data, uri, mime_type) = match part {
+        Part::InlineData { inline_data, .. } => (
+            Some(inline_data.data.clone()),
+            None,
+            inline_data.mime_type.as_str(),
+        ),
+        Part::FileData { file_data } => (
+            None,
+            Some(file_data.file_uri.clone()),
+            file_data.mime_type.as_str(),
+        ),
+        _ => return None,
+    };
+
+    let content = match mime_type.split('/').next() {
+        Some("image") => InteractionContent::Image {
+            data,
+            uri,
+            mime_type: parse_mime(mime_type),
+            resolution: None,
+        },
+        Some("audio") => InteractionContent::Audio {
+            data,
+            uri,
+            mime_type: parse_mime(mime_type),
+            channels: None,
+            sample_rate: None,
+        },
+        Some("video") => InteractionContent::Video {
+            data,
+            uri,
+            mime_type: parse_mime(mime_type),
+            resolution: None,
+        },
+        _ => InteractionContent::Document {
+            data,
+            uri,
+            mime_type: parse_mime(mime_type),
+        },
+    };
+    Some(content)
+}
+
+/// Appends output content, merging it into a directly preceding model output step.
+fn push_output(steps: &mut Vec<Step>, content: InteractionContent) {
+    if let Some(Step::ModelOutput { content: last, .. }) = steps.last_mut() {
+        last.push(content);
+    } else {
+        steps.push(Step::ModelOutput {
+            content: vec![content],
+            error: None,
+        });
+    }
+}
+
+fn candidate_steps(candidate: &Candidate) -> Vec<Step> {
+    let mut steps = Vec::new();
+    let mut function_calls = 0;
+    let mut code_calls = 0;
+
+    for part in candidate.content.parts.iter().flatten() {
+        match part {
+            Part::Text {
+                text,
+                thought: Some(true),
+                thought_signature,
+            } => steps.push(Step::Thought {
+                signature: thought_signature.clone(),
+                summary: vec![ThoughtSummaryContent::Text { text: text.clone() }],
+            }),
+            Part::Text { text, .. } => push_output(&mut steps, InteractionContent::text(text)),
+            Part::FunctionCall {
+                function_call,
+                thought_signature,
+            } => {
+                if let Some(signature) = thought_signature {
+                    steps.push(Step::Thought {
+                        signature: Some(signature.clone()),
+                        summary: vec![],
+                    });
+                }
+                function_calls += 1;
+                steps.push(Step::FunctionCall {
+                    name: function_call.name.clone(),
+                    arguments: function_call.args.clone(),
+                    id: format!("call_{function_calls}"),
+                });
+            }
+            Part::ExecutableCode { executable_code } => {
+                code_calls += 1;
+                steps.push(Step::CodeExecutionCall {
+                    arguments: CodeExecutionCallArguments {
+                        language: Some(CodeLanguage::Python),
+                        code: Some(executable_code.code.clone()),
+                    },
+                    id: format!("code_{code_calls}"),
+                    signature: None,
+                });
+            }
+            Part::CodeExecutionResult {
+                code_execution_result,
+            } => steps.push(Step::CodeExecutionResult {
+                result: code_execution_result.output.clone(),
+                call_id: format!("code_{code_calls}"),
+                is_error: Some(!matches!(
+                    code_execution_result.outcome,
+                    CodeExecutionOutcome::OutcomeOk
+                )),
+                signature: None,
+            }),
+            part => {
+                if let Some(content) = part_to_content(part) {
+                    push_output(&mut steps, content);
+                }
+            }
+        }
+    }
+
+    steps
+}
+
+/// Returns the wire name of a serializable enum value, e.g. `"SAFETY"`.
+fn wire_name<T: Serialize>(value: &T) -> String {
+    serde_json::to_value(value)
+        .ok()
+        .and_then(|v| v.as_str().map(str::to_string))
+        .unwrap_or_default()
+}
+
+impl From<GenerationResponse> for Interaction {
+    /// Converts a `generateContent` response into an [`Interaction`].
+    ///
+    /// The first candidate becomes the interaction's steps. Function calls receive
+    /// sequential ids (`call_1`, `call_2`, ...) because `generateContent` does not assign any.
+    fn from(response: GenerationResponse) -> Self {
+        let candidate = response.candidates.first();
+        let steps = candidate.map(candidate_steps).unwrap_or_default();
+
+        let block_reason = response
+            .prompt_feedback
+            .as_ref()
+            .and_then(|feedback| feedback.block_reason.as_ref());
+        let (status, error) = match (block_reason, candidate) {
+            (Some(reason), _) => (
+                InteractionStatus::Failed,
+                Some(InteractionError {
+                    code: Some(wire_name(reason).to_lowercase()),
+                    message: Some("the prompt was blocked".to_string()),
+                }),
+            ),
+            (None, None) => (
+                InteractionStatus::Failed,
+                Some(InteractionError {
+                    code: None,
+                    message: Some("the response contained no candidates".to_string()),
+                }),
+            ),
+            (None, Some(candidate)) => match &candidate.finish_reason {
+                None | Some(FinishReason::Stop) | Some(FinishReason::FinishReasonUnspecified) => {
+                    if steps
+                        .iter()
+                        .any(|step| matches!(step, Step::FunctionCall { .. }))
+                    {
+                        (InteractionStatus::RequiresAction, None)
+                    } else {
+                        (InteractionStatus::Completed, None)
+                    }
+                }
+                Some(FinishReason::MaxTokens) => (InteractionStatus::Incomplete, None),
+                Some(reason) => (
+                    InteractionStatus::Failed,
+                    Some(InteractionError {
+                        code: Some(wire_name(reason).to_lowercase()),
+                        message: Some("generation stopped before completing".to_string()),
+                    }),
+                ),
+            },
+        };
+
+        let usage = response.usage_metadata.map(|usage| InteractionUsage {
+            total_input_tokens: usage.prompt_token_count.map(i64::from),
+            total_cached_tokens: usage.cached_content_token_count.map(i64::from),
+            total_output_tokens: usage.candidates_token_count.map(i64::from),
+            total_thought_tokens: usage.thoughts_token_count.map(i64::from),
+            total_tokens: usage.total_token_count.map(i64::from),
+            ..Default::default()
+        });
+
+        Interaction {
+            id: response.response_id,
+            status,
+            model: response.model_version,
+            agent: None,
+            object: None,
+            created: None,
+            updated: None,
+            steps,
+            usage,
+            system_instruction: None,
+            tools: vec![],
+            previous_interaction_id: None,
+            environment_id: None,
+            response_modalities: None,
+            service_tier: None,
+            cached_content: None,
+            agent_config: None,
+            error,
+            filter_tags: vec![],
+        }
+    }
+}
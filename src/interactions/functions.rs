@@ -0,0 +1,137 @@
+//! Automatic function calling for interactions.
+//!
+//! A [`FunctionHandler`] declares function tools and answers the model's calls to them.
+//! Handlers registered with
+//! [`InteractionBuilder::with_function_handler`](crate::interactions::InteractionBuilder::with_function_handler)
+//! are driven by
+//! [`execute_with_functions`](crate::interactions::InteractionBuilder::execute_with_functions),
+//! which sends their results back to the model until it stops calling functions.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # use gemini_rust::prelude::*;
+//! # use gemini_rust::FunctionHandler;
+//! # use serde_json::{json, Value};
+//! struct Weather;
+//!
+//! #[async_trait::async_trait]
+//! impl FunctionHandler for Weather {
+//!     fn tools(&self) -> Vec<InteractionTool> {
+//!         vec![InteractionTool::function(
+//!             "get_weather",
+//!             "Get the current weather for a city",
+//!             json!({"type": "object", "properties": {"city": {"type": "string"}}}),
+//!         )]
+//!     }
+//!
+//!     async fn call(&self, _name: &str, arguments: Value) -> Result<StepResult, String> {
+//!         Ok(StepResult::from_json(json!({"city": arguments["city"], "sky": "clear"})))
+//!     }
+//! }
+//!
+//! # async fn example(gemini: &Gemini) -> Result<(), Box<dyn std::error::Error>> {
+//! let interaction = gemini
+//!     .create_interaction()
+//!     .with_model("gemini-2.5-flash")
+//!     .with_text("What's the weather like in Lisbon?")
+//!     .with_function_handler(Weather)
+//!     .execute_with_functions()
+//!     .await?;
+//!
+//! println!("{}", interaction.output_text());
+//! # Ok(())
+//! # }
+//! ```
+
+use async_trait::async_trait;
+use serde_json::Value;
+
+use crate::interactions::model::{
+    Interaction, InteractionContent, InteractionInput, InteractionTool, Step, StepResult,
+};
+
+/// Declares function tools and runs the model's calls to them.
+#[async_trait]
+pub trait FunctionHandler: Send + Sync {
+    /// The tools added to the request when the handler is registered.
+    fn tools(&self) -> Vec<InteractionTool>;
+
+    /// Whether calls to `name` are routed to this handler.
+    ///
+    /// Defaults to the names of the function tools returned by [`tools`](Self::tools).
+    fn handles(&self, name: &str) -> bool {
+        self.tools().iter().any(|tool| {
+            matches!(tool, InteractionTool::Function { name: Some(declared), .. } if declared == name)
+        })
+    }
+
+    /// Runs a call. An error is sent back to the model as an error result, so it can
+    /// react to the failure.
+    async fn call(&self, name: &str, arguments: Value) -> Result<StepResult, String>;
+}
+
+/// The function calls of an interaction that have no result yet, as `(name, arguments, id)`.
+pub(crate) fn pending_calls(interaction: &Interaction) -> Vec<(&str, &Value, &str)> {
+    let answered: Vec<&str> = interaction
+        .steps
+        .iter()
+        .filter_map(|step| match step {
+            Step::FunctionResult { call_id, .. } => Some(call_id.as_str()),
+            _ => None,
+        })
+        .collect();
+    interaction
+        .steps
+        .iter()
+        .filter_map(|step| match step {
+            Step::FunctionCall {
+                name,
+                arguments,
+                id,
+            } if !answered.contains(&id.as_str()) => Some((name.as_str(), arguments, id.as_str())),
+            _ => None,
+        })
+        .collect()
+}
+
+/// The step answering call `call_id` to `name`, flagged as an error when `result` is one.
+pub(crate) fn function_result(
+    name: impl Into<String>,
+    call_id: impl Into<String>,
+    result: Result<StepResult, String>,
+) -> Step {
+    let is_error = result.is_err();
+    Step::FunctionResult {
+        name: Some(name.into()),
+        call_id: call_id.into(),
+        result: result.unwrap_or_else(StepResult::String),
+        is_error: is_error.then_some(true),
+    }
+}
+
+/// The conversation so far as steps, for continuing an interaction that is not stored.
+///
+/// `input` is the input of the request that produced `interaction`; the user input the
+/// response echoes back is skipped in favour of it.
+pub(crate) fn history(input: Option<InteractionInput>, interaction: &Interaction) -> Vec<Step> {
+    let mut steps = match input {
+        Some(InteractionInput::Text(text)) => vec![Step::UserInput {
+            content: vec![InteractionContent::text(text)],
+        }],
+        Some(InteractionInput::Content(content)) => vec![Step::UserInput {
+            content: vec![content],
+        }],
+        Some(InteractionInput::ContentArray(content)) => vec![Step::UserInput { content }],
+        Some(InteractionInput::StepArray(steps)) => steps,
+        None => Vec::new(),
+    };
+    steps.extend(
+        interaction
+            .steps
+            .iter()
+            .filter(|step| !matches!(step, Step::UserInput { .. }))
+            .cloned(),
+    );
+    steps
+}
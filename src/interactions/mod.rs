@@ -29,11 +29,15 @@
 //! ```
 
 pub mod builder;
+pub mod convert;
+pub mod functions;
 pub mod handle;
 pub mod model;
 pub mod stream;
 
 pub use builder::InteractionBuilder;
+pub use convert::ConversionError;
+pub use functions::FunctionHandler;
 pub use handle::InteractionHandle;
 pub use model::*;
 pub use stream::{InteractionEvent, InteractionStream, StepDeltaData};
@@ -104,6 +108,19 @@ impl Interaction {
         self.status == InteractionStatus::Completed
     }
 
+    /// Classify why the interaction was blocked, if it was.
+    ///
+    /// This is best-effort: the Interactions API does not report safety ratings, so the
+    /// outcome is inferred from the status and error code and never lists flagged ratings.
+    pub fn safety_outcome(&self) -> crate::safety::SafetyOutcome {
+        crate::safety::SafetyOutcome::of_interaction(self)
+    }
+
+    /// Whether the interaction was blocked without producing output.
+    pub fn is_blocked(&self) -> bool {
+        self.safety_outcome().is_blocked()
+    }
+
     /// Get the output image (last model-generated image).
     pub fn output_image(&self) -> Option<&InteractionContent> {
         self.steps.iter().rev().find_map(|s| {
@@ -177,6 +194,22 @@ impl Interaction {
             .collect()
     }
 
+    /// Get the final text output with its citations resolved.
+    ///
+    /// Uses the last `model_output` step, like [`output_text`](Self::output_text).
+    pub fn annotated_text(&self) -> crate::citation::AnnotatedText {
+        let content = self
+            .steps
+            .iter()
+            .rev()
+            .find_map(|s| match s {
+                Step::ModelOutput { content, .. } => Some(content.as_slice()),
+                _ => None,
+            })
+            .unwrap_or_default();
+        crate::citation::CitationResolver::new().resolve_annotations(content)
+    }
+
     /// Get the total token count.
     pub fn total_tokens(&self) -> Option<i64> {
         self.usage.as_ref()?.total_tokens
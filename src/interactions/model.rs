@@ -1,5 +1,7 @@
 use serde::{Deserialize, Serialize};
 
+use crate::safety::{filter::FilterTag, SafetySetting};
+
 // ============================================================================
 // Content Types (polymorphic, type-tagged)
 // ============================================================================
@@ -499,6 +501,10 @@ pub struct Interaction {
 
     #[serde(skip_serializing_if = "Option::is_none")]
     pub error: Option<InteractionError>,
+
+    /// Tags raised by the builder's content and output filters; not part of the API response.
+    #[serde(skip)]
+    pub filter_tags: Vec<FilterTag>,
 }
 
 /// Interaction status.
@@ -681,6 +687,21 @@ impl InteractionTool {
         }
     }
 
+    /// Create a file search tool restricted to documents matching a metadata filter.
+    ///
+    /// Fails if the filter has an invalid key or an empty group.
+    pub fn file_search_with_filter(
+        store_names: Vec<String>,
+        metadata_filter: &crate::file_search::MetadataFilter,
+    ) -> Result<Self, crate::file_search::FilterError> {
+        metadata_filter.check()?;
+        Ok(Self::FileSearch {
+            file_search_store_names: store_names,
+            top_k: None,
+            metadata_filter: Some(metadata_filter.to_string()),
+        })
+    }
+
     /// Create an MCP Server tool.
     pub fn mcp_server(name: impl Into<String>, url: impl Into<String>) -> Self {
         Self::McpServer {
@@ -1499,6 +1520,11 @@ pub struct CreateInteractionRequest {
 
     #[serde(skip_serializing_if = "Option::is_none")]
     pub webhook_config: Option<WebhookConfig>,
+
+    /// Safety settings for when the request is converted to `generateContent`, e.g. in a
+    /// batch. The Interactions API has no such field, so they are never sent with it.
+    #[serde(skip)]
+    pub safety_settings: Vec<SafetySetting>,
 }
 
 /// Input can be a string, single Content, Content array, or Step array.
@@ -84,6 +84,15 @@ pub mod tools;
 /// File search for retrieval augmented generation (RAG)
 pub mod file_search;
 
+/// Citation resolution for grounded and annotated output
+pub mod citation;
+
+/// Local MCP servers exposed as function tools
+pub mod mcp;
+
+/// Typed computer-use actions and the loop that executes them
+pub mod computer_use;
+
 #[cfg(test)]
 mod tests;
 
@@ -100,6 +109,8 @@ pub use client::GeminiBuilder;
 pub use client::GenerationStream;
 /// Available Gemini models
 pub use client::Model;
+/// Polling configuration for long-running operations
+pub use common::PollPolicy;
 
 /// Core primitive types for building requests and parsing responses
 pub use models::{Blob, Content, FileData, Message, Modality, Part, Role};
@@ -114,9 +125,9 @@ pub use generation::{
     model::GroundingChunk, model::GroundingMetadata, model::GroundingSegment,
     model::GroundingSupport, model::MapsGroundingChunk, model::MediaResolution,
     model::MediaResolutionLevel, model::MultiSpeakerVoiceConfig, model::PrebuiltVoiceConfig,
-    model::PromptFeedback, model::PromptTokenDetails, model::SpeakerVoiceConfig,
-    model::SpeechConfig, model::ThinkingConfig, model::ThinkingLevel, model::UsageMetadata,
-    model::VoiceConfig, model::WebGroundingChunk,
+    model::PromptFeedback, model::PromptTokenDetails, model::RetrievedContext,
+    model::SpeakerVoiceConfig, model::SpeechConfig, model::ThinkingConfig, model::ThinkingLevel,
+    model::UsageMetadata, model::VoiceConfig, model::WebGroundingChunk,
 };
 
 // ========== Interactions API ==========
@@ -124,23 +135,30 @@ pub use generation::{
 
 pub use interactions::model::*;
 pub use interactions::{
-    InteractionBuilder, InteractionEvent, InteractionHandle, InteractionStream, StepDeltaData,
+    ConversionError as InteractionConversionError, FunctionHandler, InteractionBuilder,
+    InteractionEvent, InteractionHandle, InteractionStream, StepDeltaData,
 };
 
 // ========== Text Embeddings ==========
 // Types for generating and working with text embeddings
 
 pub use embedding::{
-    builder::EmbedBuilder, model::BatchContentEmbeddingResponse, model::BatchEmbedContentsRequest,
+    builder::EmbedBuilder, builder::EmbedInput, bulk::BulkDocument, bulk::BulkEmbedder,
+    bulk::ChunkEmbedding, bulk::Chunker, bulk::TextChunk, index::EmbeddingIndex, index::IndexEntry,
+    index::SearchHit, math::BinaryEmbedding, math::Int8Embedding,
+    model::BatchContentEmbeddingResponse, model::BatchEmbedContentsRequest,
     model::ContentEmbedding, model::ContentEmbeddingResponse, model::EmbedContentRequest,
-    model::TaskType,
+    model::TaskType, Error as EmbeddingError,
 };
 
 // ========== Safety & Content Filtering ==========
 // Types for content moderation and safety settings
 
-pub use safety::model::{
-    HarmBlockThreshold, HarmCategory, HarmProbability, SafetyRating, SafetySetting,
+pub use safety::{
+    filter::ContentFilter, filter::FilterAction, filter::FilterDirection, filter::FilterTag,
+    filter::RegexFilter, model::HarmBlockThreshold, model::HarmCategory, model::HarmProbability,
+    model::SafetyRating, model::SafetySetting, outcome::SafetyOutcome, policy::SafetyPolicy,
+    Error as SafetyError,
 };
 
 // ========== Function Calling & Tools ==========
@@ -156,10 +174,17 @@ pub use tools::model::{
 // Types for processing multiple requests in batch operations
 
 pub use batch::{
-    builder::BatchBuilder, handle::BatchGenerationResponseItem, handle::BatchHandle,
-    handle::BatchHandle as Batch, handle::BatchStatus, handle::Error as BatchHandleError,
-    model::BatchConfig, model::BatchGenerateContentRequest, model::BatchOperation,
-    model::BatchStats, model::IndividualRequestError, model::RequestMetadata, Error as BatchError,
+    builder::BatchBuilder, builder::ShardLimits, embed_builder::EmbedBatchBuilder,
+    embed_handle::BatchEmbeddingResponseItem, embed_handle::EmbedBatchHandle,
+    embed_handle::EmbedBatchStatus, handle::BatchGenerationResponseItem, handle::BatchHandle,
+    handle::BatchHandle as Batch, handle::BatchOutcome, handle::BatchProgress, handle::BatchStatus,
+    handle::Error as BatchHandleError, interaction_builder::InteractionBatchBuilder,
+    interaction_handle::BatchInteractionResponseItem, interaction_handle::InteractionBatchHandle,
+    interaction_handle::InteractionBatchStatus, journal::BatchJournal, journal::BatchRecord,
+    journal::ResumedBatch, model::AsyncBatchEmbedContentRequest, model::BatchConfig,
+    model::BatchGenerateContentRequest, model::BatchOperation, model::BatchStats,
+    model::IndividualRequestError, model::RequestMetadata, sharded_handle::ShardedBatchHandle,
+    Error as BatchError,
 };
 
 // ========== File Management ==========
@@ -173,8 +198,34 @@ pub use files::{
 // Types for caching contexts and system instructions
 
 pub use cache::{
-    builder::CacheBuilder, handle::CachedContentHandle, model::CacheExpirationRequest,
-    model::CacheExpirationResponse, model::CachedContent, model::CreateCachedContentRequest,
+    builder::CacheBuilder, estimate::CacheEstimate, estimate::CachePricing,
+    handle::CachedContentHandle, manager::CacheLease, manager::CacheManager, manager::CacheSavings,
+    manager::MaintenanceReport, model::CacheExpirationRequest, model::CacheExpirationResponse,
+    model::CachedContent, model::CreateCachedContentRequest,
+};
+
+// ========== Citations ==========
+// Types for resolving citations in model output
+
+pub use citation::{
+    AnnotatedText, CitationResolver, CitedSource, CitedSpan, OffsetUnit, SourceKind,
+};
+
+// ========== MCP ==========
+// Types for bridging local MCP servers into function calling
+
+pub use mcp::{
+    CallToolResult, Error as McpError, HttpTransport, McpBridge, McpClient, McpContent, McpTool,
+    StdioTransport, Transport as McpTransport,
+};
+
+// ========== Computer Use ==========
+// Types for executing computer-use actions
+
+pub use computer_use::{
+    ActionExecutor, ComputerAction, ComputerUseDriver, ComputerUseRun, Error as ComputerUseError,
+    ExecutedAction, Observation, Point, SafetyDecision, SafetyDecisionKind, ScrollDirection,
+    StopReason, PREDEFINED_FUNCTIONS,
 };
 
 // ========== File Search ==========
@@ -182,7 +233,16 @@ pub use cache::{
 
 pub use file_search::{
     model::ChunkingConfig, model::CustomMetadata, model::CustomMetadataValue, model::Document,
-    model::DocumentState, model::FileSearchStore, model::Operation, model::OperationResult,
-    model::Status, model::StringList, model::WhiteSpaceConfig, DocumentBuilder, DocumentHandle,
-    FileSearchStoreBuilder, FileSearchStoreHandle, ImportBuilder, OperationHandle, UploadBuilder,
+    model::DocumentState, model::FileSearchStore, model::ImportFileResponse, model::Operation,
+    model::OperationMetadata, model::OperationResponse, model::OperationResult, model::Status,
+    model::StringList, model::UploadToFileSearchStoreResponse, model::WhiteSpaceConfig,
+    DocumentBuilder, DocumentHandle, FailedIngest, FileSearchStoreBuilder, FileSearchStoreHandle,
+    ImportBuilder, IngestBuilder, IngestFile, IngestReport, IngestedDocument, MetadataPatch,
+    OperationCounts, OperationHandle, OperationOutcome, OperationProgress, OperationSet,
+    QueryBuilder, RetrievedChunk, SkipReason, SkippedFile, StoreStats, SyncAction, SyncBuilder,
+    SyncChange, SyncPlan, SyncReport, UploadBuilder,
+};
+pub use file_search::{
+    Comparison as MetadataComparison, FilterError as MetadataFilterError, FilterValue,
+    MetadataFilter, MetadataKind, MetadataSchema,
 };
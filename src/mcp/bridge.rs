@@ -0,0 +1,244 @@
+use async_trait::async_trait;
+use serde_json::Value;
+use std::sync::Arc;
+
+use crate::interactions::model::{ImageMimeType, InteractionTool, StepResult, StepResultContent};
+use crate::interactions::FunctionHandler;
+use crate::mcp::model::{CallToolResult, EmbeddedResource, McpContent, McpTool};
+use crate::mcp::{DuplicateToolSnafu, Error, McpClient, UnknownToolSnafu};
+use crate::tools::{FunctionCall, FunctionDeclaration};
+
+/// A tool of a connected server, under the function name the model sees.
+#[derive(Debug, Clone)]
+struct BridgedTool {
+    function_name: String,
+    server: usize,
+    tool: McpTool,
+}
+
+/// Exposes the tools of MCP servers as function tools.
+///
+/// Register the bridge with
+/// [`InteractionBuilder::with_function_handler`](crate::interactions::InteractionBuilder::with_function_handler)
+/// and the model's calls are routed to the servers by
+/// [`execute_with_functions`](crate::interactions::InteractionBuilder::execute_with_functions).
+/// Cloning the bridge shares the server connections.
+#[derive(Clone, Default)]
+pub struct McpBridge {
+    servers: Vec<(String, Arc<McpClient>)>,
+    tools: Vec<BridgedTool>,
+    prefix: bool,
+}
+
+impl McpBridge {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Names functions `{server}__{tool}` instead of after the tool alone, so servers
+    /// offering tools with the same name can be added side by side.
+    ///
+    /// Only affects servers added afterwards.
+    pub fn with_server_prefix(mut self) -> Self {
+        self.prefix = true;
+        self
+    }
+
+    /// Lists the tools of a server and adds them to the bridge.
+    ///
+    /// Fails with [`Error::DuplicateTool`] when a tool maps to the name of a function
+    /// already on the bridge.
+    pub async fn add_server(
+        mut self,
+        name: impl Into<String>,
+        client: McpClient,
+    ) -> Result<Self, Error> {
+        let name = name.into();
+        let tools = client.list_tools().await?;
+        let server = self.servers.len();
+        for tool in tools {
+            let function_name = if self.prefix {
+                function_name(&format!("{name}__{}", tool.name))
+            } else {
+                function_name(&tool.name)
+            };
+            if self.tools.iter().any(|t| t.function_name == function_name) {
+                return DuplicateToolSnafu {
+                    name: function_name,
+                }
+                .fail();
+            }
+            self.tools.push(BridgedTool {
+                function_name,
+                server,
+                tool,
+            });
+        }
+        self.servers.push((name, Arc::new(client)));
+        Ok(self)
+    }
+
+    /// The function names on the bridge, with the server and tool each one calls.
+    pub fn functions(&self) -> impl Iterator<Item = (&str, &str, &McpTool)> {
+        self.tools.iter().map(|t| {
+            (
+                t.function_name.as_str(),
+                self.servers[t.server].0.as_str(),
+                &t.tool,
+            )
+        })
+    }
+
+    /// The tools as function declarations, for
+    /// [`ContentBuilder`](crate::generation::ContentBuilder) requests.
+    pub fn function_declarations(&self) -> Vec<FunctionDeclaration> {
+        self.tools
+            .iter()
+            .map(|t| FunctionDeclaration {
+                parameters_json_schema: Some(parameters(&t.tool)),
+                ..FunctionDeclaration::new(&t.function_name, description(&t.tool), None)
+            })
+            .collect()
+    }
+
+    /// Calls the tool behind a function.
+    pub async fn call(
+        &self,
+        function_name: &str,
+        arguments: Value,
+    ) -> Result<CallToolResult, Error> {
+        let tool = self
+            .tools
+            .iter()
+            .find(|t| t.function_name == function_name)
+            .ok_or_else(|| {
+                UnknownToolSnafu {
+                    name: function_name,
+                }
+                .build()
+            })?;
+        self.servers[tool.server]
+            .1
+            .call_tool(&tool.tool.name, arguments)
+            .await
+    }
+
+    /// Calls the tool behind a function call of a `generateContent` response.
+    pub async fn call_function(&self, call: &FunctionCall) -> Result<CallToolResult, Error> {
+        self.call(&call.name, call.args.clone()).await
+    }
+}
+
+#[async_trait]
+impl FunctionHandler for McpBridge {
+    fn tools(&self) -> Vec<InteractionTool> {
+        self.tools
+            .iter()
+            .map(|t| {
+                InteractionTool::function(
+                    &t.function_name,
+                    description(&t.tool),
+                    parameters(&t.tool),
+                )
+            })
+            .collect()
+    }
+
+    fn handles(&self, name: &str) -> bool {
+        self.tools.iter().any(|t| t.function_name == name)
+    }
+
+    async fn call(&self, name: &str, arguments: Value) -> Result<StepResult, String> {
+        let result = McpBridge::call(self, name, arguments)
+            .await
+            .map_err(|e| e.to_string())?;
+        if result.is_error {
+            return Err(result.text());
+        }
+        Ok(step_result(result))
+    }
+}
+
+/// A function name the API accepts: letters, digits, `_`, `.` and `-`, at most 64 long.
+fn function_name(name: &str) -> String {
+    name.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | '-') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .take(64)
+        .collect()
+}
+
+fn description(tool: &McpTool) -> String {
+    tool.description
+        .clone()
+        .or_else(|| tool.title.clone())
+        .unwrap_or_default()
+}
+
+/// The input schema without the `$schema` keyword, which the API rejects.
+fn parameters(tool: &McpTool) -> Value {
+    let mut schema = tool.input_schema.clone();
+    if let Value::Object(map) = &mut schema {
+        map.remove("$schema");
+    }
+    schema
+}
+
+/// Converts a tool result into a function result.
+///
+/// Structured content is sent as an object and text-only results as a string. Images are
+/// passed on as images; other content the API cannot take is described in text.
+pub(crate) fn step_result(result: CallToolResult) -> StepResult {
+    if let Some(structured) = result.structured_content {
+        return StepResult::Object(structured);
+    }
+    if result
+        .content
+        .iter()
+        .all(|content| matches!(content, McpContent::Text { .. }))
+    {
+        return StepResult::String(result.text());
+    }
+
+    let image = |data: String, mime_type: &str| {
+        serde_json::from_value::<ImageMimeType>(Value::String(mime_type.to_string()))
+            .ok()
+            .map(|mime_type| StepResultContent::Image {
+                data: Some(data),
+                uri: None,
+                mime_type: Some(mime_type),
+            })
+    };
+    let text = |text: String| StepResultContent::Text { text };
+    let items = result
+        .content
+        .into_iter()
+        .map(|content| match content {
+            McpContent::Text { text: t } => text(t),
+            McpContent::Image { data, mime_type } => image(data, &mime_type)
+                .unwrap_or_else(|| text(format!("[{mime_type} image omitted]"))),
+            McpContent::Audio { mime_type, .. } => text(format!("[{mime_type} audio omitted]")),
+            McpContent::ResourceLink { uri, name, .. } => text(format!("[{name}]({uri})")),
+            McpContent::Resource {
+                resource:
+                    EmbeddedResource {
+                        uri,
+                        mime_type,
+                        text: resource_text,
+                        blob,
+                    },
+            } => match (resource_text, blob, mime_type) {
+                (Some(t), _, _) => text(t),
+                (None, Some(blob), Some(mime_type)) => image(blob, &mime_type)
+                    .unwrap_or_else(|| text(format!("[{mime_type} resource {uri} omitted]"))),
+                _ => text(format!("[resource {uri} omitted]")),
+            },
+        })
+        .collect();
+    StepResult::ContentArray(items)
+}
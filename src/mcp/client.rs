@@ -0,0 +1,123 @@
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value};
+use snafu::ResultExt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use tokio::process::Command;
+use url::Url;
+
+use crate::mcp::model::{
+    CallToolResult, InitializeResult, JsonRpcRequest, ListToolsResult, McpTool, PROTOCOL_VERSION,
+};
+use crate::mcp::transport::{HttpTransport, StdioTransport, Transport};
+use crate::mcp::{Error, InvalidResultSnafu, RpcSnafu};
+
+/// A connection to an MCP server.
+pub struct McpClient {
+    transport: Box<dyn Transport>,
+    next_id: AtomicU64,
+    server: InitializeResult,
+}
+
+impl McpClient {
+    /// Initializes a session over the given transport.
+    pub async fn connect(transport: impl Transport + 'static) -> Result<Self, Error> {
+        let mut client = Self {
+            transport: Box::new(transport),
+            next_id: AtomicU64::new(1),
+            server: InitializeResult {
+                protocol_version: PROTOCOL_VERSION.to_string(),
+                capabilities: Value::Null,
+                server_info: Default::default(),
+                instructions: None,
+            },
+        };
+        client.server = client
+            .request(
+                "initialize",
+                json!({
+                    "protocolVersion": PROTOCOL_VERSION,
+                    "capabilities": {},
+                    "clientInfo": {
+                        "name": env!("CARGO_PKG_NAME"),
+                        "version": env!("CARGO_PKG_VERSION"),
+                    },
+                }),
+            )
+            .await?;
+        client
+            .transport
+            .notify(JsonRpcRequest::notification(
+                "notifications/initialized",
+                None,
+            ))
+            .await?;
+        Ok(client)
+    }
+
+    /// Launches a server process and connects to it over stdio.
+    ///
+    /// See [`StdioTransport::launch`].
+    pub async fn launch(command: Command) -> Result<Self, Error> {
+        Self::connect(StdioTransport::launch(command)?).await
+    }
+
+    /// Connects to a server over streamable HTTP.
+    ///
+    /// Use [`HttpTransport`] with [`connect`](Self::connect) to send extra headers.
+    pub async fn connect_http(url: Url) -> Result<Self, Error> {
+        Self::connect(HttpTransport::new(url)).await
+    }
+
+    /// What the server reported about itself when the session was initialized.
+    pub fn server_info(&self) -> &InitializeResult {
+        &self.server
+    }
+
+    /// Lists every tool the server offers, following pagination.
+    pub async fn list_tools(&self) -> Result<Vec<McpTool>, Error> {
+        let mut tools = Vec::new();
+        let mut cursor: Option<String> = None;
+        loop {
+            let params = match &cursor {
+                Some(cursor) => json!({ "cursor": cursor }),
+                None => json!({}),
+            };
+            let page: ListToolsResult = self.request("tools/list", params).await?;
+            tools.extend(page.tools);
+            match page.next_cursor {
+                Some(next) if !next.is_empty() => cursor = Some(next),
+                _ => return Ok(tools),
+            }
+        }
+    }
+
+    /// Calls a tool.
+    ///
+    /// A tool that fails returns a result with [`is_error`](CallToolResult::is_error) set;
+    /// only protocol and transport failures are errors.
+    pub async fn call_tool(&self, name: &str, arguments: Value) -> Result<CallToolResult, Error> {
+        self.request(
+            "tools/call",
+            json!({ "name": name, "arguments": arguments }),
+        )
+        .await
+    }
+
+    async fn request<T: DeserializeOwned>(&self, method: &str, params: Value) -> Result<T, Error> {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let response = self
+            .transport
+            .request(JsonRpcRequest::new(id, method, Some(params)))
+            .await?;
+        if let Some(error) = response.error {
+            return RpcSnafu {
+                method,
+                code: error.code,
+                message: error.message,
+            }
+            .fail();
+        }
+        serde_json::from_value(response.result.unwrap_or(Value::Null))
+            .context(InvalidResultSnafu { method })
+    }
+}
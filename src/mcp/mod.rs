@@ -0,0 +1,100 @@
+//! Bridging local MCP servers into function calling.
+//!
+//! [`InteractionTool::McpServer`](crate::interactions::InteractionTool::McpServer) lets the
+//! API call MCP servers it can reach over the network. Servers that only run locally are
+//! connected with an [`McpClient`] instead — over stdio with a [`StdioTransport`] or over
+//! streamable HTTP with an [`HttpTransport`] — and their tools are exposed to the model as
+//! function tools by an [`McpBridge`].
+//!
+//! # Example
+//!
+//! ```no_run
+//! # use gemini_rust::prelude::*;
+//! # use gemini_rust::{McpBridge, McpClient};
+//! # async fn example(gemini: &Gemini) -> Result<(), Box<dyn std::error::Error>> {
+//! let mut command = tokio::process::Command::new("npx");
+//! command.args(["-y", "@modelcontextprotocol/server-filesystem", "."]);
+//! let bridge = McpBridge::new()
+//!     .add_server("files", McpClient::launch(command).await?)
+//!     .await?;
+//!
+//! let interaction = gemini
+//!     .create_interaction()
+//!     .with_model("gemini-2.5-flash")
+//!     .with_text("Summarize the README in this directory.")
+//!     .with_function_handler(bridge)
+//!     .execute_with_functions()
+//!     .await?;
+//!
+//! println!("{}", interaction.output_text());
+//! # Ok(())
+//! # }
+//! ```
+
+use eventsource_stream::EventStreamError;
+use snafu::Snafu;
+
+pub mod bridge;
+pub mod client;
+pub mod model;
+pub mod transport;
+
+pub use bridge::McpBridge;
+pub use client::McpClient;
+pub use model::{CallToolResult, InitializeResult, McpContent, McpTool};
+pub use transport::{HttpTransport, StdioTransport, Transport};
+
+#[derive(Debug, Snafu)]
+#[snafu(visibility(pub(crate)))]
+pub enum Error {
+    #[snafu(display("failed to launch MCP server '{program}'"))]
+    Spawn {
+        source: std::io::Error,
+        program: String,
+    },
+
+    #[snafu(display("failed to talk to MCP server"))]
+    Io { source: std::io::Error },
+
+    #[snafu(display("MCP server closed the connection"))]
+    Closed,
+
+    #[snafu(display("invalid JSON-RPC message"))]
+    Message { source: serde_json::Error },
+
+    #[snafu(display("failed to reach MCP server"))]
+    Http { source: reqwest::Error },
+
+    #[snafu(display(
+        "bad response from MCP server; code {code}; description: {}",
+        description.as_deref().unwrap_or("none")
+    ))]
+    BadResponse {
+        code: u16,
+        description: Option<String>,
+    },
+
+    #[snafu(display("failed to read MCP server event stream"))]
+    Stream {
+        source: EventStreamError<reqwest::Error>,
+    },
+
+    #[snafu(display("MCP request '{method}' failed with code {code}: {message}"))]
+    Rpc {
+        method: String,
+        code: i64,
+        message: String,
+    },
+
+    #[snafu(display("unexpected result for MCP request '{method}'"))]
+    InvalidResult {
+        source: serde_json::Error,
+        method: String,
+    },
+
+    #[snafu(display("no MCP tool is bridged as function '{name}'"))]
+    UnknownTool { name: String },
+
+    #[snafu(display("function '{name}' is already bridged to another MCP tool"))]
+    DuplicateTool { name: String },
+}
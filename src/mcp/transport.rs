@@ -0,0 +1,232 @@
+use async_trait::async_trait;
+use eventsource_stream::Eventsource;
+use futures::StreamExt;
+use reqwest::header::{HeaderMap, HeaderValue, ACCEPT};
+use snafu::ResultExt;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::process::{Child, Command};
+use tokio::sync::Mutex;
+use url::Url;
+
+use crate::mcp::model::{JsonRpcError, JsonRpcMessage, JsonRpcRequest, PROTOCOL_VERSION};
+use crate::mcp::{
+    BadResponseSnafu, ClosedSnafu, Error, HttpSnafu, IoSnafu, MessageSnafu, SpawnSnafu, StreamSnafu,
+};
+
+const SESSION_HEADER: &str = "mcp-session-id";
+const PROTOCOL_HEADER: &str = "mcp-protocol-version";
+
+/// Carries JSON-RPC messages between an [`McpClient`](crate::mcp::McpClient) and a server.
+#[async_trait]
+pub trait Transport: Send + Sync {
+    /// Sends a request and waits for the response to it.
+    async fn request(&self, request: JsonRpcRequest) -> Result<JsonRpcMessage, Error>;
+
+    /// Sends a notification.
+    async fn notify(&self, notification: JsonRpcRequest) -> Result<(), Error>;
+}
+
+type Reader = BufReader<Box<dyn AsyncRead + Send + Unpin>>;
+type Writer = Box<dyn AsyncWrite + Send + Unpin>;
+
+/// Talks to a server over newline-delimited JSON on a pair of streams, usually the
+/// standard input and output of a child process.
+///
+/// Requests are sent one at a time. Pings from the server are answered, other server
+/// requests are refused and server notifications are ignored.
+pub struct StdioTransport {
+    streams: Mutex<(Reader, Writer)>,
+    /// Kept so the server is killed when the transport is dropped.
+    _child: Option<Child>,
+}
+
+impl StdioTransport {
+    /// Uses the given streams, e.g. one end of a [`tokio::io::duplex`] for an in-process server.
+    pub fn new(
+        reader: impl AsyncRead + Send + Unpin + 'static,
+        writer: impl AsyncWrite + Send + Unpin + 'static,
+    ) -> Self {
+        Self {
+            streams: Mutex::new((BufReader::new(Box::new(reader)), Box::new(writer))),
+            _child: None,
+        }
+    }
+
+    /// Launches a server process and talks to it over its standard input and output.
+    ///
+    /// The process inherits standard error, and is killed when the transport is dropped.
+    pub fn launch(mut command: Command) -> Result<Self, Error> {
+        let program = command
+            .as_std()
+            .get_program()
+            .to_string_lossy()
+            .into_owned();
+        let mut child = command
+            .stdin(std::process::Stdio::piped())
+            .stdout(std::process::Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .context(SpawnSnafu { program })?;
+        let stdin = child.stdin.take().expect("stdin is piped");
+        let stdout = child.stdout.take().expect("stdout is piped");
+        Ok(Self {
+            _child: Some(child),
+            ..Self::new(stdout, stdin)
+        })
+    }
+
+    async fn write(writer: &mut Writer, message: &impl serde::Serialize) -> Result<(), Error> {
+        let mut line = serde_json::to_vec(message).context(MessageSnafu)?;
+        line.push(b'\n');
+        writer.write_all(&line).await.context(IoSnafu)?;
+        writer.flush().await.context(IoSnafu)
+    }
+}
+
+#[async_trait]
+impl Transport for StdioTransport {
+    async fn request(&self, request: JsonRpcRequest) -> Result<JsonRpcMessage, Error> {
+        let id = request.id.unwrap_or_default();
+        let mut streams = self.streams.lock().await;
+        let (reader, writer) = &mut *streams;
+        Self::write(writer, &request).await?;
+
+        let mut line = String::new();
+        loop {
+            line.clear();
+            if reader.read_line(&mut line).await.context(IoSnafu)? == 0 {
+                return ClosedSnafu.fail();
+            }
+            if line.trim().is_empty() {
+                continue;
+            }
+            let message: JsonRpcMessage = serde_json::from_str(&line).context(MessageSnafu)?;
+            if message.is_response_to(id) {
+                return Ok(message);
+            }
+            if let (Some(method), Some(server_id)) = (&message.method, &message.id) {
+                let reply = match method.as_str() {
+                    "ping" => serde_json::json!({"jsonrpc": "2.0", "id": server_id, "result": {}}),
+                    _ => serde_json::json!({
+                        "jsonrpc": "2.0",
+                        "id": server_id,
+                        "error": JsonRpcError {
+                            code: -32601,
+                            message: format!("method not supported: {method}"),
+                            data: None,
+                        },
+                    }),
+                };
+                Self::write(writer, &reply).await?;
+            } else {
+                tracing::debug!(message = line.trim(), "ignoring MCP server message");
+            }
+        }
+    }
+
+    async fn notify(&self, notification: JsonRpcRequest) -> Result<(), Error> {
+        let mut streams = self.streams.lock().await;
+        Self::write(&mut streams.1, &notification).await
+    }
+}
+
+/// Talks to a server over the streamable HTTP transport.
+///
+/// Every message is POSTed to the endpoint; responses may come back as JSON or as an
+/// event stream. The session id the server assigns on initialization is sent with every
+/// later message.
+pub struct HttpTransport {
+    http_client: reqwest::Client,
+    url: Url,
+    headers: HeaderMap,
+    session: std::sync::Mutex<Option<HeaderValue>>,
+}
+
+impl HttpTransport {
+    pub fn new(url: Url) -> Self {
+        Self {
+            http_client: reqwest::Client::new(),
+            url,
+            headers: HeaderMap::new(),
+            session: std::sync::Mutex::new(None),
+        }
+    }
+
+    /// Sends the given headers, e.g. `Authorization`, with every message.
+    pub fn with_headers(mut self, headers: HeaderMap) -> Self {
+        self.headers.extend(headers);
+        self
+    }
+
+    /// Uses a custom HTTP client, e.g. one with a timeout or a proxy.
+    pub fn with_http_client(mut self, http_client: reqwest::Client) -> Self {
+        self.http_client = http_client;
+        self
+    }
+
+    async fn post(&self, message: &JsonRpcRequest) -> Result<reqwest::Response, Error> {
+        let mut request = self
+            .http_client
+            .post(self.url.clone())
+            .headers(self.headers.clone())
+            .header(ACCEPT, "application/json, text/event-stream")
+            .json(message);
+        if message.method != "initialize" {
+            request = request.header(PROTOCOL_HEADER, PROTOCOL_VERSION);
+        }
+        let session = self.session.lock().expect("session lock poisoned").clone();
+        if let Some(session) = session {
+            request = request.header(SESSION_HEADER, session);
+        }
+
+        let response = request.send().await.context(HttpSnafu)?;
+        let status = response.status();
+        if !status.is_success() {
+            let description = response.text().await.ok();
+            return BadResponseSnafu {
+                code: status.as_u16(),
+                description,
+            }
+            .fail();
+        }
+        if let Some(session) = response.headers().get(SESSION_HEADER) {
+            *self.session.lock().expect("session lock poisoned") = Some(session.clone());
+        }
+        Ok(response)
+    }
+}
+
+#[async_trait]
+impl Transport for HttpTransport {
+    async fn request(&self, request: JsonRpcRequest) -> Result<JsonRpcMessage, Error> {
+        let id = request.id.unwrap_or_default();
+        let response = self.post(&request).await?;
+        let is_stream = response
+            .headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .is_some_and(|value| value.starts_with("text/event-stream"));
+        if !is_stream {
+            let body = response.bytes().await.context(HttpSnafu)?;
+            return serde_json::from_slice(&body).context(MessageSnafu);
+        }
+
+        let mut events = response.bytes_stream().eventsource();
+        while let Some(event) = events.next().await {
+            let event = event.context(StreamSnafu)?;
+            if event.data.trim().is_empty() {
+                continue;
+            }
+            let message: JsonRpcMessage =
+                serde_json::from_str(&event.data).context(MessageSnafu)?;
+            if message.is_response_to(id) {
+                return Ok(message);
+            }
+        }
+        ClosedSnafu.fail()
+    }
+
+    async fn notify(&self, notification: JsonRpcRequest) -> Result<(), Error> {
+        self.post(&notification).await.map(drop)
+    }
+}
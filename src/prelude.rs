@@ -30,7 +30,7 @@ pub use crate::{ContentEmbeddingResponse, CountTokensResponse, GenerationRespons
 pub use crate::{GenerationConfig, TaskType};
 
 // Safety settings
-pub use crate::{HarmBlockThreshold, HarmCategory, SafetySetting};
+pub use crate::{HarmBlockThreshold, HarmCategory, SafetyPolicy, SafetySetting};
 
 // Function calling
 pub use crate::{FunctionDeclaration, FunctionResponse, Tool};
@@ -0,0 +1,556 @@
+//! Local content moderation applied before requests leave the process and after responses
+//! arrive.
+//!
+//! A [`ContentFilter`] inspects every text part and every inline data part of a request
+//! registered with `with_content_filter` on
+//! [`ContentBuilder`](crate::generation::ContentBuilder::with_content_filter) or
+//! [`InteractionBuilder`](crate::interactions::InteractionBuilder::with_content_filter), and
+//! every text part of the model output when registered with `with_output_filter`. For each
+//! part it returns a [`FilterAction`]:
+//!
+//! - [`Allow`](FilterAction::Allow) leaves the part untouched;
+//! - [`Redact`](FilterAction::Redact) replaces the text (inline data parts are removed);
+//! - [`Reject`](FilterAction::Reject) stops the request with
+//!   [`ClientError::ContentRejected`] — for outbound content, nothing is sent;
+//! - [`Tag`](FilterAction::Tag) lets the part through and reports the tag, so flagged
+//!   traffic can be audited.
+//!
+//! Tags are logged in a `tracing` event and, for requests sent with `execute`, returned in
+//! the `filter_tags` of the [`GenerationResponse`] or [`Interaction`], covering both the
+//! request and the response. Tags raised while streaming, building a request or batching it
+//! are only logged.
+//!
+//! Filters run in the order they were added; a redaction is visible to the filters after it.
+//! [`RegexFilter`] provides detectors for email addresses, phone numbers and credit card
+//! numbers.
+//!
+//! Function results and thought summaries count as text: every string in a JSON function
+//! response is filtered on its own. Function call arguments and URIs of uploaded files are
+//! not inspected. Output filters are not applied to streaming responses.
+
+#![allow(deprecated)]
+
+use regex::Regex;
+use serde::Serialize;
+use std::{fmt, sync::Arc};
+
+use crate::client::Error as ClientError;
+use crate::generation::model::{GenerateContentRequest, GenerationResponse};
+use crate::interactions::model::{
+    CreateInteractionRequest, Interaction, InteractionContent, InteractionInput, Step, StepResult,
+    StepResultContent, ThoughtSummaryContent,
+};
+use crate::{Content, Part};
+
+/// What a [`ContentFilter`] decided about a part.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FilterAction {
+    /// Leave the part as it is.
+    Allow,
+    /// Replace the part's text with the given text. Inline data parts are removed.
+    Redact(String),
+    /// Refuse the whole request or response.
+    Reject {
+        /// Why the content was refused.
+        reason: String,
+    },
+    /// Let the part through, reporting the tag.
+    Tag(String),
+}
+
+/// Whether content is being sent to the API or was received from it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FilterDirection {
+    /// Content about to be sent.
+    Outbound,
+    /// Model output that was received.
+    Inbound,
+}
+
+impl fmt::Display for FilterDirection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Outbound => write!(f, "outbound"),
+            Self::Inbound => write!(f, "inbound"),
+        }
+    }
+}
+
+/// A tag raised by a filter returning [`FilterAction::Tag`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FilterTag {
+    /// The tag returned by the filter.
+    pub tag: String,
+    /// Whether the tagged part was sent or received.
+    pub direction: FilterDirection,
+}
+
+/// A local moderation check run over text and inline data parts.
+pub trait ContentFilter: Send + Sync {
+    /// A name identifying the filter in errors and tags.
+    fn name(&self) -> &str {
+        std::any::type_name::<Self>()
+    }
+
+    /// Inspects a text part.
+    fn filter_text(&self, text: &str) -> FilterAction;
+
+    /// Inspects an inline data part, given its MIME type and base64-encoded data.
+    ///
+    /// Allows everything by default.
+    fn filter_inline_data(&self, _mime_type: &str, _data: &str) -> FilterAction {
+        FilterAction::Allow
+    }
+}
+
+/// What a [`RegexFilter`] does with a match.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum RegexMode {
+    Redact(String),
+    Reject,
+    Tag,
+}
+
+/// A [`ContentFilter`] that looks for a regular expression in text parts.
+///
+/// By default every match is replaced by `[REDACTED:<name>]`; use [`reject`](Self::reject)
+/// or [`tag`](Self::tag) to refuse or tag matching content instead.
+#[derive(Debug, Clone)]
+pub struct RegexFilter {
+    name: String,
+    regex: Regex,
+    /// Extra check on each match, to weed out false positives.
+    validate: Option<fn(&str) -> bool>,
+    mode: RegexMode,
+}
+
+impl RegexFilter {
+    /// Creates a filter named `name` matching `pattern`.
+    pub fn new(name: impl Into<String>, pattern: &str) -> Result<Self, regex::Error> {
+        let name = name.into();
+        Ok(Self {
+            regex: Regex::new(pattern)?,
+            validate: None,
+            mode: RegexMode::Redact(format!("[REDACTED:{name}]")),
+            name,
+        })
+    }
+
+    fn builtin(name: &str, pattern: &str) -> Self {
+        Self::new(name, pattern).expect("built-in pattern is valid")
+    }
+
+    /// Detects email addresses.
+    pub fn email() -> Self {
+        Self::builtin("email", r"[A-Za-z0-9._%+-]+@[A-Za-z0-9.-]+\.[A-Za-z]{2,}")
+    }
+
+    /// Detects phone numbers: international numbers starting with `+` and North American
+    /// style numbers such as `(555) 123-4567`.
+    pub fn phone_number() -> Self {
+        Self::builtin(
+            "phone_number",
+            r"(?:\+\d{1,3}[\s.-]?)?(?:\(\d{2,4}\)|\b\d{2,4})[\s.-]\d{3,4}[\s.-]\d{3,4}\b|\+\d{8,15}\b",
+        )
+    }
+
+    /// Detects payment card numbers of 13 to 19 digits, optionally grouped with spaces or
+    /// dashes, that pass the Luhn checksum.
+    pub fn credit_card() -> Self {
+        let mut filter = Self::builtin("credit_card", r"\b\d(?:[ -]?\d){12,18}\b");
+        filter.validate = Some(luhn_valid);
+        filter
+    }
+
+    /// Replaces every match with `replacement`.
+    pub fn redact_with(mut self, replacement: impl Into<String>) -> Self {
+        self.mode = RegexMode::Redact(replacement.into());
+        self
+    }
+
+    /// Rejects content containing a match.
+    pub fn reject(mut self) -> Self {
+        self.mode = RegexMode::Reject;
+        self
+    }
+
+    /// Tags content containing a match with the filter's name.
+    pub fn tag(mut self) -> Self {
+        self.mode = RegexMode::Tag;
+        self
+    }
+
+    fn is_match(&self, candidate: &str) -> bool {
+        self.validate.is_none_or(|validate| validate(candidate))
+    }
+}
+
+impl ContentFilter for RegexFilter {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn filter_text(&self, text: &str) -> FilterAction {
+        let found = self
+            .regex
+            .find_iter(text)
+            .any(|m| self.is_match(m.as_str()));
+        if !found {
+            return FilterAction::Allow;
+        }
+        match &self.mode {
+            RegexMode::Redact(replacement) => FilterAction::Redact(
+                self.regex
+                    .replace_all(text, |captures: &regex::Captures| {
+                        let matched = &captures[0];
+                        if self.is_match(matched) {
+                            replacement.clone()
+                        } else {
+                            matched.to_string()
+                        }
+                    })
+                    .into_owned(),
+            ),
+            RegexMode::Reject => FilterAction::Reject {
+                reason: format!("content contains {}", self.name),
+            },
+            RegexMode::Tag => FilterAction::Tag(self.name.clone()),
+        }
+    }
+}
+
+/// Checks the Luhn checksum of the digits in `candidate`.
+fn luhn_valid(candidate: &str) -> bool {
+    let digits: Vec<u32> = candidate.chars().filter_map(|c| c.to_digit(10)).collect();
+    let sum: u32 = digits
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(i, &d)| match (i % 2, d * 2) {
+            (0, _) => d,
+            (_, doubled) if doubled > 9 => doubled - 9,
+            (_, doubled) => doubled,
+        })
+        .sum();
+    sum.is_multiple_of(10)
+}
+
+/// The filters registered on a builder for one direction.
+#[derive(Clone, Default)]
+pub(crate) struct FilterChain {
+    filters: Vec<Arc<dyn ContentFilter>>,
+}
+
+impl FilterChain {
+    pub(crate) fn push(&mut self, filter: Arc<dyn ContentFilter>) {
+        self.filters.push(filter);
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.filters.is_empty()
+    }
+
+    /// Runs the filters over a text part, redacting it in place.
+    fn text(
+        &self,
+        text: &mut String,
+        direction: FilterDirection,
+        tags: &mut Vec<String>,
+    ) -> Result<(), ClientError> {
+        for filter in &self.filters {
+            match filter.filter_text(text) {
+                FilterAction::Allow => {}
+                FilterAction::Redact(redacted) => *text = redacted,
+                FilterAction::Reject { reason } => {
+                    return Err(rejected(filter.as_ref(), reason, direction))
+                }
+                FilterAction::Tag(tag) => tags.push(tag),
+            }
+        }
+        Ok(())
+    }
+
+    /// Runs the filters over an inline data part, returning whether to keep it.
+    fn inline_data(
+        &self,
+        mime_type: &str,
+        data: &str,
+        direction: FilterDirection,
+        tags: &mut Vec<String>,
+    ) -> Result<bool, ClientError> {
+        for filter in &self.filters {
+            match filter.filter_inline_data(mime_type, data) {
+                FilterAction::Allow => {}
+                FilterAction::Redact(_) => return Ok(false),
+                FilterAction::Reject { reason } => {
+                    return Err(rejected(filter.as_ref(), reason, direction))
+                }
+                FilterAction::Tag(tag) => tags.push(tag),
+            }
+        }
+        Ok(true)
+    }
+
+    fn parts(
+        &self,
+        content: &mut Content,
+        direction: FilterDirection,
+        tags: &mut Vec<String>,
+    ) -> Result<(), ClientError> {
+        let Some(parts) = content.parts.take() else {
+            return Ok(());
+        };
+        let mut kept = Vec::with_capacity(parts.len());
+        for mut part in parts {
+            let keep = match &mut part {
+                Part::Text { text, .. } => {
+                    self.text(text, direction, tags)?;
+                    true
+                }
+                Part::InlineData { inline_data, .. } => {
+                    self.inline_data(&inline_data.mime_type, &inline_data.data, direction, tags)?
+                }
+                Part::FunctionResponse {
+                    function_response, ..
+                } => {
+                    if let Some(response) = &mut function_response.response {
+                        self.json(response, direction, tags)?;
+                    }
+                    true
+                }
+                _ => true,
+            };
+            if keep {
+                kept.push(part);
+            }
+        }
+        content.parts = Some(kept);
+        Ok(())
+    }
+
+    fn interaction_contents(
+        &self,
+        contents: &mut Vec<InteractionContent>,
+        direction: FilterDirection,
+        tags: &mut Vec<String>,
+    ) -> Result<(), ClientError> {
+        let mut kept = Vec::with_capacity(contents.len());
+        for mut content in contents.drain(..) {
+            if self.interaction_content(&mut content, direction, tags)? {
+                kept.push(content);
+            }
+        }
+        *contents = kept;
+        Ok(())
+    }
+
+    /// Filters one content item, returning whether to keep it.
+    fn interaction_content(
+        &self,
+        content: &mut InteractionContent,
+        direction: FilterDirection,
+        tags: &mut Vec<String>,
+    ) -> Result<bool, ClientError> {
+        let (data, mime_type) = match content {
+            InteractionContent::Text { text, .. } => {
+                self.text(text, direction, tags)?;
+                return Ok(true);
+            }
+            InteractionContent::Image {
+                data, mime_type, ..
+            } => (data.as_deref(), mime_type.as_ref().map(mime_name)),
+            InteractionContent::Audio {
+                data, mime_type, ..
+            } => (data.as_deref(), mime_type.as_ref().map(mime_name)),
+            InteractionContent::Document {
+                data, mime_type, ..
+            } => (data.as_deref(), mime_type.as_ref().map(mime_name)),
+            InteractionContent::Video {
+                data, mime_type, ..
+            } => (data.as_deref(), mime_type.as_ref().map(mime_name)),
+        };
+        match data {
+            Some(data) => self.inline_data(
+                mime_type.as_deref().unwrap_or_default(),
+                data,
+                direction,
+                tags,
+            ),
+            None => Ok(true),
+        }
+    }
+
+    /// Filters the text of a step: inputs, outputs, thought summaries and function results.
+    fn step(
+        &self,
+        step: &mut Step,
+        direction: FilterDirection,
+        tags: &mut Vec<String>,
+    ) -> Result<(), ClientError> {
+        match step {
+            Step::UserInput { content } | Step::ModelOutput { content, .. } => {
+                self.interaction_contents(content, direction, tags)
+            }
+            Step::Thought { summary, .. } => {
+                for ThoughtSummaryContent::Text { text } in summary {
+                    self.text(text, direction, tags)?;
+                }
+                Ok(())
+            }
+            Step::FunctionResult { result, .. } => match result {
+                StepResult::String(text) => self.text(text, direction, tags),
+                StepResult::Object(value) => self.json(value, direction, tags),
+                StepResult::ContentArray(contents) => {
+                    let mut kept = Vec::with_capacity(contents.len());
+                    for mut content in contents.drain(..) {
+                        let keep = match &mut content {
+                            StepResultContent::Text { text } => {
+                                self.text(text, direction, tags)?;
+                                true
+                            }
+                            StepResultContent::Image {
+                                data: Some(data),
+                                mime_type,
+                                ..
+                            } => self.inline_data(
+                                &mime_type.as_ref().map(mime_name).unwrap_or_default(),
+                                data,
+                                direction,
+                                tags,
+                            )?,
+                            StepResultContent::Image { .. } => true,
+                        };
+                        if keep {
+                            kept.push(content);
+                        }
+                    }
+                    *contents = kept;
+                    Ok(())
+                }
+            },
+            _ => Ok(()),
+        }
+    }
+
+    /// Runs the text filters over every string in a JSON value.
+    fn json(
+        &self,
+        value: &mut serde_json::Value,
+        direction: FilterDirection,
+        tags: &mut Vec<String>,
+    ) -> Result<(), ClientError> {
+        match value {
+            serde_json::Value::String(text) => self.text(text, direction, tags),
+            serde_json::Value::Array(values) => values
+                .iter_mut()
+                .try_for_each(|value| self.json(value, direction, tags)),
+            serde_json::Value::Object(map) => map
+                .values_mut()
+                .try_for_each(|value| self.json(value, direction, tags)),
+            _ => Ok(()),
+        }
+    }
+
+    /// Filters the contents and system instruction of an outbound request, returning the tags
+    /// raised.
+    pub(crate) fn filter_request(
+        &self,
+        request: &mut GenerateContentRequest,
+    ) -> Result<Vec<FilterTag>, ClientError> {
+        let mut tags = Vec::new();
+        let direction = FilterDirection::Outbound;
+        if let Some(instruction) = &mut request.system_instruction {
+            self.parts(instruction, direction, &mut tags)?;
+        }
+        for content in &mut request.contents {
+            self.parts(content, direction, &mut tags)?;
+        }
+        Ok(report(direction, tags))
+    }
+
+    /// Filters the candidates of a response, adding the tags raised to its `filter_tags`.
+    pub(crate) fn filter_response(
+        &self,
+        response: &mut GenerationResponse,
+    ) -> Result<(), ClientError> {
+        let mut tags = Vec::new();
+        let direction = FilterDirection::Inbound;
+        for candidate in &mut response.candidates {
+            self.parts(&mut candidate.content, direction, &mut tags)?;
+        }
+        response.filter_tags.extend(report(direction, tags));
+        Ok(())
+    }
+
+    /// Filters the input and system instruction of an outbound interaction, returning the
+    /// tags raised.
+    pub(crate) fn filter_interaction_request(
+        &self,
+        request: &mut CreateInteractionRequest,
+    ) -> Result<Vec<FilterTag>, ClientError> {
+        let mut tags = Vec::new();
+        let direction = FilterDirection::Outbound;
+        if let Some(instruction) = &mut request.system_instruction {
+            self.text(instruction, direction, &mut tags)?;
+        }
+        match &mut request.input {
+            InteractionInput::Text(text) => self.text(text, direction, &mut tags)?,
+            InteractionInput::Content(content) => {
+                if !self.interaction_content(content, direction, &mut tags)? {
+                    request.input = InteractionInput::ContentArray(vec![]);
+                }
+            }
+            InteractionInput::ContentArray(contents) => {
+                self.interaction_contents(contents, direction, &mut tags)?
+            }
+            InteractionInput::StepArray(steps) => {
+                for step in steps {
+                    self.step(step, direction, &mut tags)?;
+                }
+            }
+        }
+        Ok(report(direction, tags))
+    }
+
+    /// Filters the model output of an interaction, adding the tags raised to its
+    /// `filter_tags`.
+    pub(crate) fn filter_interaction(
+        &self,
+        interaction: &mut Interaction,
+    ) -> Result<(), ClientError> {
+        let mut tags = Vec::new();
+        let direction = FilterDirection::Inbound;
+        for step in &mut interaction.steps {
+            if let Step::ModelOutput { .. } | Step::Thought { .. } = step {
+                self.step(step, direction, &mut tags)?;
+            }
+        }
+        interaction.filter_tags.extend(report(direction, tags));
+        Ok(())
+    }
+}
+
+fn rejected(filter: &dyn ContentFilter, reason: String, direction: FilterDirection) -> ClientError {
+    ClientError::ContentRejected {
+        filter: filter.name().to_string(),
+        reason,
+        direction,
+    }
+}
+
+/// Logs the tags raised in one direction and pairs each with the direction.
+fn report(direction: FilterDirection, tags: Vec<String>) -> Vec<FilterTag> {
+    if !tags.is_empty() {
+        tracing::info!(%direction, tags = ?tags, "content filter tagged content");
+    }
+    tags.into_iter()
+        .map(|tag| FilterTag { tag, direction })
+        .collect()
+}
+
+fn mime_name<T: Serialize>(mime_type: &T) -> String {
+    serde_json::to_value(mime_type)
+        .ok()
+        .and_then(|v| v.as_str().map(str::to_string))
+        .unwrap_or_default()
+}
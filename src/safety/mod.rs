@@ -1,2 +1,17 @@
+use snafu::Snafu;
+
+pub mod filter;
 pub mod model;
+pub mod outcome;
+pub mod policy;
+pub use filter::{ContentFilter, FilterAction, FilterDirection, FilterTag, RegexFilter};
 pub use model::*;
+pub use outcome::SafetyOutcome;
+pub use policy::{validate_settings, SafetyPolicy};
+
+#[derive(Debug, Snafu)]
+#[snafu(visibility(pub(crate)))]
+pub enum Error {
+    #[snafu(display("harm category {category:?} is not supported by Gemini models"))]
+    UnsupportedCategory { category: HarmCategory },
+}
@@ -1,7 +1,7 @@
 use serde::{Deserialize, Serialize};
 
 /// Setting for safety
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct SafetySetting {
     /// The category of content to filter
     pub category: HarmCategory,
@@ -10,7 +10,7 @@ pub struct SafetySetting {
 }
 
 /// Category of harmful content
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
 pub enum HarmCategory {
     /// Category is unspecified.
     #[serde(rename = "HARM_CATEGORY_UNSPECIFIED")]
@@ -52,7 +52,7 @@ pub enum HarmCategory {
 
 /// Threshold for blocking harmful content
 #[allow(clippy::enum_variant_names)]
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
 pub enum HarmBlockThreshold {
     /// Threshold is unspecified.
@@ -70,7 +70,9 @@ pub enum HarmBlockThreshold {
 }
 
 /// Probability that content is harmful
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+///
+/// Variants are ordered from least to most likely, so probabilities can be compared.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
 pub enum HarmProbability {
     /// Probability is unspecified.
@@ -92,4 +94,7 @@ pub struct SafetyRating {
     pub category: HarmCategory,
     /// The probability that the content is harmful
     pub probability: HarmProbability,
+    /// Whether the content was blocked because of this rating
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub blocked: Option<bool>,
 }
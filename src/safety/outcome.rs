@@ -0,0 +1,227 @@
+//! Classification of safety feedback attached to generation results.
+//!
+//! The API reports blocks in several places: `promptFeedback.blockReason` when the prompt is
+//! rejected, a candidate's `finishReason` when the output is stopped, and per-category
+//! [`SafetyRating`]s on both. [`SafetyOutcome`] folds these into a single answer to "what
+//! happened?", available from
+//! [`GenerationResponse::safety_outcome`](crate::GenerationResponse::safety_outcome) and
+//! [`Interaction::safety_outcome`](crate::Interaction::safety_outcome).
+
+#![allow(deprecated)]
+
+use std::fmt;
+
+use super::model::{HarmCategory, HarmProbability, SafetyRating};
+use crate::generation::model::{BlockReason, FinishReason, GenerationResponse};
+use crate::interactions::model::{Interaction, InteractionStatus, Step};
+
+/// What the safety system did with a request.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SafetyOutcome {
+    /// Nothing was blocked.
+    Allowed,
+    /// The prompt was rejected; no output was generated.
+    PromptBlocked {
+        /// Why the prompt was rejected.
+        reason: BlockReason,
+        /// The ratings that caused the block.
+        flagged: Vec<SafetyRating>,
+    },
+    /// The output was stopped before any content was returned.
+    OutputBlocked {
+        /// Why generation stopped.
+        reason: FinishReason,
+        /// The ratings that caused the block.
+        flagged: Vec<SafetyRating>,
+    },
+    /// The output was stopped because it recited training data, before any content was
+    /// returned.
+    Recitation,
+    /// The output was stopped for safety or recitation reasons after part of it had been
+    /// returned.
+    Partial {
+        /// Why generation stopped.
+        reason: FinishReason,
+        /// The ratings that caused the block.
+        flagged: Vec<SafetyRating>,
+    },
+}
+
+impl SafetyOutcome {
+    /// Whether the request produced no usable output because of a block.
+    ///
+    /// [`Partial`](Self::Partial) outcomes are not considered blocked, since some content
+    /// was returned.
+    pub fn is_blocked(&self) -> bool {
+        matches!(
+            self,
+            Self::PromptBlocked { .. } | Self::OutputBlocked { .. } | Self::Recitation
+        )
+    }
+
+    /// Whether the safety system intervened at all.
+    pub fn is_allowed(&self) -> bool {
+        matches!(self, Self::Allowed)
+    }
+
+    /// Returns the ratings that caused the block, if any.
+    pub fn flagged(&self) -> &[SafetyRating] {
+        match self {
+            Self::PromptBlocked { flagged, .. }
+            | Self::OutputBlocked { flagged, .. }
+            | Self::Partial { flagged, .. } => flagged,
+            Self::Allowed | Self::Recitation => &[],
+        }
+    }
+
+    /// Returns the categories of the ratings that caused the block, with their probabilities.
+    pub fn categories(&self) -> Vec<(&HarmCategory, &HarmProbability)> {
+        self.flagged()
+            .iter()
+            .map(|rating| (&rating.category, &rating.probability))
+            .collect()
+    }
+
+    /// Analyzes the prompt feedback and the first candidate of a response.
+    pub(crate) fn of_response(response: &GenerationResponse) -> Self {
+        if let Some(feedback) = &response.prompt_feedback {
+            if let Some(reason) = &feedback.block_reason {
+                return Self::PromptBlocked {
+                    reason: reason.clone(),
+                    flagged: flagged(&feedback.safety_ratings),
+                };
+            }
+        }
+
+        let Some(candidate) = response.candidates.first() else {
+            return Self::Allowed;
+        };
+        let Some(reason) = candidate.finish_reason.clone() else {
+            return Self::Allowed;
+        };
+        if !is_safety_stop(&reason) {
+            return Self::Allowed;
+        }
+
+        let flagged = flagged(candidate.safety_ratings.as_deref().unwrap_or_default());
+        let has_content = candidate
+            .content
+            .parts
+            .as_ref()
+            .is_some_and(|parts| !parts.is_empty());
+        match (has_content, reason) {
+            (true, reason) => Self::Partial { reason, flagged },
+            (false, FinishReason::Recitation) => Self::Recitation,
+            (false, reason) => Self::OutputBlocked { reason, flagged },
+        }
+    }
+
+    /// Infers the outcome from an interaction's status and error.
+    ///
+    /// The Interactions API does not return safety ratings, so `flagged` is always empty and
+    /// the reason is recovered from the error code, when the server provides one.
+    pub(crate) fn of_interaction(interaction: &Interaction) -> Self {
+        if !matches!(
+            interaction.status,
+            InteractionStatus::Failed | InteractionStatus::Incomplete
+        ) {
+            return Self::Allowed;
+        }
+        let Some(error) = &interaction.error else {
+            return Self::Allowed;
+        };
+        let Some(code) = error.code.as_deref() else {
+            return Self::Allowed;
+        };
+        let code = serde_json::Value::String(code.to_uppercase());
+
+        let mentions_prompt = error
+            .message
+            .as_deref()
+            .is_some_and(|message| message.to_lowercase().contains("prompt"));
+        if mentions_prompt {
+            if let Ok(reason) = serde_json::from_value::<BlockReason>(code.clone()) {
+                return Self::PromptBlocked {
+                    reason,
+                    flagged: vec![],
+                };
+            }
+        }
+
+        let Ok(reason) = serde_json::from_value::<FinishReason>(code) else {
+            return Self::Allowed;
+        };
+        if !is_safety_stop(&reason) {
+            return Self::Allowed;
+        }
+        let has_output = interaction
+            .steps
+            .iter()
+            .any(|step| matches!(step, Step::ModelOutput { content, .. } if !content.is_empty()));
+        match (has_output, reason) {
+            (true, reason) => Self::Partial {
+                reason,
+                flagged: vec![],
+            },
+            (false, FinishReason::Recitation) => Self::Recitation,
+            (false, reason) => Self::OutputBlocked {
+                reason,
+                flagged: vec![],
+            },
+        }
+    }
+}
+
+impl fmt::Display for SafetyOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Allowed => return write!(f, "not blocked"),
+            Self::PromptBlocked { reason, .. } => write!(f, "prompt blocked ({reason:?})")?,
+            Self::OutputBlocked { reason, .. } => write!(f, "output blocked ({reason:?})")?,
+            Self::Recitation => return write!(f, "output blocked (Recitation)"),
+            Self::Partial { reason, .. } => write!(f, "output cut short ({reason:?})")?,
+        }
+        let categories = self.categories();
+        if !categories.is_empty() {
+            let listed: Vec<String> = categories
+                .iter()
+                .map(|(category, probability)| format!("{category:?}: {probability:?}"))
+                .collect();
+            write!(f, ", flagged {}", listed.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+/// Whether a finish reason means generation was stopped by a safety or recitation check.
+fn is_safety_stop(reason: &FinishReason) -> bool {
+    matches!(
+        reason,
+        FinishReason::Safety
+            | FinishReason::Recitation
+            | FinishReason::Blocklist
+            | FinishReason::ProhibitedContent
+            | FinishReason::Spii
+            | FinishReason::ImageSafety
+    )
+}
+
+/// Picks the ratings responsible for a block.
+///
+/// Ratings the server marked as `blocked` are used when present; otherwise every rating of
+/// medium probability or higher.
+fn flagged(ratings: &[SafetyRating]) -> Vec<SafetyRating> {
+    let blocked: Vec<SafetyRating> = ratings
+        .iter()
+        .filter(|rating| rating.blocked == Some(true))
+        .cloned()
+        .collect();
+    if !blocked.is_empty() {
+        return blocked;
+    }
+    ratings
+        .iter()
+        .filter(|rating| rating.probability >= HarmProbability::Medium)
+        .cloned()
+        .collect()
+}
@@ -0,0 +1,140 @@
+//! Named safety presets with per-category overrides.
+//!
+//! A [`SafetyPolicy`] produces the `safetySettings` of a request. Only the categories Gemini
+//! models accept (see [`HarmCategory::GEMINI`]) can be configured; the PaLM-era categories
+//! are rejected when the policy is built rather than by the API.
+//!
+//! ```
+//! use gemini_rust::{HarmBlockThreshold, HarmCategory, SafetyPolicy};
+//!
+//! let policy = SafetyPolicy::strict()
+//!     .with_category(HarmCategory::CivicIntegrity, HarmBlockThreshold::BlockOnlyHigh)?;
+//! assert_eq!(policy.settings().len(), HarmCategory::GEMINI.len());
+//! # Ok::<(), gemini_rust::SafetyError>(())
+//! ```
+
+use snafu::ensure;
+
+use super::model::{HarmBlockThreshold, HarmCategory, SafetySetting};
+use super::{Error, UnsupportedCategorySnafu};
+
+impl HarmCategory {
+    /// The categories Gemini models accept in safety settings.
+    pub const GEMINI: [HarmCategory; 5] = [
+        HarmCategory::Harassment,
+        HarmCategory::HateSpeech,
+        HarmCategory::SexuallyExplicit,
+        HarmCategory::DangerousContent,
+        HarmCategory::CivicIntegrity,
+    ];
+
+    /// Whether Gemini models accept this category in safety settings.
+    pub fn is_supported_by_gemini(&self) -> bool {
+        Self::GEMINI.contains(self)
+    }
+}
+
+/// Checks that every setting uses a category Gemini models accept.
+pub fn validate_settings(settings: &[SafetySetting]) -> Result<(), Error> {
+    for setting in settings {
+        ensure!(
+            setting.category.is_supported_by_gemini(),
+            UnsupportedCategorySnafu {
+                category: setting.category.clone(),
+            }
+        );
+    }
+    Ok(())
+}
+
+/// Safety settings for every Gemini harm category, built from a preset.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SafetyPolicy {
+    settings: Vec<SafetySetting>,
+}
+
+impl SafetyPolicy {
+    /// Applies `threshold` to every Gemini harm category.
+    pub fn uniform(threshold: HarmBlockThreshold) -> Self {
+        Self {
+            settings: HarmCategory::GEMINI
+                .into_iter()
+                .map(|category| SafetySetting {
+                    category,
+                    threshold: threshold.clone(),
+                })
+                .collect(),
+        }
+    }
+
+    /// Blocks content with a low or higher probability of harm.
+    pub fn strict() -> Self {
+        Self::uniform(HarmBlockThreshold::BlockLowAndAbove)
+    }
+
+    /// Blocks only content with a high probability of harm.
+    pub fn permissive() -> Self {
+        Self::uniform(HarmBlockThreshold::BlockOnlyHigh)
+    }
+
+    /// Turns the safety filter off.
+    pub fn off() -> Self {
+        Self::uniform(HarmBlockThreshold::Off)
+    }
+
+    /// Builds a policy from raw settings, rejecting categories Gemini models do not accept.
+    ///
+    /// A category listed more than once keeps its last threshold.
+    pub fn from_settings(settings: Vec<SafetySetting>) -> Result<Self, Error> {
+        let mut policy = Self { settings: vec![] };
+        for setting in settings {
+            policy = policy.with_category(setting.category, setting.threshold)?;
+        }
+        Ok(policy)
+    }
+
+    /// Overrides the threshold of one category.
+    pub fn with_category(
+        mut self,
+        category: HarmCategory,
+        threshold: HarmBlockThreshold,
+    ) -> Result<Self, Error> {
+        ensure!(
+            category.is_supported_by_gemini(),
+            UnsupportedCategorySnafu { category }
+        );
+        match self.settings.iter_mut().find(|s| s.category == category) {
+            Some(setting) => setting.threshold = threshold,
+            None => self.settings.push(SafetySetting {
+                category,
+                threshold,
+            }),
+        }
+        Ok(self)
+    }
+
+    /// Returns the threshold configured for `category`.
+    pub fn threshold(&self, category: &HarmCategory) -> Option<&HarmBlockThreshold> {
+        self.settings
+            .iter()
+            .find(|setting| &setting.category == category)
+            .map(|setting| &setting.threshold)
+    }
+
+    /// Returns the settings sent with requests.
+    pub fn settings(&self) -> &[SafetySetting] {
+        &self.settings
+    }
+
+    /// Consumes the policy, returning its settings.
+    pub fn into_settings(self) -> Vec<SafetySetting> {
+        self.settings
+    }
+}
+
+impl Default for SafetyPolicy {
+    /// Blocks content with a medium or higher probability of harm.
+    fn default() -> Self {
+        Self::uniform(HarmBlockThreshold::BlockMediumAndAbove)
+    }
+}
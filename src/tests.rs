@@ -378,3 +378,2777 @@ fn test_builder_safety_settings() {
     assert_eq!(req_settings.len(), 1);
     assert_eq!(req_settings[0].category, HarmCategory::Harassment);
 }
+
+#[test]
+#[allow(deprecated)]
+fn test_batch_keyed_requests() {
+    use crate::{BatchStatus, GeminiBuilder};
+
+    let client = GeminiBuilder::new("_key").build().unwrap();
+    let request = client.generate_content().with_user_message("Hi").build();
+
+    let batch = client
+        .batch_generate_content()
+        .with_request(request.clone())
+        .with_keyed_request("customer-42", request.clone())
+        .with_request(request.clone())
+        .build()
+        .unwrap();
+    let serialized = serde_json::to_value(&batch).unwrap();
+    let keys: Vec<_> = serialized["batch"]["inputConfig"]["requests"]["requests"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|item| item["metadata"]["key"].as_str().unwrap().to_string())
+        .collect();
+    assert_eq!(keys, ["0", "customer-42", "2"]);
+
+    let duplicate = client
+        .batch_generate_content()
+        .with_request(request.clone())
+        .with_keyed_request("0", request)
+        .build();
+    assert!(matches!(
+        duplicate,
+        Err(crate::batch::Error::DuplicateKey { key, .. }) if key == "0"
+    ));
+
+    let item: crate::batch::model::InlinedBatchGenerationResponseItem = serde_json::from_value(
+        json!({"metadata": {"key": "customer-42"}, "error": {"code": 3, "message": "bad"}}),
+    )
+    .unwrap();
+    assert_eq!(item.metadata.key, "customer-42");
+
+    // Without the submission order, numeric keys sort before custom ones.
+    let mut keys = vec!["customer-42", "10", "b", "2"];
+    crate::batch::model::sort_results(&mut keys, None, |key| key);
+    assert_eq!(keys, ["2", "10", "b", "customer-42"]);
+    let order = ["10", "customer-42", "2"].map(String::from);
+    crate::batch::model::sort_results(&mut keys, Some(&order), |key| key);
+    assert_eq!(keys, ["10", "customer-42", "2", "b"]);
+
+    let results = keys
+        .iter()
+        .map(|key| crate::BatchGenerationResponseItem {
+            response: Ok(
+                serde_json::from_value::<GenerationResponse>(json!({"candidates": []})).unwrap(),
+            ),
+            meta: crate::RequestMetadata {
+                key: key.to_string(),
+            },
+        })
+        .collect();
+    let by_key = BatchStatus::Succeeded { results }
+        .into_results_by_key()
+        .unwrap();
+    assert_eq!(by_key.len(), 4);
+    assert_eq!(by_key["customer-42"].meta.key, "customer-42");
+    assert!(BatchStatus::Pending.into_results_by_key().is_none());
+}
+
+#[test]
+fn test_embed_batch_wire_format() {
+    use crate::batch::model::{
+        EmbedBatchOperation, EmbedBatchOperationResponse, EmbedOperationResult,
+    };
+    use crate::{Content, EmbedContentRequest, GeminiBuilder};
+
+    let client = GeminiBuilder::new("_key").build().unwrap();
+    let request = client
+        .batch_embed_content()
+        .with_keyed_request(
+            "doc-1",
+            EmbedContentRequest {
+                model: Model::TextEmbedding004,
+                content: Content::text("hello"),
+                task_type: None,
+                title: None,
+                output_dimensionality: None,
+            },
+        )
+        .build()
+        .unwrap();
+    let serialized = serde_json::to_value(&request).unwrap();
+    let item = &serialized["batch"]["inputConfig"]["requests"]["requests"][0];
+    assert_eq!(item["metadata"]["key"], "doc-1");
+    assert_eq!(item["request"]["model"], "models/text-embedding-004");
+
+    let operation: EmbedBatchOperation = serde_json::from_value(json!({
+        "name": "batches/embed-1",
+        "done": true,
+        "metadata": {
+            "@type": "type.googleapis.com/google.ai.generativelanguage.v1main.EmbedContentBatch",
+            "model": "models/text-embedding-004",
+            "displayName": "RustEmbedBatch",
+            "createTime": "2025-01-01T00:00:00Z",
+            "updateTime": "2025-01-01T00:00:00Z",
+            "batchStats": {"requestCount": "2"},
+            "state": "BATCH_STATE_SUCCEEDED",
+            "name": "batches/embed-1"
+        },
+        "response": {
+            "@type": "type.googleapis.com/google.ai.generativelanguage.v1main.EmbedContentBatchOutput",
+            "inlinedResponses": {"inlinedResponses": [
+                {"metadata": {"key": "doc-1"}, "response": {"embedding": {"values": [0.5, -0.5]}}},
+                {"metadata": {"key": "doc-2"}, "error": {"code": 3, "message": "bad"}}
+            ]}
+        }
+    }))
+    .unwrap();
+
+    let Some(EmbedOperationResult::Response(EmbedBatchOperationResponse::InlinedResponses {
+        inlined_responses,
+    })) = operation.result
+    else {
+        panic!("expected inlined embedding responses");
+    };
+    let results: Vec<Result<_, _>> = inlined_responses
+        .inlined_responses
+        .into_iter()
+        .map(|item| item.result.into())
+        .collect();
+    assert_eq!(results[0].as_ref().unwrap().values, vec![0.5, -0.5]);
+    assert_eq!(results[1].as_ref().unwrap_err().code, 3);
+}
+
+#[test]
+#[allow(deprecated)]
+fn test_interaction_batch_conversion() {
+    use crate::batch::{BatchGenerationResponseItem, BatchInteractionResponseItem};
+    use crate::generation::GenerateContentRequest;
+    use crate::{
+        CreateInteractionRequest, GenerationResponse, InteractionInput, InteractionStatus,
+        InteractionTool, RequestMetadata, Role, Step,
+    };
+
+    let client = crate::GeminiBuilder::new("_key").build().unwrap();
+    let request: CreateInteractionRequest = client
+        .create_interaction()
+        .with_text("What's the weather?")
+        .with_system_instruction("Be brief")
+        .with_temperature(0.5)
+        .with_function(
+            "get_weather",
+            "Look up the weather",
+            json!({"type": "object"}),
+        )
+        .build()
+        .unwrap();
+
+    let converted = GenerateContentRequest::try_from(&request).unwrap();
+    assert_eq!(converted.contents.len(), 1);
+    assert_eq!(converted.contents[0].role, Some(Role::User));
+    assert_eq!(converted.generation_config.unwrap().temperature, Some(0.5));
+    let tools = serde_json::to_value(converted.tools.unwrap()).unwrap();
+    assert_eq!(tools[0]["function_declarations"][0]["name"], "get_weather");
+
+    let mut seeded = request.clone();
+    seeded
+        .generation_config
+        .get_or_insert_with(Default::default)
+        .seed = Some(i64::from(i32::MAX) + 1);
+    assert!(matches!(
+        GenerateContentRequest::try_from(&seeded),
+        Err(crate::interactions::convert::ConversionError::OutOfRange { field: "seed", .. })
+    ));
+
+    let mut stateful = request.clone();
+    stateful.previous_interaction_id = Some("prev".to_string());
+    assert!(GenerateContentRequest::try_from(&stateful).is_err());
+
+    let mut mcp = request;
+    mcp.input = InteractionInput::Text("hi".to_string());
+    mcp.tools = vec![InteractionTool::McpServer {
+        name: None,
+        url: Some("https://example.com/mcp".to_string()),
+        headers: None,
+        allowed_tools: None,
+    }];
+    assert!(GenerateContentRequest::try_from(&mcp).is_err());
+
+    let response: GenerationResponse = serde_json::from_value(json!({
+        "candidates": [{
+            "content": {"role": "model", "parts": [
+                {"text": "Checking."},
+                {"functionCall": {"name": "get_weather", "args": {"city": "Oslo"}}}
+            ]},
+            "finishReason": "STOP"
+        }],
+        "usageMetadata": {"promptTokenCount": 7, "candidatesTokenCount": 3, "totalTokenCount": 10},
+        "modelVersion": "gemini-2.5-flash",
+        "responseId": "resp-1"
+    }))
+    .unwrap();
+    let item: BatchInteractionResponseItem = BatchGenerationResponseItem {
+        response: Ok(response),
+        meta: RequestMetadata {
+            key: "weather".to_string(),
+        },
+    }
+    .into();
+
+    let interaction = item.response.unwrap();
+    assert_eq!(item.meta.key, "weather");
+    assert_eq!(interaction.status, InteractionStatus::RequiresAction);
+    assert_eq!(interaction.output_text(), "Checking.");
+    assert!(matches!(
+        &interaction.steps[1],
+        Step::FunctionCall { name, id, .. } if name == "get_weather" && id == "call_1"
+    ));
+    assert_eq!(interaction.usage.unwrap().total_tokens, Some(10));
+}
+
+#[tokio::test]
+async fn test_batch_wait_polling() {
+    use crate::batch::handle::{wait_for_outcome, BatchOutcome, BatchProgress, PollState};
+    use crate::{BatchHandleError, PollPolicy};
+    use std::time::Duration;
+
+    let policy = PollPolicy::fixed(Duration::from_millis(1));
+    let mut states = vec![
+        PollState::InProgress(None),
+        PollState::InProgress(Some(BatchProgress {
+            pending_count: 1,
+            completed_count: 1,
+            failed_count: 0,
+            total_count: 2,
+        })),
+        PollState::Finished(BatchOutcome::Succeeded {
+            results: vec!["a", "b"],
+        }),
+    ]
+    .into_iter();
+    let mut progress = Vec::new();
+    let outcome = wait_for_outcome(
+        "batches/test",
+        &policy,
+        || {
+            let state = states.next().unwrap();
+            async move { Ok(state) }
+        },
+        |p| progress.push(*p),
+    )
+    .await
+    .unwrap();
+    assert_eq!(outcome.into_results(), Some(vec!["a", "b"]));
+    assert_eq!(progress.len(), 1);
+    assert_eq!(progress[0].completed_count, 1);
+
+    let policy = policy.with_timeout(Duration::from_millis(5));
+    let result = wait_for_outcome::<(), _>(
+        "batches/test",
+        &policy,
+        || async { Ok(PollState::InProgress(None)) },
+        |_| {},
+    )
+    .await;
+    assert!(matches!(result, Err(BatchHandleError::WaitTimeout { .. })));
+
+    let token = tokio_util::sync::CancellationToken::new();
+    token.cancel();
+    let policy = PollPolicy::default().with_cancellation(token);
+    let result = wait_for_outcome::<(), _>(
+        "batches/test",
+        &policy,
+        || async { Ok(PollState::InProgress(None)) },
+        |_| {},
+    )
+    .await;
+    assert!(matches!(
+        result,
+        Err(BatchHandleError::WaitCancelled { .. })
+    ));
+
+    let policy = PollPolicy::default()
+        .with_max_interval(Duration::MAX)
+        .with_multiplier(f64::INFINITY);
+    assert_eq!(policy.multiplier, f64::MAX);
+    assert_eq!(policy.next_interval(Duration::from_secs(5)), Duration::MAX);
+    let policy = PollPolicy::default().with_multiplier(f64::NAN);
+    assert_eq!(policy.multiplier, 1.0);
+    assert_eq!(
+        policy.next_interval(Duration::from_secs(5)),
+        Duration::from_secs(5)
+    );
+    let policy = PollPolicy::default().with_multiplier(1e300);
+    assert_eq!(
+        policy.next_interval(Duration::from_secs(30)),
+        Duration::from_secs(60)
+    );
+}
+
+#[test]
+fn test_batch_sharding() {
+    use crate::batch::builder::plan_shards;
+    use crate::batch::sharded_handle::{combine_statuses, Shard};
+    use crate::batch::{BatchGenerationResponseItem, BatchStatus};
+    use crate::RequestMetadata;
+
+    assert_eq!(plan_shards(&[4, 4, 4], 10, None), vec![0..2, 2..3]);
+    assert_eq!(
+        plan_shards(&[1, 1, 1, 1, 1], 100, Some(2)),
+        vec![0..2, 2..4, 4..5]
+    );
+    assert_eq!(plan_shards(&[10, 1], 10, None), vec![0..1, 1..2]);
+    assert!(plan_shards(&[], 10, None).is_empty());
+
+    let item = |key: &str| BatchGenerationResponseItem {
+        response: Ok(serde_json::from_value(json!({"candidates": []})).unwrap()),
+        meta: RequestMetadata {
+            key: key.to_string(),
+        },
+    };
+    let shards = vec![
+        Shard::new("batches/1".to_string(), vec!["b".into(), "a".into()]),
+        Shard::new("batches/2".to_string(), vec!["c".into()]),
+    ];
+    let order: Vec<String> = vec!["b".into(), "a".into(), "c".into()];
+
+    let running = combine_statuses(
+        &shards,
+        &order,
+        [
+            BatchStatus::Succeeded {
+                results: vec![item("a"), item("b")],
+            },
+            BatchStatus::Pending,
+        ],
+    );
+    assert_eq!(
+        running,
+        BatchStatus::Running {
+            pending_count: 1,
+            completed_count: 2,
+            failed_count: 0,
+            total_count: 3,
+        }
+    );
+
+    let done = combine_statuses(
+        &shards,
+        &order,
+        [
+            BatchStatus::Succeeded {
+                results: vec![item("a"), item("b")],
+            },
+            BatchStatus::Succeeded {
+                results: vec![item("c")],
+            },
+        ],
+    );
+    let BatchStatus::Succeeded { results } = done else {
+        panic!("expected merged results");
+    };
+    let keys: Vec<_> = results.iter().map(|r| r.meta.key.as_str()).collect();
+    assert_eq!(keys, ["b", "a", "c"]);
+
+    let cancelled = combine_statuses(
+        &shards,
+        &order,
+        [
+            BatchStatus::Succeeded { results: vec![] },
+            BatchStatus::Cancelled,
+        ],
+    );
+    assert_eq!(cancelled, BatchStatus::Cancelled);
+}
+
+#[tokio::test]
+async fn test_batch_partial_submission() {
+    use crate::batch::Error as BatchError;
+    use crate::{GeminiBuilder, GenerateContentRequest, ShardLimits};
+
+    let (base_url, _) = serve_json(vec![
+        Reply::from(json!({})).with_header("x-goog-upload-url", "{base}/upload/1"),
+        Reply::from(json!({"file": {"name": "files/shard-1", "mimeType": "application/jsonl"}})),
+        Reply::from(json!({
+            "name": "batches/1",
+            "metadata": {
+                "@type": "type.googleapis.com/google.ai.generativelanguage.v1main.GenerateContentBatch",
+                "model": "models/gemini-2.5-flash",
+                "displayName": "sharded-1",
+                "createTime": "2025-01-01T00:00:00Z",
+                "updateTime": "2025-01-01T00:00:00Z",
+                "batchStats": {"requestCount": "1"},
+                "state": "BATCH_STATE_PENDING",
+                "name": "batches/1"
+            }
+        })),
+        Reply::status(500, json!({"error": {"message": "unavailable"}})),
+    ])
+    .await;
+    let client = GeminiBuilder::new("_key")
+        .with_base_url(base_url)
+        .build()
+        .unwrap();
+    let request: GenerateContentRequest =
+        serde_json::from_value(json!({"contents": [{"parts": [{"text": "hi"}]}]})).unwrap();
+
+    let result = client
+        .batch_generate_content()
+        .with_name("sharded".to_string())
+        .with_request(request.clone())
+        .with_request(request.clone())
+        .with_shard_limits(ShardLimits {
+            max_inline_bytes: 0,
+            max_file_bytes: 1024,
+            max_requests_per_batch: Some(1),
+        })
+        .execute_auto()
+        .await;
+    let Err(BatchError::PartialSubmission {
+        shard, submitted, ..
+    }) = result
+    else {
+        panic!("expected a partial submission");
+    };
+    assert_eq!(shard, 2);
+    assert_eq!(submitted.names(), ["batches/1"]);
+    assert_eq!(
+        submitted.shard_keys("batches/1"),
+        Some(&["0".to_string()][..])
+    );
+
+    // A job that runs but cannot be journaled is reported by name, not as a failed submission.
+    let (base_url, _) = serve_json(vec![
+        Reply::from(json!({})).with_header("x-goog-upload-url", "{base}/upload/1"),
+        Reply::from(json!({"file": {"name": "files/shard-1", "mimeType": "application/jsonl"}})),
+        Reply::from(json!({
+            "name": "batches/1",
+            "metadata": {
+                "@type": "type.googleapis.com/google.ai.generativelanguage.v1main.GenerateContentBatch",
+                "model": "models/gemini-2.5-flash",
+                "displayName": "sharded-1",
+                "createTime": "2025-01-01T00:00:00Z",
+                "updateTime": "2025-01-01T00:00:00Z",
+                "batchStats": {"requestCount": "1"},
+                "state": "BATCH_STATE_PENDING",
+                "name": "batches/1"
+            }
+        })),
+    ])
+    .await;
+    let client = GeminiBuilder::new("_key")
+        .with_base_url(base_url)
+        .build()
+        .unwrap();
+    let journal = crate::BatchJournal::new(std::env::temp_dir().join("missing-dir/journal.jsonl"));
+    let result = client
+        .batch_generate_content()
+        .with_name("sharded".to_string())
+        .with_request(request.clone())
+        .with_request(request)
+        .with_journal(journal)
+        .with_shard_limits(ShardLimits {
+            max_inline_bytes: 0,
+            max_file_bytes: 1024,
+            max_requests_per_batch: Some(1),
+        })
+        .execute_auto()
+        .await;
+    let Err(BatchError::Unjournaled {
+        name, submitted, ..
+    }) = result
+    else {
+        panic!("expected an unjournaled job");
+    };
+    assert_eq!(name, "batches/1");
+    assert_eq!(submitted.names(), ["batches/1"]);
+}
+
+#[tokio::test]
+async fn test_batch_journal_replay() {
+    use crate::BatchJournal;
+
+    let path = std::env::temp_dir().join(format!("gemini-journal-{}.jsonl", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    let journal = BatchJournal::new(&path);
+    assert!(journal.records().await.unwrap().is_empty());
+
+    journal
+        .record_submitted("batches/a", "nightly", None, vec!["0".into(), "1".into()])
+        .await
+        .unwrap();
+    journal
+        .record_submitted("batches/b", "nightly-2", Some("files/in"), vec!["x".into()])
+        .await
+        .unwrap();
+    journal.mark_collected("batches/a").await.unwrap();
+    journal.mark_collected("batches/a").await.unwrap();
+
+    // Simulate a crash in the middle of writing the next event.
+    let mut content = std::fs::read_to_string(&path).unwrap();
+    content.push_str(r#"{"event":"collected","name":"batc"#);
+    std::fs::write(&path, content).unwrap();
+
+    let records = journal.records().await.unwrap();
+    assert_eq!(records.len(), 2);
+    assert!(records[0].collected);
+    assert_eq!(records[0].keys, ["0", "1"]);
+
+    let pending = journal.pending().await.unwrap();
+    assert_eq!(pending.len(), 1);
+    assert_eq!(pending[0].name, "batches/b");
+    assert_eq!(pending[0].input_file.as_deref(), Some("files/in"));
+
+    // Appending after the torn line drops it instead of gluing the new event onto it.
+    journal.mark_collected("batches/b").await.unwrap();
+    let content = std::fs::read_to_string(&path).unwrap();
+    assert!(!content.contains(r#""name":"batc{"#));
+    assert!(content.ends_with('\n'));
+    let records = journal.records().await.unwrap();
+    assert_eq!(records.len(), 2);
+    assert!(journal.pending().await.unwrap().is_empty());
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_interaction_with_cache_model_check() {
+    use crate::{ClientError, GeminiBuilder};
+
+    let client = GeminiBuilder::new("_key").build().unwrap();
+    let cache = client
+        .get_cached_content("cachedContents/abc")
+        .with_model("models/gemini-2.5-pro".to_string());
+
+    let request = client
+        .create_interaction()
+        .with_model("gemini-2.5-pro")
+        .with_text("Summarize the document")
+        .with_cache(&cache)
+        .build()
+        .unwrap();
+    assert_eq!(
+        request.cached_content.as_deref(),
+        Some("cachedContents/abc")
+    );
+
+    let mismatch = client
+        .create_interaction()
+        .with_model("gemini-2.5-flash")
+        .with_text("Summarize the document")
+        .with_cache(&cache)
+        .build();
+    assert!(matches!(
+        mismatch,
+        Err(ClientError::CacheModelMismatch { .. })
+    ));
+
+    // Handles without a known model are passed through unchecked.
+    let unknown = client.get_cached_content("cachedContents/abc");
+    assert!(client
+        .create_interaction()
+        .with_model("gemini-2.5-flash")
+        .with_text("hi")
+        .with_cache(&unknown)
+        .build()
+        .is_ok());
+}
+
+#[test]
+fn test_cache_manager_fingerprint_and_savings() {
+    use crate::{GeminiBuilder, InteractionUsage, Model, UsageMetadata};
+    use std::time::Duration;
+
+    let client = GeminiBuilder::new("_key").build().unwrap();
+    let base = || {
+        client
+            .create_cache()
+            .with_system_instruction("You are a librarian")
+            .with_user_message("corpus")
+    };
+
+    let a = base().fingerprint();
+    let b = base()
+        .with_display_name("other")
+        .unwrap()
+        .with_ttl(Duration::from_secs(60))
+        .fingerprint();
+    assert_eq!(a, b);
+    assert_eq!(a.len(), 64);
+    assert_ne!(a, base().with_model(Model::Gemini25Pro).fingerprint());
+    assert_ne!(a, base().with_user_message("more").fingerprint());
+
+    let manager = client.cache_manager();
+    let usage: UsageMetadata = serde_json::from_value(json!({
+        "promptTokenCount": 1000,
+        "cachedContentTokenCount": 900,
+        "totalTokenCount": 1010
+    }))
+    .unwrap();
+    manager.record_usage(&usage);
+    manager.record_interaction_usage(&InteractionUsage {
+        total_input_tokens: Some(1000),
+        total_cached_tokens: Some(700),
+        ..Default::default()
+    });
+    let savings = manager.savings();
+    assert_eq!(savings.requests, 2);
+    assert_eq!(savings.cached_tokens, 1600);
+    assert!((savings.cached_ratio() - 0.8).abs() < f64::EPSILON);
+}
+
+#[tokio::test]
+async fn test_cache_manager_maintenance() {
+    use crate::GeminiBuilder;
+    use std::time::Duration;
+
+    let cache = |name: &str| {
+        json!({
+            "name": name,
+            "model": "models/gemini-2.5-flash",
+            "createTime": "2025-01-01T00:00:00Z",
+            "updateTime": "2025-01-01T00:00:00Z",
+            "usageMetadata": {"totalTokenCount": 2048},
+            "expireTime": "2999-01-01T00:00:00Z"
+        })
+    };
+    let (base_url, _) = serve_json(vec![
+        Reply::from(json!({})),
+        Reply::from(cache("cachedContents/a")),
+        Reply::from(json!({})),
+        Reply::from(cache("cachedContents/b")),
+        Reply::status(500, json!({"error": {"message": "internal"}})),
+        Reply::status(404, json!({"error": {"message": "not found"}})),
+    ])
+    .await;
+    let client = GeminiBuilder::new("_key")
+        .with_base_url(base_url)
+        .build()
+        .unwrap();
+    let manager = client.cache_manager().with_idle_timeout(Duration::ZERO);
+    for corpus in ["first corpus", "second corpus"] {
+        let lease = manager
+            .acquire(client.create_cache().with_user_message(corpus))
+            .await
+            .unwrap();
+        drop(lease);
+    }
+
+    // One delete fails, the other finds the cache already gone; the pass covers both.
+    let report = manager.maintain().await;
+    assert_eq!(report.failed.len(), 1);
+    assert_eq!(report.expired.len(), 1);
+    assert!(report.deleted.is_empty());
+
+    // The failed cache stays tracked and is retried; the missing one is forgotten.
+    let report = manager.maintain().await;
+    assert!(report.failed.is_empty());
+    assert_eq!(report.expired.len(), 1);
+    assert!(manager.maintain().await.expired.is_empty());
+
+    // A TTL below the default refresh margin still leases the cache it creates.
+    let expire_time = (time::OffsetDateTime::now_utc() + Duration::from_secs(30))
+        .format(&time::format_description::well_known::Rfc3339)
+        .unwrap();
+    let mut short = cache("cachedContents/short");
+    short["expireTime"] = json!(expire_time);
+    let (base_url, _) = serve_json(vec![Reply::from(json!({})), Reply::from(short)]).await;
+    let client = GeminiBuilder::new("_key")
+        .with_base_url(base_url)
+        .build()
+        .unwrap();
+    let lease = client
+        .cache_manager()
+        .with_ttl(Duration::from_secs(120))
+        .acquire(client.create_cache().with_user_message("short corpus"))
+        .await
+        .unwrap();
+    assert_eq!(lease.name(), "cachedContents/short");
+}
+
+#[test]
+fn test_cache_estimate() {
+    use crate::cache::{min_cache_tokens, CacheEstimate, CachePricing};
+    use std::time::Duration;
+
+    assert_eq!(min_cache_tokens("models/gemini-2.5-flash"), 1024);
+    assert_eq!(min_cache_tokens("gemini-2.5-pro"), 4096);
+    assert!(CachePricing::for_model("models/gemini-2.5-flash").is_some());
+    assert!(CachePricing::for_model("models/unknown").is_none());
+
+    let pricing = CachePricing {
+        input_per_million: 1.0,
+        cached_input_per_million: 0.1,
+        storage_per_million_per_hour: 1.0,
+    };
+    let hour = Duration::from_secs(3600);
+
+    // 1M tokens: creation $1 + storage $1, saving $0.90 per reuse.
+    let estimate = CacheEstimate::new("gemini-2.5-flash".into(), 1_000_000, 10, hour, pricing);
+    assert!((estimate.cost_without_cache - 10.0).abs() < 1e-9);
+    assert!((estimate.cost_with_cache - 3.0).abs() < 1e-9);
+    assert_eq!(estimate.break_even_reuses, Some(3));
+    assert!(estimate.is_worthwhile());
+
+    let few = CacheEstimate::new("gemini-2.5-flash".into(), 1_000_000, 2, hour, pricing);
+    assert!(few.savings() < 0.0);
+    assert!(!few.is_worthwhile());
+
+    let small = CacheEstimate::new("gemini-2.5-flash".into(), 500, 1000, hour, pricing);
+    assert!(!small.meets_minimum());
+    assert!(!small.is_worthwhile());
+}
+
+#[test]
+fn test_safety_outcome() {
+    use crate::{HarmCategory, HarmProbability, Interaction, SafetyOutcome};
+
+    let prompt_blocked: GenerationResponse = serde_json::from_value(json!({
+        "promptFeedback": {
+            "blockReason": "SAFETY",
+            "safetyRatings": [
+                {"category": "HARM_CATEGORY_HARASSMENT", "probability": "NEGLIGIBLE"},
+                {"category": "HARM_CATEGORY_DANGEROUS_CONTENT", "probability": "HIGH", "blocked": true}
+            ]
+        }
+    }))
+    .unwrap();
+    let outcome = prompt_blocked.safety_outcome();
+    assert!(matches!(outcome, SafetyOutcome::PromptBlocked { .. }));
+    assert!(prompt_blocked.is_blocked());
+    assert_eq!(
+        outcome.categories(),
+        vec![(&HarmCategory::DangerousContent, &HarmProbability::High)]
+    );
+
+    let output_blocked: GenerationResponse = serde_json::from_value(json!({
+        "candidates": [{
+            "content": {"role": "model"},
+            "finishReason": "SAFETY",
+            "safetyRatings": [
+                {"category": "HARM_CATEGORY_HATE_SPEECH", "probability": "MEDIUM"},
+                {"category": "HARM_CATEGORY_HARASSMENT", "probability": "LOW"}
+            ]
+        }]
+    }))
+    .unwrap();
+    let outcome = output_blocked.safety_outcome();
+    assert!(matches!(
+        outcome,
+        SafetyOutcome::OutputBlocked {
+            reason: FinishReason::Safety,
+            ..
+        }
+    ));
+    assert_eq!(outcome.flagged().len(), 1);
+
+    let partial: GenerationResponse = serde_json::from_value(json!({
+        "candidates": [{
+            "content": {"role": "model", "parts": [{"text": "Once upon"}]},
+            "finishReason": "RECITATION"
+        }]
+    }))
+    .unwrap();
+    assert!(matches!(
+        partial.safety_outcome(),
+        SafetyOutcome::Partial { .. }
+    ));
+    assert!(!partial.is_blocked());
+
+    // The conversion to an Interaction keeps enough information to classify the block.
+    let interaction = Interaction::from(output_blocked);
+    assert!(matches!(
+        interaction.safety_outcome(),
+        SafetyOutcome::OutputBlocked {
+            reason: FinishReason::Safety,
+            ..
+        }
+    ));
+    assert!(matches!(
+        Interaction::from(prompt_blocked).safety_outcome(),
+        SafetyOutcome::PromptBlocked { .. }
+    ));
+}
+
+#[tokio::test]
+async fn test_safety_policy() {
+    use crate::{
+        ClientError, GeminiBuilder, HarmBlockThreshold, HarmCategory, SafetyError, SafetyPolicy,
+        SafetySetting,
+    };
+
+    let policy = SafetyPolicy::strict()
+        .with_category(HarmCategory::CivicIntegrity, HarmBlockThreshold::Off)
+        .unwrap();
+    assert_eq!(policy.settings().len(), HarmCategory::GEMINI.len());
+    assert_eq!(
+        policy.threshold(&HarmCategory::Harassment),
+        Some(&HarmBlockThreshold::BlockLowAndAbove)
+    );
+    assert_eq!(
+        policy.threshold(&HarmCategory::CivicIntegrity),
+        Some(&HarmBlockThreshold::Off)
+    );
+    assert_eq!(
+        SafetyPolicy::default().threshold(&HarmCategory::HateSpeech),
+        Some(&HarmBlockThreshold::BlockMediumAndAbove)
+    );
+
+    assert!(matches!(
+        SafetyPolicy::off().with_category(HarmCategory::Toxicity, HarmBlockThreshold::BlockNone),
+        Err(SafetyError::UnsupportedCategory {
+            category: HarmCategory::Toxicity
+        })
+    ));
+
+    let client = GeminiBuilder::new("_key").build().unwrap();
+
+    // Unsupported categories are rejected before anything is sent.
+    #[allow(deprecated)]
+    let result = client
+        .generate_content()
+        .with_user_message("Hi")
+        .with_safety_settings(vec![SafetySetting {
+            category: HarmCategory::Medical,
+            threshold: HarmBlockThreshold::BlockNone,
+        }])
+        .execute()
+        .await;
+    assert!(matches!(
+        result,
+        Err(ClientError::InvalidSafetySettings { .. })
+    ));
+
+    let request = client
+        .create_interaction()
+        .with_text("Hi")
+        .with_safety_policy(SafetyPolicy::permissive())
+        .build()
+        .unwrap();
+    // The Interactions API has no safety settings; they only apply through generateContent.
+    let json = serde_json::to_value(&request).unwrap();
+    assert!(json.get("safety_settings").is_none());
+
+    #[allow(deprecated)]
+    let converted = crate::GenerateContentRequest::try_from(&request).unwrap();
+    assert_eq!(
+        converted.safety_settings.as_deref(),
+        Some(SafetyPolicy::permissive().settings())
+    );
+
+    // Executing it directly would drop the policy, so it fails instead.
+    let result = client
+        .create_interaction()
+        .with_text("Hi")
+        .with_safety_policy(SafetyPolicy::permissive())
+        .execute()
+        .await;
+    assert!(matches!(
+        result,
+        Err(ClientError::SafetySettingsUnsupported)
+    ));
+
+    // Batched requests are validated too.
+    let mut request = converted;
+    request.safety_settings = Some(vec![SafetySetting {
+        category: HarmCategory::Medical,
+        threshold: HarmBlockThreshold::BlockNone,
+    }]);
+    let result = client
+        .batch_generate_content()
+        .with_keyed_request("medical", request)
+        .build();
+    assert!(matches!(
+        result,
+        Err(crate::batch::Error::InvalidSafetySettings { key, .. }) if key == "medical"
+    ));
+}
+
+#[tokio::test]
+async fn test_content_filters() {
+    use crate::safety::filter::FilterChain;
+    use crate::{ClientError, ContentFilter, FilterAction, FilterDirection, FilterTag};
+    use crate::{GeminiBuilder, Interaction, InteractionContent, InteractionInput, RegexFilter};
+    use crate::{Step, StepResult, StepResultContent, ThoughtSummaryContent};
+    use std::sync::Arc;
+
+    let email = RegexFilter::email();
+    assert_eq!(
+        email.filter_text("write to jane.doe@example.com today"),
+        FilterAction::Redact("write to [REDACTED:email] today".into())
+    );
+    assert_eq!(
+        RegexFilter::phone_number()
+            .tag()
+            .filter_text("call (555) 123-4567"),
+        FilterAction::Tag("phone_number".into())
+    );
+    // Only numbers passing the Luhn check are treated as cards.
+    let card = RegexFilter::credit_card();
+    assert_eq!(
+        card.filter_text("card 4111 1111 1111 1111"),
+        FilterAction::Redact("card [REDACTED:credit_card]".into())
+    );
+    assert_eq!(
+        card.filter_text("order 1234 5678 9012 3456"),
+        FilterAction::Allow
+    );
+
+    let mut chain = FilterChain::default();
+    chain.push(Arc::new(RegexFilter::email()));
+    let client = GeminiBuilder::new("_key").build().unwrap();
+    let mut request = client
+        .create_interaction()
+        .with_content_input(vec![
+            InteractionContent::text("from bob@example.org"),
+            InteractionContent::text("hello"),
+        ])
+        .build()
+        .unwrap();
+    chain.filter_interaction_request(&mut request).unwrap();
+    let InteractionInput::ContentArray(contents) = &request.input else {
+        panic!("unexpected input: {:?}", request.input);
+    };
+    assert_eq!(
+        contents[0],
+        InteractionContent::text("from [REDACTED:email]")
+    );
+
+    // Function results and thought summaries are filtered too.
+    let mut request = client
+        .create_interaction()
+        .with_step_input(vec![
+            Step::Thought {
+                signature: None,
+                summary: vec![ThoughtSummaryContent::Text {
+                    text: "ask ann@example.com".into(),
+                }],
+            },
+            Step::FunctionResult {
+                name: Some("lookup".into()),
+                call_id: "1".into(),
+                result: StepResult::String("ann@example.com".into()),
+                is_error: None,
+            },
+            Step::FunctionResult {
+                name: Some("lookup".into()),
+                call_id: "2".into(),
+                result: StepResult::Object(json!({"people": [{"mail": "ann@example.com"}]})),
+                is_error: None,
+            },
+            Step::FunctionResult {
+                name: Some("lookup".into()),
+                call_id: "3".into(),
+                result: StepResult::ContentArray(vec![StepResultContent::Text {
+                    text: "ann@example.com".into(),
+                }]),
+                is_error: None,
+            },
+        ])
+        .build()
+        .unwrap();
+    chain.filter_interaction_request(&mut request).unwrap();
+    let input = serde_json::to_string(&request.input).unwrap();
+    assert!(!input.contains("ann@example.com"), "{input}");
+    assert_eq!(input.matches("[REDACTED:email]").count(), 4);
+
+    // Builders added to a batch run their filters.
+    let result = client
+        .batch_create_interactions()
+        .with_interaction(
+            client
+                .create_interaction()
+                .with_text("my card is 4111-1111-1111-1111")
+                .with_content_filter(RegexFilter::credit_card().reject()),
+        )
+        .execute()
+        .await;
+    assert!(matches!(
+        result,
+        Err(crate::batch::Error::BuildInteraction {
+            source: ClientError::ContentRejected { .. },
+            ..
+        })
+    ));
+
+    let mut response: GenerationResponse = serde_json::from_value(json!({
+        "candidates": [{"content": {"role": "model", "parts": [{"text": "mail x@y.io"}]}}]
+    }))
+    .unwrap();
+    chain.filter_response(&mut response).unwrap();
+    assert_eq!(response.text(), "mail [REDACTED:email]");
+    let mut interaction = Interaction::from(response);
+    chain.filter_interaction(&mut interaction).unwrap();
+    assert_eq!(interaction.output_text(), "mail [REDACTED:email]");
+
+    // A rejecting filter stops the request before it is sent.
+    #[allow(deprecated)]
+    let result = client
+        .generate_content()
+        .with_user_message("my card is 4111-1111-1111-1111")
+        .with_content_filter(RegexFilter::credit_card().reject())
+        .execute()
+        .await;
+    assert!(matches!(
+        result,
+        Err(ClientError::ContentRejected {
+            direction: FilterDirection::Outbound,
+            ..
+        })
+    ));
+
+    // `build_checked` runs the filters that `build` skips.
+    #[allow(deprecated)]
+    let result = client
+        .generate_content()
+        .with_user_message("my card is 4111-1111-1111-1111")
+        .with_content_filter(RegexFilter::credit_card().reject())
+        .build_checked();
+    assert!(matches!(result, Err(ClientError::ContentRejected { .. })));
+
+    // Tags from both directions are returned with the response.
+    let (base_url, _) = serve_json(vec![json!({
+        "candidates": [{"content": {"role": "model", "parts": [{"text": "mail x@y.io"}]}}]
+    })])
+    .await;
+    let client = GeminiBuilder::new("_key")
+        .with_base_url(base_url)
+        .build()
+        .unwrap();
+    #[allow(deprecated)]
+    let response = client
+        .generate_content()
+        .with_user_message("call (555) 123-4567")
+        .with_content_filter(RegexFilter::phone_number().tag())
+        .with_output_filter(RegexFilter::email().tag())
+        .execute()
+        .await
+        .unwrap();
+    assert_eq!(
+        response.filter_tags,
+        vec![
+            FilterTag {
+                tag: "phone_number".into(),
+                direction: FilterDirection::Outbound,
+            },
+            FilterTag {
+                tag: "email".into(),
+                direction: FilterDirection::Inbound,
+            },
+        ]
+    );
+    assert_eq!(response.text(), "mail x@y.io");
+}
+
+#[tokio::test]
+async fn test_embedding_math_and_index() {
+    use crate::{BinaryEmbedding, ContentEmbedding, EmbeddingError, EmbeddingIndex};
+
+    let a = ContentEmbedding::new(vec![3.0, 4.0]);
+    let b = ContentEmbedding::new(vec![4.0, 3.0]);
+    assert!((a.norm() - 5.0).abs() < 1e-6);
+    assert!((a.normalized().norm() - 1.0).abs() < 1e-6);
+    assert!((a.dot(&b) - 24.0).abs() < 1e-6);
+    assert!((a.cosine_similarity(&b) - 0.96).abs() < 1e-6);
+    assert!((a.euclidean_distance(&b) - 2f32.sqrt()).abs() < 1e-6);
+
+    let int8 = a.quantize_int8();
+    assert_eq!(int8.values, vec![95, 127]);
+    let restored = int8.dequantize();
+    assert!(restored.euclidean_distance(&a) < 0.05);
+
+    let c = ContentEmbedding::new(vec![0.5, -0.1, 0.0, 2.0, -3.0, 1.0, 1.0, 1.0, -1.0]);
+    let binary = c.quantize_binary();
+    assert_eq!(binary.bits, vec![0b1110_1001, 0]);
+    assert_eq!(binary.hamming_distance(&binary), 0);
+    // Comparing embeddings of different dimensions panics, as documented.
+    let empty = ContentEmbedding::new(vec![]).quantize_binary();
+    assert!(std::panic::catch_unwind(|| empty.similarity(&binary)).is_err());
+    assert!(std::panic::catch_unwind(|| a.dot(&c)).is_err());
+    assert!((binary.dequantize().unwrap().norm() - 1.0).abs() < 1e-6);
+    let short = BinaryEmbedding {
+        bits: vec![0xff],
+        dimensions: 9,
+    };
+    assert!(matches!(
+        short.dequantize(),
+        Err(EmbeddingError::BinaryLength {
+            dimensions: 9,
+            bytes: 1
+        })
+    ));
+    assert!(
+        serde_json::from_value::<BinaryEmbedding>(json!({"bits": [1], "dimensions": 9})).is_err()
+    );
+    assert_eq!(
+        serde_json::from_value::<BinaryEmbedding>(serde_json::to_value(&binary).unwrap()).unwrap(),
+        binary
+    );
+
+    let mut index = EmbeddingIndex::new();
+    index
+        .insert(
+            "east",
+            &ContentEmbedding::new(vec![1.0, 0.0]),
+            json!({"lang": "en"}),
+        )
+        .unwrap();
+    index
+        .insert(
+            "north",
+            &ContentEmbedding::new(vec![0.0, 2.0]),
+            json!({"lang": "fr"}),
+        )
+        .unwrap();
+    index
+        .insert(
+            "northeast",
+            &ContentEmbedding::new(vec![1.0, 1.0]),
+            json!({"lang": "en"}),
+        )
+        .unwrap();
+    assert!(matches!(
+        index.insert("bad", &ContentEmbedding::new(vec![1.0]), json!(null)),
+        Err(EmbeddingError::DimensionMismatch {
+            expected: 2,
+            actual: 1
+        })
+    ));
+
+    let query = ContentEmbedding::new(vec![0.1, 1.0]);
+    let hits = index.search(&query, 2).unwrap();
+    let ids: Vec<&str> = hits.iter().map(|hit| hit.id).collect();
+    assert_eq!(ids, vec!["north", "northeast"]);
+    let english = index
+        .search_filtered(&query, 5, |metadata| metadata["lang"] == "en")
+        .unwrap();
+    assert_eq!(english.len(), 2);
+    assert_eq!(english[0].id, "northeast");
+
+    let path = std::env::temp_dir().join(format!("gemini-index-{}.json", std::process::id()));
+    index.save(&path).await.unwrap();
+    let loaded: EmbeddingIndex = EmbeddingIndex::load(&path).await.unwrap();
+    assert_eq!(loaded, index);
+
+    // An index whose entries disagree on dimensions is rejected.
+    let mut corrupted = serde_json::to_value(&index).unwrap();
+    corrupted["entries"][0]["embedding"]["values"] = json!([1.0, 0.0, 0.0]);
+    std::fs::write(&path, corrupted.to_string()).unwrap();
+    let loaded = EmbeddingIndex::<serde_json::Value>::load(&path).await;
+    std::fs::remove_file(&path).unwrap();
+    assert!(matches!(loaded, Err(EmbeddingError::Deserialize { .. })));
+}
+
+#[test]
+fn test_chunker() {
+    use crate::Chunker;
+
+    // 4 characters per token: chunks of at most 12 characters, sharing 4.
+    let chunker = Chunker::new(3).with_overlap(1);
+    let text = "alpha beta gamma delta epsilon";
+    let chunks = chunker.split(text);
+    for chunk in &chunks {
+        assert_eq!(&text[chunk.start..chunk.end], chunk.text);
+        assert!(chunk.text.chars().count() <= 12);
+    }
+    assert_eq!(chunks[0].text, "alpha beta ");
+    assert_eq!(chunks[1].start, chunks[0].end - 4);
+    assert_eq!(chunks.last().unwrap().end, text.len());
+
+    // Without whitespace, chunks are cut at the limit and respect char boundaries.
+    let chunks = Chunker::new(1).split("ééééé");
+    let texts: Vec<&str> = chunks.iter().map(|chunk| chunk.text).collect();
+    assert_eq!(texts, vec!["éééé", "é"]);
+
+    assert!(Chunker::default().split("   ").is_empty());
+    assert_eq!(Chunker::default().estimate_tokens("abcdefgh"), 2);
+}
+
+#[tokio::test]
+async fn test_bulk_embedder() {
+    use crate::{BulkDocument, Chunker, EmbeddingError, GeminiBuilder};
+    use std::time::Duration;
+
+    let embeddings = |values: &[f32]| json!({"embeddings": values.iter().map(|v| json!({"values": [v]})).collect::<Vec<_>>()});
+    // The first call is retried after a server error; the second returns too few embeddings.
+    let (base_url, requests) = serve_json(vec![
+        Reply::status(500, json!({"error": {"message": "unavailable"}})),
+        Reply::from(embeddings(&[1.0, 2.0])),
+        Reply::from(embeddings(&[3.0])),
+    ])
+    .await;
+    let client = GeminiBuilder::new("_key")
+        .with_base_url(base_url)
+        .build()
+        .unwrap();
+
+    let results: Vec<_> = futures::StreamExt::collect(
+        client
+            .bulk_embed()
+            .with_chunker(Chunker::new(2))
+            .with_batch_size(2)
+            .with_concurrency(1)
+            .with_retries(1, Duration::from_millis(1))
+            .embed_stream(futures::stream::iter(vec![
+                BulkDocument::new("a", "aaa"),
+                BulkDocument::new("b", "bbbb cccc"),
+                BulkDocument::new("c", "ddd"),
+            ])),
+    )
+    .await;
+
+    assert_eq!(results.len(), 3);
+    let embedded: Vec<_> = results[..2]
+        .iter()
+        .map(|result| {
+            let chunk = result.as_ref().unwrap();
+            (
+                chunk.document_id.as_deref(),
+                chunk.chunk_index,
+                chunk.text.as_str(),
+                chunk.embedding.values[0],
+            )
+        })
+        .collect();
+    assert_eq!(
+        embedded,
+        vec![(Some("a"), 0, "aaa", 1.0), (Some("b"), 0, "bbbb ", 2.0)]
+    );
+    let Err(error @ EmbeddingError::EmbeddingCount { actual: 1, .. }) = &results[2] else {
+        panic!("expected an embedding count mismatch, got {:?}", results[2]);
+    };
+    assert_eq!(error.chunks(), [(1, 1), (2, 0)]);
+
+    let requests = requests.lock().unwrap().clone();
+    assert_eq!(requests.len(), 3);
+    assert_eq!(requests[0], requests[1]);
+    let texts: Vec<_> = requests[2]["requests"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|request| request["content"]["parts"][0]["text"].clone())
+        .collect();
+    assert_eq!(texts, vec![json!("cccc"), json!("ddd")]);
+
+    // Chunks are sent without waiting for a full batch from a stream that has stalled.
+    let (base_url, _) = serve_json(vec![embeddings(&[4.0])]).await;
+    let client = GeminiBuilder::new("_key")
+        .with_base_url(base_url)
+        .build()
+        .unwrap();
+    let documents = futures::StreamExt::chain(
+        futures::stream::iter(vec![BulkDocument::new("a", "aaa")]),
+        futures::stream::pending(),
+    );
+    let embeddings = client.bulk_embed().embed_stream(documents);
+    let first = tokio::time::timeout(
+        Duration::from_secs(5),
+        futures::StreamExt::next(&mut Box::pin(embeddings)),
+    )
+    .await
+    .expect("the first chunk waited for a full batch");
+    assert_eq!(first.unwrap().unwrap().embedding.values, vec![4.0]);
+}
+
+#[test]
+fn test_embed_inputs() {
+    use crate::{EmbedInput, GeminiBuilder, TaskType};
+
+    let client = GeminiBuilder::new("_key").build().unwrap();
+    let request = client
+        .embed_content()
+        .with_model(Model::GeminiEmbedding001)
+        .with_task_type(TaskType::RetrievalDocument)
+        .with_text("plain")
+        .with_input(EmbedInput::text("query").with_task_type(TaskType::RetrievalQuery))
+        .with_input(EmbedInput::pdf("JVBERi0=").with_title("Manual"))
+        .with_image("image/png", "iVBORw0=")
+        .batch_request();
+
+    let json = serde_json::to_value(&request).unwrap();
+    let requests = json["requests"].as_array().unwrap();
+    assert_eq!(requests.len(), 4);
+    assert!(requests
+        .iter()
+        .all(|r| r["model"] == "models/gemini-embedding-001"));
+    assert_eq!(requests[0]["taskType"], "RETRIEVAL_DOCUMENT");
+    assert_eq!(requests[1]["taskType"], "RETRIEVAL_QUERY");
+    assert_eq!(requests[2]["title"], "Manual");
+    assert_eq!(
+        requests[2]["content"]["parts"][0]["inlineData"]["mimeType"],
+        "application/pdf"
+    );
+    assert_eq!(
+        requests[3]["content"]["parts"][0]["inlineData"]["mimeType"],
+        "image/png"
+    );
+}
+
+#[tokio::test]
+async fn test_embed_model_urls() {
+    use crate::{GeminiBuilder, Model};
+
+    let (base_url, requests) = serve_json(vec![
+        json!({"embedding": {"values": [1.0]}}),
+        json!({"embeddings": [{"values": [1.0]}]}),
+        json!({
+            "name": "batches/1",
+            "metadata": {
+                "@type": "type.googleapis.com/google.ai.generativelanguage.v1main.EmbedContentBatch",
+                "model": "models/gemini-embedding-001",
+                "displayName": "RustEmbedBatch",
+                "createTime": "2025-01-01T00:00:00Z",
+                "updateTime": "2025-01-01T00:00:00Z",
+                "batchStats": {"requestCount": "1"},
+                "state": "BATCH_STATE_PENDING",
+                "name": "batches/1"
+            }
+        }),
+    ])
+    .await;
+    let client = GeminiBuilder::new("_key")
+        .with_base_url(base_url)
+        .build()
+        .unwrap();
+    let builder = client
+        .embed_content()
+        .with_model(Model::GeminiEmbedding001)
+        .with_text("hello");
+
+    builder.clone().execute().await.unwrap();
+    builder.clone().execute_batch().await.unwrap();
+    let request = builder.batch_request().requests.remove(0);
+    client
+        .batch_embed_content()
+        .with_request(request)
+        .execute()
+        .await
+        .unwrap();
+
+    // Every call goes to the endpoint of the model named in its body.
+    let lines: Vec<_> = requests
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|request| request.line.clone())
+        .collect();
+    assert_eq!(
+        lines,
+        vec![
+            "POST /v1beta/models/gemini-embedding-001:embedContent",
+            "POST /v1beta/models/gemini-embedding-001:batchEmbedContents",
+            "POST /v1beta/models/gemini-embedding-001:asyncBatchEmbedContent",
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_ingest_directory_selection() {
+    use crate::file_search::ingest::{walk, FileSelector};
+    use crate::{IngestFile, SkipReason};
+    use glob::Pattern;
+    use std::path::PathBuf;
+
+    let root = std::env::temp_dir().join(format!("gemini-ingest-{}", std::process::id()));
+    std::fs::create_dir_all(root.join("guides/drafts")).unwrap();
+    std::fs::write(root.join("README.md"), "# Readme").unwrap();
+    std::fs::write(
+        root.join("guides/setup.md"),
+        "---\ntitle: \"Setup\"\ntags: a, b\n---\nBody",
+    )
+    .unwrap();
+    std::fs::write(root.join("guides/drafts/wip.md"), "wip").unwrap();
+    std::fs::write(root.join("guides/data.unknownext"), "?").unwrap();
+    std::fs::write(root.join("guides/big.txt"), "x".repeat(200)).unwrap();
+
+    let (files, failed) = walk(&root).await.unwrap();
+    std::fs::remove_dir_all(&root).unwrap();
+    assert!(failed.is_empty());
+
+    let selector = FileSelector {
+        include: vec![Pattern::new("guides/**").unwrap()],
+        exclude: vec![Pattern::new("guides/drafts/**").unwrap()],
+        max_file_size: Some(100),
+    };
+    let decisions: Vec<_> = files
+        .iter()
+        .map(|(path, size)| (path.to_str().unwrap(), selector.skip_reason(path, *size)))
+        .collect();
+    assert_eq!(
+        decisions,
+        vec![
+            ("README.md", Some(SkipReason::NotIncluded)),
+            (
+                "guides/big.txt",
+                Some(SkipReason::TooLarge {
+                    size: 200,
+                    limit: 100
+                })
+            ),
+            ("guides/data.unknownext", Some(SkipReason::UnknownMimeType)),
+            ("guides/drafts/wip.md", Some(SkipReason::Excluded)),
+            ("guides/setup.md", None),
+        ]
+    );
+
+    // Patterns without a slash match the file name at any depth.
+    let selector = FileSelector {
+        include: vec![Pattern::new("*.md").unwrap()],
+        ..Default::default()
+    };
+    assert_eq!(
+        selector.skip_reason(&PathBuf::from("guides/drafts/wip.md"), 3),
+        None
+    );
+
+    let file = IngestFile {
+        path: root.join("guides/setup.md"),
+        relative_path: PathBuf::from("guides/setup.md"),
+        mime_type: mime::TEXT_PLAIN,
+        data: b"---\ntitle: \"Setup\"\ntags: a, b\n---\nBody".to_vec(),
+    };
+    assert_eq!(
+        file.frontmatter(),
+        vec![
+            ("title".to_string(), "Setup".to_string()),
+            ("tags".to_string(), "a, b".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn test_sync_plan() {
+    use crate::file_search::sync::{content_hash, default_namespace, plan_changes};
+    use crate::file_search::Document;
+    use crate::{SyncAction, SyncPlan};
+    use std::path::{Path, PathBuf};
+
+    let document = |id: &str, path: &str, hash: &str| -> Document {
+        serde_json::from_value(json!({
+            "name": format!("fileSearchStores/s/documents/{id}"),
+            "customMetadata": [
+                {"key": "sync_root", "stringValue": if id == "other" { "other" } else { "docs" }},
+                {"key": "source_path", "stringValue": path},
+                {"key": "content_sha256", "stringValue": hash},
+                {"key": "tags", "stringListValue": {"values": ["a"]}}
+            ],
+            "createTime": "2025-01-01T00:00:00Z",
+            "updateTime": "2025-01-01T00:00:00Z",
+            "state": "STATE_ACTIVE",
+            "sizeBytes": "10",
+            "mimeType": "text/markdown"
+        }))
+        .unwrap()
+    };
+    let same = content_hash(b"same");
+    let documents = vec![
+        document("same", "same.md", &same),
+        document("same-dup", "same.md", &same),
+        document("changed", "guides/changed.md", &content_hash(b"old")),
+        document("removed", "removed.md", &content_hash(b"gone")),
+        // Synced from another directory, so not a document of a removed file.
+        document("other", "other.md", &content_hash(b"other")),
+    ];
+    let local = vec![
+        (PathBuf::from("guides/changed.md"), content_hash(b"new")),
+        (PathBuf::from("guides/new.md"), content_hash(b"new")),
+        (PathBuf::from("same.md"), same),
+    ];
+
+    let changes = plan_changes("docs", &local, &documents);
+    let actions: Vec<_> = changes
+        .iter()
+        .map(|c| (c.path.to_str().unwrap(), c.action.clone()))
+        .collect();
+    let doc = |id: &str| format!("fileSearchStores/s/documents/{id}");
+    assert_eq!(
+        actions,
+        vec![
+            (
+                "guides/changed.md",
+                SyncAction::Update {
+                    document: doc("changed")
+                }
+            ),
+            ("guides/new.md", SyncAction::Create),
+            (
+                "removed.md",
+                SyncAction::Delete {
+                    document: doc("removed")
+                }
+            ),
+            (
+                "same.md",
+                SyncAction::Unchanged {
+                    document: doc("same")
+                }
+            ),
+            (
+                "same.md",
+                SyncAction::Delete {
+                    document: doc("same-dup")
+                }
+            ),
+        ]
+    );
+
+    // The default namespace does not depend on how the directory was named.
+    let root = std::env::temp_dir().join(format!("gemini-sync-{}", std::process::id()));
+    std::fs::create_dir_all(root.join("docs")).unwrap();
+    let relative = root.join("docs/../docs/.");
+    let namespace = default_namespace(&root.join("docs"));
+    assert_eq!(default_namespace(&relative), namespace);
+    assert!(Path::new(&namespace).is_absolute());
+    std::fs::remove_dir_all(&root).unwrap();
+
+    let plan = SyncPlan {
+        changes,
+        ..Default::default()
+    };
+    assert!(plan.has_changes());
+    assert_eq!(plan.uploads().count(), 2);
+    assert_eq!(
+        plan.to_string(),
+        "~ guides/changed.md\n+ guides/new.md\n- removed.md\n- same.md\n\
+         1 to create, 1 to update, 2 to delete, 1 unchanged"
+    );
+}
+
+#[test]
+fn test_custom_metadata_wire_format() {
+    use crate::{CustomMetadata, CustomMetadataValue, StringList};
+
+    let entries = vec![
+        CustomMetadata::string("lang", "en"),
+        CustomMetadata {
+            key: "tags".into(),
+            value: CustomMetadataValue::StringListValue {
+                string_list_value: StringList {
+                    values: vec!["a".into(), "b".into()],
+                },
+            },
+        },
+        CustomMetadata {
+            key: "year".into(),
+            value: CustomMetadataValue::NumericValue {
+                numeric_value: 2024.0,
+            },
+        },
+    ];
+    let wire = json!([
+        {"key": "lang", "stringValue": "en"},
+        {"key": "tags", "stringListValue": {"values": ["a", "b"]}},
+        {"key": "year", "numericValue": 2024.0}
+    ]);
+    assert_eq!(serde_json::to_value(&entries).unwrap(), wire);
+
+    let parsed: Vec<CustomMetadata> = serde_json::from_value(wire).unwrap();
+    assert_eq!(parsed[0].value.as_str(), Some("en"));
+    assert!(matches!(
+        &parsed[1].value,
+        CustomMetadataValue::StringListValue { string_list_value } if string_list_value.values == ["a", "b"]
+    ));
+    assert!(matches!(
+        parsed[2].value,
+        CustomMetadataValue::NumericValue { numeric_value } if numeric_value == 2024.0
+    ));
+}
+
+#[test]
+fn test_metadata_filter() {
+    use crate::{
+        InteractionTool, MetadataFilter, MetadataFilterError, MetadataKind, MetadataSchema, Tool,
+    };
+
+    let schema = MetadataSchema::from_metadata(&[
+        crate::CustomMetadata::string("author", "Graves"),
+        crate::CustomMetadata {
+            key: "year".into(),
+            value: crate::CustomMetadataValue::NumericValue {
+                numeric_value: 1934.0,
+            },
+        },
+    ])
+    .with_key("tags", MetadataKind::StringList);
+
+    let filter = MetadataFilter::any([
+        MetadataFilter::eq("author", "Graves").and(MetadataFilter::lt("year", 1940)),
+        MetadataFilter::has("tags", "myth").not(),
+    ]);
+    assert_eq!(
+        filter.to_string(),
+        r#"(author = "Graves" AND year < 1940) OR NOT tags:"myth""#
+    );
+    filter.validate(&schema).unwrap();
+
+    assert!(matches!(
+        MetadataFilter::eq("autor", "Graves").validate(&schema),
+        Err(MetadataFilterError::UnknownKey { .. })
+    ));
+    assert!(matches!(
+        MetadataFilter::eq("year", "1934").validate(&schema),
+        Err(MetadataFilterError::KindMismatch { .. })
+    ));
+    assert!(matches!(
+        MetadataFilter::has("author", "Graves").validate(&schema),
+        Err(MetadataFilterError::KindMismatch { .. })
+    ));
+    assert!(matches!(
+        MetadataFilter::eq("bad key", 1).check(),
+        Err(MetadataFilterError::InvalidKey { .. })
+    ));
+    assert!(matches!(
+        MetadataFilter::all([]).check(),
+        Err(MetadataFilterError::EmptyGroup)
+    ));
+    assert_eq!(
+        MetadataFilter::eq("title", "a \\ \"b\"")
+            .not()
+            .not()
+            .to_string(),
+        r#"NOT (NOT title = "a \\ \"b\"")"#
+    );
+
+    let stores = || vec!["fileSearchStores/s".to_string()];
+    let tool =
+        serde_json::to_value(Tool::file_search_with_filter(stores(), &filter).unwrap()).unwrap();
+    assert_eq!(tool["file_search"]["metadataFilter"], filter.to_string());
+    let tool =
+        serde_json::to_value(InteractionTool::file_search_with_filter(stores(), &filter).unwrap())
+            .unwrap();
+    assert_eq!(tool["metadata_filter"], filter.to_string());
+
+    let invalid = MetadataFilter::eq("bad key", 1);
+    assert!(matches!(
+        Tool::file_search_with_filter(stores(), &invalid),
+        Err(MetadataFilterError::InvalidKey { .. })
+    ));
+    assert!(matches!(
+        InteractionTool::file_search_with_filter(stores(), &MetadataFilter::any([])),
+        Err(MetadataFilterError::EmptyGroup)
+    ));
+}
+
+/// A canned HTTP response served by [`serve_json`].
+struct Reply {
+    status: u16,
+    headers: Vec<(&'static str, String)>,
+    body: serde_json::Value,
+}
+
+impl Reply {
+    fn status(status: u16, body: serde_json::Value) -> Self {
+        Self {
+            status,
+            headers: Vec::new(),
+            body,
+        }
+    }
+}
+
+impl Reply {
+    /// Adds a header; `{base}` in the value is replaced by the server's address.
+    fn with_header(mut self, name: &'static str, value: &str) -> Self {
+        self.headers.push((name, value.to_string()));
+        self
+    }
+}
+
+impl From<serde_json::Value> for Reply {
+    fn from(body: serde_json::Value) -> Self {
+        Self::status(200, body)
+    }
+}
+
+/// A request received by [`serve_json`]. Dereferences to its JSON body.
+#[derive(Debug, Clone, PartialEq)]
+struct Recorded {
+    /// The request line, e.g. `PATCH /v1beta/fileSearchStores/s?updateMask=displayName`,
+    /// without the HTTP version.
+    line: String,
+    body: serde_json::Value,
+}
+
+impl std::ops::Deref for Recorded {
+    type Target = serde_json::Value;
+
+    fn deref(&self) -> &serde_json::Value {
+        &self.body
+    }
+}
+
+/// Serves `replies` in turn to HTTP requests, repeating the last one, and records the
+/// requests with their JSON bodies. Returns the base URL.
+async fn serve_json(
+    replies: Vec<impl Into<Reply>>,
+) -> (url::Url, std::sync::Arc<std::sync::Mutex<Vec<Recorded>>>) {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    let replies: Vec<Reply> = replies.into_iter().map(Into::into).collect();
+    let requests = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+    let recorded = requests.clone();
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        for index in 0.. {
+            let Some(reply) = replies.get(index).or(replies.last()) else {
+                return;
+            };
+            let Ok((mut socket, _)) = listener.accept().await else {
+                return;
+            };
+            let mut request = Vec::new();
+            let mut buffer = [0; 8 * 1024];
+            loop {
+                let read = socket.read(&mut buffer).await.unwrap_or(0);
+                request.extend_from_slice(&buffer[..read]);
+                let text = String::from_utf8_lossy(&request);
+                if let Some(head_end) = text.find("\r\n\r\n") {
+                    let length = text[..head_end]
+                        .lines()
+                        .find_map(|line| {
+                            let (name, value) = line.split_once(':')?;
+                            name.eq_ignore_ascii_case("content-length")
+                                .then(|| value.trim().parse::<usize>().ok())?
+                        })
+                        .unwrap_or(0);
+                    if request.len() >= head_end + 4 + length || read == 0 {
+                        let line = text.lines().next().unwrap_or_default();
+                        let line = line.rsplit_once(' ').map_or(line, |(line, _)| line);
+                        let body = &request[head_end + 4..];
+                        recorded.lock().unwrap().push(Recorded {
+                            line: line.to_string(),
+                            body: serde_json::from_slice(body).unwrap_or_default(),
+                        });
+                        break;
+                    }
+                }
+                if read == 0 {
+                    break;
+                }
+            }
+            let body = reply.body.to_string();
+            let headers: String = reply
+                .headers
+                .iter()
+                .map(|(name, value)| {
+                    let value = value.replace("{base}", &format!("http://{address}"));
+                    format!("{name}: {value}\r\n")
+                })
+                .collect();
+            let response = format!(
+                "HTTP/1.1 {} Reply\r\ncontent-type: application/json\r\ncontent-length: {}\r\n{headers}connection: close\r\n\r\n{body}",
+                reply.status,
+                body.len()
+            );
+            let _ = socket.write_all(response.as_bytes()).await;
+        }
+    });
+    (
+        format!("http://{address}/v1beta/").parse().unwrap(),
+        requests,
+    )
+}
+
+#[tokio::test]
+async fn test_operation_set() {
+    use crate::file_search::Operation;
+    use crate::{ClientError, GeminiBuilder, OperationSet, PollPolicy};
+    use futures::StreamExt;
+    use std::time::Duration;
+
+    let (base_url, _) = serve_json(vec![json!({
+        "name": "fileSearchStores/s/documents/doc-1",
+        "createTime": "2025-01-01T00:00:00Z",
+        "updateTime": "2025-01-01T00:00:00Z",
+        "state": "STATE_ACTIVE",
+        "sizeBytes": "42",
+        "mimeType": "text/plain"
+    })])
+    .await;
+    let client = GeminiBuilder::new("_key")
+        .with_base_url(base_url)
+        .build()
+        .unwrap();
+    let operation = |value: serde_json::Value| {
+        client.resume_file_search_operation(serde_json::from_value::<Operation>(value).unwrap())
+    };
+    let operations: OperationSet = [
+        operation(json!({
+            "name": "fileSearchStores/s/operations/ok",
+            "done": true,
+            "response": {"documentName": "fileSearchStores/s/documents/doc-1"}
+        })),
+        operation(json!({
+            "name": "fileSearchStores/s/operations/failed",
+            "done": true,
+            "error": {"code": 3, "message": "unsupported file"}
+        })),
+        operation(
+            json!({"name": "fileSearchStores/s/operations/empty", "done": true, "response": {}}),
+        ),
+    ]
+    .into_iter()
+    .collect();
+
+    let policy = PollPolicy::fixed(Duration::from_millis(1));
+    let updates: Vec<_> = operations
+        .clone()
+        .with_poll_policy(policy)
+        .watch()
+        .collect()
+        .await;
+    assert_eq!(updates.len(), 1);
+    assert_eq!(updates[0].counts.succeeded, 1);
+    assert_eq!(updates[0].counts.failed, 2);
+    assert!(updates[0].counts.is_finished());
+
+    let outcomes = operations.wait().await;
+    assert_eq!(
+        outcomes.iter().map(|o| o.index).collect::<Vec<_>>(),
+        vec![0, 1, 2]
+    );
+    assert_eq!(
+        outcomes[0].result.as_ref().unwrap().name,
+        "fileSearchStores/s/documents/doc-1"
+    );
+    assert!(matches!(
+        outcomes[1].result,
+        Err(ClientError::OperationFailed { code: 3, .. })
+    ));
+    assert!(matches!(
+        outcomes[2].result,
+        Err(ClientError::OperationMissingDocument { .. })
+    ));
+}
+
+#[test]
+fn test_operation_responses() {
+    use crate::file_search::{Operation, OperationResponse, OperationResult};
+
+    let operation = |response: serde_json::Value| -> Operation {
+        serde_json::from_value(json!({
+            "name": "fileSearchStores/s/operations/op",
+            "metadata": {
+                "@type": "type.googleapis.com/google.ai.generativelanguage.v1main.UploadToFileSearchStoreMetadata",
+                "createTime": "2025-01-01T00:00:00Z",
+                "progressPercent": 100
+            },
+            "done": true,
+            "response": response
+        }))
+        .unwrap()
+    };
+    let response = |operation: Operation| match operation.result {
+        Some(OperationResult::Response { response }) => response,
+        other => panic!("unexpected result {other:?}"),
+    };
+
+    let upload = operation(json!({
+        "@type": "type.googleapis.com/google.ai.generativelanguage.v1main.UploadToFileSearchStoreResponse",
+        "parent": "fileSearchStores/s",
+        "documentName": "fileSearchStores/s/documents/d1",
+        "mimeType": "text/plain",
+        "sizeBytes": "42"
+    }));
+    let metadata = upload.metadata.clone().unwrap();
+    assert_eq!(metadata.progress_percent, Some(100.0));
+    assert!(metadata.create_time.is_some());
+    match response(upload) {
+        OperationResponse::Upload(upload) => {
+            assert_eq!(upload.size_bytes, Some(42));
+            assert_eq!(upload.mime_type, Some(mime::TEXT_PLAIN));
+        }
+        other => panic!("expected an upload response, got {other:?}"),
+    }
+
+    let import = response(operation(json!({
+        "@type": "type.googleapis.com/google.ai.generativelanguage.v1beta.ImportFileResponse",
+        "parent": "fileSearchStores/s",
+        "documentName": "fileSearchStores/s/documents/d2"
+    })));
+    assert!(matches!(import, OperationResponse::Import(_)));
+    assert_eq!(
+        import.document_name(),
+        Some("fileSearchStores/s/documents/d2")
+    );
+
+    let other = response(operation(json!({"unexpected": true})));
+    assert!(matches!(other, OperationResponse::Other(_)));
+    assert_eq!(other.document_name(), None);
+
+    // Fields of unexpected types fall back to the raw values instead of failing the operation.
+    let malformed = operation(json!({
+        "@type": "type.googleapis.com/google.ai.generativelanguage.v1main.UploadToFileSearchStoreResponse",
+        "documentName": "fileSearchStores/s/documents/d3",
+        "sizeBytes": "many"
+    }));
+    let malformed = response(malformed);
+    assert!(matches!(malformed, OperationResponse::Other(_)));
+    assert_eq!(
+        malformed.document_name(),
+        Some("fileSearchStores/s/documents/d3")
+    );
+    let operation: Operation = serde_json::from_value(json!({
+        "name": "fileSearchStores/s/operations/op",
+        "metadata": {"createTime": "yesterday", "progressPercent": "half"},
+        "done": false
+    }))
+    .unwrap();
+    let metadata = operation.metadata.unwrap();
+    assert_eq!(metadata.create_time, None);
+    assert_eq!(metadata.other["progressPercent"], "half");
+}
+
+#[test]
+fn test_store_management_helpers() {
+    use crate::file_search::sync::{content_hash, plan_changes};
+    use crate::{Document, MetadataFilter, StoreStats, SyncAction};
+    use std::path::PathBuf;
+
+    let document = |id: &str, state: &str, mime: &str, size: i64, metadata: serde_json::Value| {
+        serde_json::from_value::<Document>(json!({
+            "name": format!("fileSearchStores/s/documents/{id}"),
+            "customMetadata": metadata,
+            "createTime": "2025-01-01T00:00:00Z",
+            "updateTime": "2025-01-01T00:00:00Z",
+            "state": state,
+            "sizeBytes": size.to_string(),
+            "mimeType": mime
+        }))
+        .unwrap()
+    };
+    let hash = content_hash(b"a");
+    let documents = vec![
+        document(
+            "a",
+            "STATE_FAILED",
+            "text/markdown",
+            10,
+            json!([
+                {"key": "sync_root", "stringValue": "docs"},
+                {"key": "source_path", "stringValue": "a.md"},
+                {"key": "content_sha256", "stringValue": hash},
+                {"key": "year", "numericValue": 2021},
+                {"key": "tags", "stringListValue": {"values": ["draft"]}}
+            ]),
+        ),
+        document(
+            "b",
+            "STATE_ACTIVE",
+            "text/plain; charset=utf-8",
+            5,
+            json!([{"key": "year", "numericValue": 2024}]),
+        ),
+        document("c", "STATE_ACTIVE", "text/plain", 7, json!([])),
+    ];
+
+    let stats = StoreStats::from_documents(&documents);
+    assert_eq!(
+        (
+            stats.documents,
+            stats.active,
+            stats.failed,
+            stats.total_bytes
+        ),
+        (3, 2, 1, 22)
+    );
+    assert_eq!(stats.by_mime_type["text/plain"], 2);
+    assert_eq!(stats.by_mime_type["text/markdown"], 1);
+
+    let metadata = |d: &Document| d.custom_metadata.clone().unwrap_or_default();
+    let old_drafts = MetadataFilter::lt("year", 2022).or(MetadataFilter::has("tags", "draft"));
+    let matching: Vec<_> = documents
+        .iter()
+        .filter(|d| old_drafts.matches(&metadata(d)))
+        .map(|d| d.name.as_str())
+        .collect();
+    assert_eq!(matching, vec!["fileSearchStores/s/documents/a"]);
+    assert!(!MetadataFilter::ne("year", 2024).matches(&metadata(&documents[2])));
+    assert!(MetadataFilter::ne("year", 2021).matches(&metadata(&documents[1])));
+
+    // A failed document is re-uploaded by a sync even though its hash matches.
+    let changes = plan_changes("docs", &[(PathBuf::from("a.md"), hash)], &documents);
+    assert!(matches!(changes[0].action, SyncAction::Update { .. }));
+}
+
+#[tokio::test]
+async fn test_file_search_query() {
+    use crate::{GeminiBuilder, MetadataFilter};
+
+    let (base_url, requests) = serve_json(vec![
+        Reply::from(json!({
+            "name": "fileSearchStores/s",
+            "createTime": "2025-01-01T00:00:00Z",
+            "updateTime": "2025-01-01T00:00:00Z"
+        })),
+        Reply::from(json!({
+            "candidates": [{
+                "content": {"role": "model", "parts": [{"text": "Chunks overlap."}]},
+                "groundingMetadata": {
+                    "groundingChunks": [
+                        {"retrievedContext": {
+                            "title": "guide.md",
+                            "text": "Chunks overlap by 20 tokens.",
+                            "documentName": "fileSearchStores/s/documents/guide"
+                        }},
+                        {"retrievedContext": {
+                            "text": "Chunks are 200 tokens long.",
+                            "documentName": "fileSearchStores/s/documents/guide"
+                        }}
+                    ],
+                    "groundingSupports": [{
+                        "segment": {"startIndex": 0, "endIndex": 15},
+                        "groundingChunkIndices": [1]
+                    }]
+                }
+            }]
+        })),
+        Reply::from(json!({
+            "name": "fileSearchStores/s/documents/guide",
+            "customMetadata": [{"key": "lang", "stringValue": "en"}],
+            "createTime": "2025-01-01T00:00:00Z",
+            "updateTime": "2025-01-01T00:00:00Z",
+            "state": "STATE_ACTIVE",
+            "sizeBytes": "10",
+            "mimeType": "text/markdown"
+        })),
+    ])
+    .await;
+    let store = GeminiBuilder::new("_key")
+        .with_base_url(base_url)
+        .build()
+        .unwrap()
+        .get_file_search_store("fileSearchStores/s")
+        .await
+        .unwrap();
+
+    assert!(store
+        .query("overlap")
+        .with_metadata_filter(&MetadataFilter::all([]))
+        .is_err());
+    let chunks = store
+        .query("overlap")
+        .with_metadata_filter(&MetadataFilter::eq("lang", "en"))
+        .unwrap()
+        .execute()
+        .await
+        .unwrap();
+
+    let ranked: Vec<_> = chunks
+        .iter()
+        .map(|chunk| (chunk.rank, chunk.text.as_deref(), chunk.supports))
+        .collect();
+    assert_eq!(
+        ranked,
+        vec![
+            (0, Some("Chunks overlap by 20 tokens."), 0),
+            (1, Some("Chunks are 200 tokens long."), 1)
+        ]
+    );
+    assert_eq!(chunks[1].custom_metadata[0].value.as_str(), Some("en"));
+
+    // The metadata of a document is fetched once, and the query only uses file search.
+    let requests = requests.lock().unwrap().clone();
+    assert_eq!(requests.len(), 3);
+    assert_eq!(
+        requests[1]["tools"],
+        json!([{"file_search": {
+            "fileSearchStoreNames": ["fileSearchStores/s"],
+            "metadataFilter": "lang = \"en\""
+        }}])
+    );
+}
+
+#[tokio::test]
+async fn test_store_and_document_updates() {
+    use crate::{CustomMetadata, GeminiBuilder, MetadataPatch};
+    use futures::TryStreamExt;
+
+    let store = |display_name: &str| {
+        json!({
+            "name": "fileSearchStores/s",
+            "displayName": display_name,
+            "createTime": "2025-01-01T00:00:00Z",
+            "updateTime": "2025-01-01T00:00:00Z"
+        })
+    };
+    let document = |metadata: serde_json::Value| {
+        json!({
+            "name": "fileSearchStores/s/documents/a",
+            "customMetadata": metadata,
+            "createTime": "2025-01-01T00:00:00Z",
+            "updateTime": "2025-01-01T00:00:00Z",
+            "state": "STATE_ACTIVE",
+            "sizeBytes": "1",
+            "mimeType": "text/plain"
+        })
+    };
+    let (base_url, requests) = serve_json(vec![
+        Reply::from(store("old")),
+        Reply::from(store("new")),
+        Reply::from(json!({"documents": [document(json!([
+            {"key": "source_path", "stringValue": "a.md"},
+            {"key": "year", "numericValue": 2021}
+        ]))]})),
+        Reply::from(document(json!([
+            {"key": "source_path", "stringValue": "b.md"},
+            {"key": "lang", "stringValue": "en"}
+        ]))),
+    ])
+    .await;
+    let mut handle = GeminiBuilder::new("_key")
+        .with_base_url(base_url)
+        .build()
+        .unwrap()
+        .get_file_search_store("fileSearchStores/s")
+        .await
+        .unwrap();
+
+    handle.update_display_name("new").await.unwrap();
+    assert_eq!(handle.display_name(), Some("new"));
+
+    let documents: Vec<_> = handle
+        .documents()
+        .list(Some(20))
+        .try_collect()
+        .await
+        .unwrap();
+    let mut document = documents.into_iter().next().unwrap();
+    document
+        .update_metadata(
+            MetadataPatch::new()
+                .set(CustomMetadata::string("source_path", "b.md"))
+                .set(CustomMetadata::string("lang", "en"))
+                .remove("year"),
+        )
+        .await
+        .unwrap();
+    assert_eq!(
+        document.document().custom_metadata.as_ref().unwrap().len(),
+        2
+    );
+
+    // Only the changed fields are sent, named by the update mask.
+    let requests = requests.lock().unwrap().clone();
+    assert_eq!(
+        requests[1].line,
+        "PATCH /v1beta/fileSearchStores/s?updateMask=displayName"
+    );
+    assert_eq!(*requests[1], json!({"displayName": "new"}));
+    assert_eq!(
+        requests[3].line,
+        "PATCH /v1beta/fileSearchStores/s/documents/a?updateMask=customMetadata"
+    );
+    assert_eq!(
+        *requests[3],
+        json!({"customMetadata": [
+            {"key": "source_path", "stringValue": "b.md"},
+            {"key": "lang", "stringValue": "en"}
+        ]})
+    );
+}
+
+#[tokio::test]
+async fn test_document_partial_failures() {
+    use crate::{GeminiBuilder, MetadataFilter, PollPolicy};
+    use futures::TryStreamExt;
+    use std::time::Duration;
+
+    let store = json!({
+        "name": "fileSearchStores/s",
+        "createTime": "2025-01-01T00:00:00Z",
+        "updateTime": "2025-01-01T00:00:00Z"
+    });
+    let document = |id: &str| {
+        json!({
+            "name": format!("fileSearchStores/s/documents/{id}"),
+            "customMetadata": [{"key": "year", "numericValue": 2021}],
+            "createTime": "2025-01-01T00:00:00Z",
+            "updateTime": "2025-01-01T00:00:00Z",
+            "state": "STATE_FAILED",
+            "sizeBytes": "1",
+            "mimeType": "text/plain"
+        })
+    };
+    let unavailable = || Reply::status(500, json!({"error": {"message": "unavailable"}}));
+    let client = |base_url| {
+        GeminiBuilder::new("_key")
+            .with_base_url(base_url)
+            .build()
+            .unwrap()
+    };
+
+    // The documents deleted before a failure are reported with the error.
+    let (base_url, _) = serve_json(vec![
+        Reply::from(store.clone()),
+        Reply::from(json!({"documents": [document("a"), document("b")]})),
+        Reply::from(json!({})),
+        unavailable(),
+    ])
+    .await;
+    let handle = client(base_url)
+        .get_file_search_store("fileSearchStores/s")
+        .await
+        .unwrap();
+    let Err((deleted, _)) = handle
+        .delete_documents_where(&MetadataFilter::lt("year", 2022))
+        .await
+    else {
+        panic!("expected the second deletion to fail");
+    };
+    assert_eq!(deleted, vec!["fileSearchStores/s/documents/a"]);
+
+    // A retried document is only deleted once its replacement is processed, and the
+    // replacement is returned even if that deletion fails.
+    let (base_url, requests) = serve_json(vec![
+        Reply::from(store),
+        Reply::from(json!({"documents": [document("a")]})),
+        Reply::from(json!({})).with_header("x-goog-upload-url", "{base}/upload/1"),
+        Reply::from(json!({"name": "fileSearchStores/s/operations/1"})),
+        Reply::from(json!({
+            "name": "fileSearchStores/s/operations/1",
+            "done": true,
+            "response": {"documentName": "fileSearchStores/s/documents/a2"}
+        })),
+        unavailable(),
+    ])
+    .await;
+    let handle = client(base_url)
+        .get_file_search_store("fileSearchStores/s")
+        .await
+        .unwrap();
+    let failed: Vec<_> = handle.failed_documents().try_collect().await.unwrap();
+    let policy = PollPolicy::fixed(Duration::from_millis(1));
+    let Err((Some(operation), _)) = failed
+        .into_iter()
+        .next()
+        .unwrap()
+        .retry(b"a".to_vec(), &policy)
+        .await
+    else {
+        panic!("expected the deletion to fail after the upload");
+    };
+    assert!(operation.is_done());
+    assert_eq!(
+        operation.document_name(),
+        Some("fileSearchStores/s/documents/a2")
+    );
+    assert_eq!(requests.lock().unwrap().len(), 6);
+}
+
+#[test]
+fn test_citation_resolver() {
+    use crate::{
+        AnnotatedText, Annotation, CitationResolver, GroundingMetadata, InteractionContent,
+        OffsetUnit, SourceKind,
+    };
+    use serde_json::json;
+
+    // Offsets are in UTF-16 code units, as the API reports them for grounding.
+    let parts = ["Café ☕ opens at 8. ", "東京 has 🍣 sushi."];
+    let metadata: GroundingMetadata = serde_json::from_value(json!({
+        "groundingChunks": [
+            {"web": {"uri": "https://example.com/cafe", "title": "Café"}},
+            {"retrievedContext": {
+                "uri": "fileSearchStores/s/documents/menu",
+                "title": "Menu",
+                "text": "Sushi is served daily."
+            }}
+        ],
+        "groundingSupports": [
+            {
+                "segment": {"startIndex": 0, "endIndex": 18, "text": "Café ☕ opens at 8."},
+                "groundingChunkIndices": [0]
+            },
+            {
+                "segment": {"partIndex": 1, "startIndex": 7, "endIndex": 16, "text": "🍣 sushi."},
+                "groundingChunkIndices": [1, 0]
+            }
+        ]
+    }))
+    .unwrap();
+
+    let check = |annotated: &AnnotatedText| {
+        let texts: Vec<_> = annotated
+            .spans
+            .iter()
+            .map(|span| annotated.span_text(span))
+            .collect();
+        assert_eq!(texts, vec!["Café ☕ opens at 8.", "🍣 sushi."]);
+        assert_eq!(
+            annotated.span_range(&annotated.spans[1], OffsetUnit::Utf16CodeUnits),
+            26..35
+        );
+        assert_eq!(
+            annotated.render_markers(),
+            "Café ☕ opens at 8.[1] 東京 has 🍣 sushi.[1][2]"
+        );
+    };
+
+    let detected = CitationResolver::new().resolve_grounding(&parts, &metadata);
+    check(&detected);
+    assert_eq!(detected.sources[1].kind, SourceKind::Document);
+    assert_eq!(
+        detected.sources[1].document_name.as_deref(),
+        Some("fileSearchStores/s/documents/menu")
+    );
+    assert_eq!(
+        detected.render_with_references(),
+        "Café ☕ opens at 8.[1] 東京 has 🍣 sushi.[1][2]\n\nReferences:\n\
+         [1] Café (https://example.com/cafe)\n\
+         [2] Menu (fileSearchStores/s/documents/menu)\n"
+    );
+
+    // Without segment text to check against, the unit has to be given.
+    let mut bare = metadata.clone();
+    for support in bare.grounding_supports.iter_mut().flatten() {
+        support.segment.text = None;
+    }
+    check(
+        &CitationResolver::new()
+            .with_offset_unit(OffsetUnit::Utf16CodeUnits)
+            .resolve_grounding(&parts, &bare),
+    );
+    let misread = CitationResolver::new().resolve_grounding(&parts, &bare);
+    assert_eq!(misread.span_text(&misread.spans[0]), "Café ☕ opens at");
+
+    // Interaction annotations, with offsets falling inside a character clamped to it.
+    let content = vec![InteractionContent::Text {
+        text: "Grüße aus Köln.".into(),
+        annotations: vec![
+            Annotation::UrlCitation {
+                url: Some("https://example.com/koeln".into()),
+                title: Some("Köln".into()),
+                start_index: Some(12),
+                end_index: Some(18),
+            },
+            Annotation::UrlCitation {
+                url: Some("https://example.com/koeln".into()),
+                title: Some("Köln".into()),
+                start_index: Some(0),
+                end_index: Some(3),
+            },
+        ],
+    }];
+    let annotated = CitationResolver::new().resolve_annotations(&content);
+    assert_eq!(annotated.sources.len(), 1);
+    let texts: Vec<_> = annotated
+        .spans
+        .iter()
+        .map(|span| annotated.span_text(span))
+        .collect();
+    assert_eq!(texts, vec!["Gr", "Köln."]);
+    assert_eq!(annotated.render_markers(), "Gr[1]üße aus Köln.[1]");
+}
+
+/// Runs a fake MCP server answering on one end of an in-memory stream, with two pages of
+/// tools: `echo`, `fail` and `lookup`.
+fn fake_mcp_server() -> crate::StdioTransport {
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+    let (client, server) = tokio::io::duplex(64 * 1024);
+    let (client_read, client_write) = tokio::io::split(client);
+    tokio::spawn(async move {
+        let (server_read, mut server_write) = tokio::io::split(server);
+        let mut lines = BufReader::new(server_read).lines();
+        while let Ok(Some(line)) = lines.next_line().await {
+            let request: serde_json::Value = serde_json::from_str(&line).unwrap();
+            let Some(id) = request.get("id").cloned() else {
+                continue;
+            };
+            let params = &request["params"];
+            let result = match request["method"].as_str().unwrap() {
+                "initialize" => json!({
+                    "protocolVersion": "2025-06-18",
+                    "capabilities": {"tools": {}},
+                    "serverInfo": {"name": "fake", "version": "1.0"}
+                }),
+                "tools/list" if params.get("cursor").is_none() => json!({
+                    "tools": [
+                        {
+                            "name": "echo",
+                            "description": "Echo the text back",
+                            "inputSchema": {
+                                "$schema": "http://json-schema.org/draft-07/schema#",
+                                "type": "object",
+                                "properties": {"text": {"type": "string"}}
+                            }
+                        },
+                        {"name": "fail", "inputSchema": {"type": "object"}}
+                    ],
+                    "nextCursor": "2"
+                }),
+                "tools/list" => json!({
+                    "tools": [{"name": "lookup", "title": "Lookup", "inputSchema": {"type": "object"}}]
+                }),
+                "tools/call" => match params["name"].as_str().unwrap() {
+                    "echo" => json!({
+                        "content": [{"type": "text", "text": params["arguments"]["text"]}]
+                    }),
+                    "fail" => json!({
+                        "content": [{"type": "text", "text": "disk full"}],
+                        "isError": true
+                    }),
+                    _ => json!({
+                        "content": [
+                            {"type": "text", "text": "found"},
+                            {"type": "image", "data": "iVBORw0KGgo=", "mimeType": "image/png"}
+                        ]
+                    }),
+                },
+                method => {
+                    let error = json!({
+                        "jsonrpc": "2.0",
+                        "id": id,
+                        "error": {"code": -32601, "message": format!("unknown method {method}")}
+                    });
+                    let _ = server_write
+                        .write_all(format!("{error}\n").as_bytes())
+                        .await;
+                    continue;
+                }
+            };
+            // Interleave a notification, which the client has to skip.
+            let notification = json!({"jsonrpc": "2.0", "method": "notifications/message"});
+            let response = json!({"jsonrpc": "2.0", "id": id, "result": result});
+            let _ = server_write
+                .write_all(format!("{notification}\n{response}\n").as_bytes())
+                .await;
+        }
+    });
+    crate::StdioTransport::new(client_read, client_write)
+}
+
+#[tokio::test]
+async fn test_mcp_bridge() {
+    use crate::{
+        FunctionHandler, GeminiBuilder, InteractionTool, McpBridge, McpClient, McpError,
+        StepResult, StepResultContent,
+    };
+
+    let client = McpClient::connect(fake_mcp_server()).await.unwrap();
+    assert_eq!(client.server_info().server_info.name, "fake");
+    assert!(matches!(
+        client.call_tool("missing", json!({})).await,
+        Ok(result) if result.content.len() == 2
+    ));
+
+    let bridge = McpBridge::new().add_server("fake", client).await.unwrap();
+    let tools = FunctionHandler::tools(&bridge);
+    assert_eq!(
+        tools[0],
+        InteractionTool::function(
+            "echo",
+            "Echo the text back",
+            json!({"type": "object", "properties": {"text": {"type": "string"}}})
+        )
+    );
+    let names: Vec<_> = bridge.functions().map(|(name, ..)| name).collect();
+    assert_eq!(names, vec!["echo", "fail", "lookup"]);
+    let declarations = bridge.function_declarations();
+    assert_eq!(declarations[2].description, "Lookup");
+    assert!(bridge.handles("lookup") && !bridge.handles("get_weather"));
+
+    assert_eq!(
+        FunctionHandler::call(&bridge, "fail", json!({})).await,
+        Err("disk full".to_string())
+    );
+    let Ok(StepResult::ContentArray(items)) =
+        FunctionHandler::call(&bridge, "lookup", json!({})).await
+    else {
+        panic!("expected content");
+    };
+    assert!(matches!(
+        &items[1],
+        StepResultContent::Image {
+            mime_type: Some(_),
+            ..
+        }
+    ));
+
+    let duplicate = McpClient::connect(fake_mcp_server()).await.unwrap();
+    assert!(matches!(
+        bridge.clone().add_server("again", duplicate).await,
+        Err(McpError::DuplicateTool { name }) if name == "echo"
+    ));
+    let prefixed = McpClient::connect(fake_mcp_server()).await.unwrap();
+    let both = bridge
+        .clone()
+        .with_server_prefix()
+        .add_server("second", prefixed)
+        .await
+        .unwrap();
+    assert!(both.handles("second__echo"));
+
+    // The model calls `echo`, gets the result back, then answers.
+    let (base_url, requests) = serve_json(vec![
+        json!({
+            "id": "interaction-1",
+            "status": "requires_action",
+            "steps": [{"type": "function_call", "name": "echo", "arguments": {"text": "hi"}, "id": "call-1"}]
+        }),
+        json!({
+            "id": "interaction-2",
+            "status": "completed",
+            "steps": [{"type": "model_output", "content": [{"type": "text", "text": "It said hi."}]}]
+        }),
+    ])
+    .await;
+    let gemini = GeminiBuilder::new("_key")
+        .with_base_url(base_url)
+        .build()
+        .unwrap();
+    let interaction = gemini
+        .create_interaction()
+        .with_model("gemini-2.5-flash")
+        .with_text("Echo hi")
+        .with_function_handler(bridge)
+        .execute_with_functions()
+        .await
+        .unwrap();
+    assert_eq!(interaction.output_text(), "It said hi.");
+
+    let requests = requests.lock().unwrap();
+    assert_eq!(requests.len(), 2);
+    assert_eq!(requests[0]["tools"].as_array().unwrap().len(), 3);
+    assert_eq!(requests[1]["previous_interaction_id"], "interaction-1");
+    assert_eq!(
+        requests[1]["input"],
+        json!([{"type": "function_result", "name": "echo", "call_id": "call-1", "result": "hi"}])
+    );
+}
+
+#[tokio::test]
+async fn test_computer_use_driver() {
+    use crate::{
+        ActionExecutor, ComputerAction, ComputerUseDriver, ComputerUseEnvironment,
+        ComputerUseError, GeminiBuilder, Observation, Point, SafetyDecision, SafetyDecisionKind,
+        ScrollDirection, StopReason,
+    };
+
+    assert_eq!(
+        ComputerAction::from_call("key_combination", &json!({"keys": "Control+Shift+T"})).unwrap(),
+        ComputerAction::KeyCombination {
+            keys: vec!["Control".into(), "Shift".into(), "T".into()]
+        }
+    );
+    assert_eq!(
+        ComputerAction::from_call("scroll_at", &json!({"x": 10, "y": 20, "direction": "down"}))
+            .unwrap(),
+        ComputerAction::ScrollAt {
+            at: Point { x: 10, y: 20 },
+            direction: ScrollDirection::Down,
+            magnitude: 800
+        }
+    );
+    assert!(matches!(
+        ComputerAction::from_call("click_at", &json!({"x": 1})),
+        Err(ComputerUseError::InvalidArguments { .. })
+    ));
+    assert!(matches!(
+        ComputerAction::from_call("take_a_break", &json!({})),
+        Err(ComputerUseError::UnknownAction { .. })
+    ));
+    assert_eq!(Point { x: 500, y: 999 }.to_pixels(1440, 900), (720, 899));
+
+    #[derive(Default)]
+    struct FakeBrowser {
+        url: String,
+        actions: Vec<ComputerAction>,
+    }
+
+    #[async_trait::async_trait]
+    impl ActionExecutor for FakeBrowser {
+        async fn execute(&mut self, action: &ComputerAction) -> Result<(), String> {
+            self.actions.push(action.clone());
+            match action {
+                ComputerAction::Navigate { url } => self.url = url.clone(),
+                ComputerAction::HoverAt { .. } => return Err("no pointer".into()),
+                _ => {}
+            }
+            Ok(())
+        }
+
+        async fn observe(&mut self) -> Result<Observation, String> {
+            Ok(Observation::png("iVBORw0KGgo=").with_url(&self.url))
+        }
+
+        async fn confirm(&mut self, action: &ComputerAction, decision: &SafetyDecision) -> bool {
+            assert_eq!(decision.explanation, "Submits a search");
+            matches!(action, ComputerAction::TypeTextAt { .. })
+        }
+    }
+
+    let require_confirmation = json!({
+        "decision": "require_confirmation",
+        "explanation": "Submits a search"
+    });
+    let (base_url, requests) = serve_json(vec![
+        json!({
+            "id": "interaction-1",
+            "status": "requires_action",
+            "steps": [
+                {"type": "function_call", "name": "navigate", "arguments": {"url": "https://example.com"}, "id": "call-1"},
+                {"type": "function_call", "name": "drag_and_drop", "arguments": {"x": 1, "y": 2, "destination_x": 3, "destination_y": 4}, "id": "call-2"},
+                {"type": "function_call", "name": "hover_at", "arguments": {"x": 5, "y": 6}, "id": "call-3"}
+            ]
+        }),
+        json!({
+            "id": "interaction-2",
+            "status": "requires_action",
+            "steps": [{
+                "type": "function_call",
+                "name": "type_text_at",
+                "arguments": {"x": 500, "y": 100, "text": "rust", "safety_decision": require_confirmation},
+                "id": "call-4"
+            }]
+        }),
+        json!({
+            "id": "interaction-3",
+            "status": "requires_action",
+            "steps": [{
+                "type": "function_call",
+                "name": "click_at",
+                "arguments": {"x": 500, "y": 300, "safety_decision": require_confirmation},
+                "id": "call-5"
+            }]
+        }),
+    ])
+    .await;
+    let gemini = GeminiBuilder::new("_key")
+        .with_base_url(base_url)
+        .build()
+        .unwrap();
+
+    let mut driver =
+        ComputerUseDriver::new(FakeBrowser::default(), ComputerUseEnvironment::Browser)
+            .with_excluded_function("drag_and_drop");
+    let run = driver
+        .run(
+            gemini
+                .create_interaction()
+                .with_model("gemini-2.5-computer-use-preview-10-2025")
+                .with_text("Search for rust"),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(run.turns, 3);
+    assert_eq!(
+        run.stop,
+        StopReason::Declined {
+            action: ComputerAction::ClickAt {
+                at: Point { x: 500, y: 300 }
+            },
+            decision: serde_json::from_value(require_confirmation).unwrap(),
+        }
+    );
+    let executed: Vec<_> = run.actions.iter().map(|a| a.action.name()).collect();
+    assert_eq!(executed, vec!["navigate", "hover_at", "type_text_at"]);
+    assert_eq!(run.actions[1].error.as_deref(), Some("no pointer"));
+    assert_eq!(driver.executor().actions.len(), 3);
+
+    let requests = requests.lock().unwrap().clone();
+    assert_eq!(requests.len(), 3);
+    assert_eq!(
+        requests[0]["tools"],
+        json!([{"type": "computer_use", "environment": "browser", "excluded_predefined_functions": ["drag_and_drop"]}])
+    );
+    assert_eq!(
+        requests[0]["input"][0],
+        json!({"type": "text", "text": "Search for rust"})
+    );
+    assert_eq!(requests[0]["input"][1]["type"], "image");
+
+    let results = requests[1]["input"].as_array().unwrap();
+    assert_eq!(results.len(), 3);
+    assert_eq!(
+        results[0]["result"][0]["text"],
+        r#"{"url":"https://example.com"}"#
+    );
+    assert_eq!(results[0]["result"][1]["mime_type"], "image/png");
+    assert_eq!(
+        results[1]["result"],
+        "function drag_and_drop is not available"
+    );
+    assert_eq!(results[1]["is_error"], true);
+    assert_eq!(
+        results[2]["result"][0]["text"],
+        r#"{"error":"no pointer","url":"https://example.com"}"#
+    );
+    assert_eq!(
+        requests[2]["input"][0]["result"][0]["text"],
+        r#"{"safety_acknowledgement":"true","url":"https://example.com"}"#
+    );
+
+    // Decisions of an unknown kind or that cannot be read still ask for confirmation.
+    #[derive(Default)]
+    struct Confirming {
+        decisions: Vec<SafetyDecision>,
+    }
+
+    #[async_trait::async_trait]
+    impl ActionExecutor for Confirming {
+        async fn execute(&mut self, _action: &ComputerAction) -> Result<(), String> {
+            Ok(())
+        }
+
+        async fn observe(&mut self) -> Result<Observation, String> {
+            Ok(Observation::png("iVBORw0KGgo="))
+        }
+
+        async fn confirm(&mut self, _action: &ComputerAction, decision: &SafetyDecision) -> bool {
+            self.decisions.push(decision.clone());
+            true
+        }
+    }
+
+    let (base_url, requests) = serve_json(vec![
+        json!({
+            "id": "interaction-1",
+            "status": "requires_action",
+            "steps": [{
+                "type": "function_call",
+                "name": "click_at",
+                "arguments": {"x": 1, "y": 2, "safety_decision": {"decision": "block_unless_admin"}},
+                "id": "call-1"
+            }]
+        }),
+        json!({
+            "id": "interaction-2",
+            "status": "requires_action",
+            "steps": [{
+                "type": "function_call",
+                "name": "click_at",
+                "arguments": {"x": 3, "y": 4, "safety_decision": "require_confirmation"},
+                "id": "call-2"
+            }]
+        }),
+        json!({
+            "id": "interaction-3",
+            "status": "completed",
+            "steps": [{"type": "model_output", "content": [{"type": "text", "text": "Done."}]}]
+        }),
+    ])
+    .await;
+    let gemini = GeminiBuilder::new("_key")
+        .with_base_url(base_url)
+        .build()
+        .unwrap();
+    let mut driver = ComputerUseDriver::new(Confirming::default(), ComputerUseEnvironment::Browser)
+        .with_initial_observation(false);
+    let run = driver
+        .run(gemini.create_interaction().with_text("Click around"))
+        .await
+        .unwrap();
+    assert_eq!(run.stop, StopReason::Completed);
+    let decisions = &driver.executor().decisions;
+    assert_eq!(decisions.len(), 2);
+    assert!(decisions
+        .iter()
+        .all(|d| d.decision == SafetyDecisionKind::Unknown));
+    assert_eq!(
+        decisions[1].explanation,
+        r#"unreadable safety decision: "require_confirmation""#
+    );
+    let requests = requests.lock().unwrap();
+    for request in &requests[1..] {
+        assert_eq!(
+            request["input"][0]["result"][0]["text"],
+            r#"{"safety_acknowledgement":"true"}"#
+        );
+    }
+}
@@ -169,6 +169,20 @@ impl Tool {
             },
         }
     }
+
+    /// Create a new File Search tool restricted to documents matching a typed metadata filter
+    ///
+    /// Fails if the filter has an invalid key or an empty group.
+    pub fn file_search_with_filter(
+        store_names: Vec<String>,
+        metadata_filter: &crate::file_search::MetadataFilter,
+    ) -> Result<Self, crate::file_search::FilterError> {
+        metadata_filter.check()?;
+        Ok(Self::file_search(
+            store_names,
+            Some(metadata_filter.to_string()),
+        ))
+    }
 }
 
 /// Defines the function behavior